@@ -216,31 +216,23 @@ fn process_demo(
 
     let instructions_per_tx = 32;
     let num_cranks = dsl.len() / instruction::INSTRUCTION_SIZE;
-    let mut current = 0;
-    while current < num_cranks {
-        instructions.clear();
-        let iter_start = current;
-        for _ in 0..instructions_per_tx {
-            if current >= num_cranks {
-                break;
-            }
-            instructions.push(
-                instruction::crank_compute(
-                    instruction_buffer.pubkey(),
-                    input_buffer.pubkey(),
-                    compute_buffer.pubkey(),
-                ),
-            );
-            current += 1;
-        }
+    let cranks = instruction::crank_compute_instructions(
+        instruction_buffer.pubkey(),
+        input_buffer.pubkey(),
+        compute_buffer.pubkey(),
+        num_cranks,
+        1,
+    );
+    for (i, chunk) in cranks.chunks(instructions_per_tx).enumerate() {
+        let iter_start = i * instructions_per_tx;
         send(
             rpc_client,
             &format!(
                 "Iterations {}..{}",
                 iter_start,
-                current,
+                iter_start + chunk.len(),
             ),
-            instructions.as_slice(),
+            chunk,
             &[payer],
         )?;
     }
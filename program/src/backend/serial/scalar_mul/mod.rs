@@ -1 +1,2 @@
+pub mod pippenger;
 pub mod straus;
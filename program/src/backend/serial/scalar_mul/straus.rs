@@ -18,6 +18,7 @@ use core::borrow::Borrow;
 use crate::edwards::EdwardsPoint;
 use crate::scalar::Scalar;
 use crate::traits::MultiscalarMul;
+use crate::traits::VartimeMultiscalarMul;
 
 #[allow(unused_imports)]
 use std::prelude::*;
@@ -141,3 +142,86 @@ impl MultiscalarMul for Straus {
         Q
     }
 }
+
+impl VartimeMultiscalarMul for Straus {
+    type Point = EdwardsPoint;
+
+    /// Variable-time Straus using a non-adjacent form of width \\(5\\).
+    ///
+    /// This is completely similar to the constant-time code, but we
+    /// use a non-adjacent form for the scalar, and do not do table
+    /// lookups in constant time.
+    ///
+    /// The non-adjacent form has signed, odd digits.  Using only odd
+    /// digits halves the table size (since we only need odd
+    /// multiples), or gives fewer additions for the same table size.
+    fn optional_multiscalar_mul<I, J>(scalars: I, points: J) -> Option<EdwardsPoint>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Scalar>,
+        J: IntoIterator<Item = Option<EdwardsPoint>>,
+    {
+        use crate::edwards::{CompletedPoint, ProjectiveNielsPoint};
+        use crate::window::NafLookupTable5;
+        use crate::traits::Identity;
+
+        let nafs: Vec<_> = scalars
+            .into_iter()
+            .map(|c| c.borrow().non_adjacent_form(5))
+            .collect();
+
+        let lookup_tables = points
+            .into_iter()
+            .map(|P_opt| P_opt.map(|P| NafLookupTable5::<ProjectiveNielsPoint>::from(&P)))
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut r = EdwardsPoint::identity().to_projective();
+
+        for i in (0..256).rev() {
+            let mut t: CompletedPoint = r.double();
+
+            for (naf, lookup_table) in nafs.iter().zip(lookup_tables.iter()) {
+                if naf[i] > 0 {
+                    t = &t.to_extended() + &lookup_table.select(naf[i] as usize);
+                } else if naf[i] < 0 {
+                    t = &t.to_extended() - &lookup_table.select(-naf[i] as usize);
+                }
+            }
+
+            r = t.to_projective();
+        }
+
+        Some(r.to_extended())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::serial::u64::constants;
+
+    // projective equality, X1/Z1 == X2/Z2 and Y1/Z1 == Y2/Z2
+    fn points_equal(P: &EdwardsPoint, Q: &EdwardsPoint) -> bool {
+        &P.X * &Q.Z == &Q.X * &P.Z && &P.Y * &Q.Z == &Q.Y * &P.Z
+    }
+
+    #[test]
+    fn vartime_matches_constant_time() {
+        let mut scalars: Vec<_> = (0..29u64)
+            .map(|i| Scalar::hash_from_bytes::<sha2::Sha512>(&i.to_le_bytes()))
+            .collect();
+        scalars.extend_from_slice(&[Scalar::zero(), Scalar::one(), -Scalar::one()]);
+        let points: Vec<_> = (0..scalars.len())
+            .map(|i| &Scalar::from(7919 * i as u64 + 3) * &constants::ED25519_BASEPOINT_TABLE)
+            .collect();
+
+        for n in [1, 2, 7, scalars.len()].iter() {
+            let expected = Straus::multiscalar_mul(&scalars[..*n], &points[..*n]);
+            let result = Straus::optional_multiscalar_mul(
+                &scalars[..*n],
+                points[..*n].iter().map(|P| Some(*P)),
+            ).unwrap();
+            assert!(points_equal(&result, &expected));
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Helpers for programs that gate on a finished compute buffer. Depend on this
+//! crate with the `cpi` feature to pull these in without the entrypoint.
+
+use crate::{
+    error::Curve25519Error,
+    instruction::*,
+};
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Checks that `compute_buffer_info` is a `ComputeBufferV1` owned by this
+/// program, was set up against the expected instruction and input buffers, ran
+/// every instruction in its instruction buffer, and ended with an accepted
+/// verdict.
+///
+/// Only the pubkeys of the instruction and input buffers are compared. That
+/// is enough because neither can change under a compute buffer: the
+/// instruction buffer is finalized before the first crank, inputs are only
+/// read from a finalized input buffer, finalized buffers can't be written,
+/// and `CloseBuffer` refuses buffers that a compute buffer still points to.
+/// Callers still need to check that the instruction and input buffers hold
+/// the DSL and inputs they expect. Table buffers are not covered, so only
+/// trust the generators in table buffers whose authority you trust.
+pub fn verify_completed_compute_buffer(
+    compute_buffer_info: &AccountInfo,
+    expected_instruction_buffer: &Pubkey,
+    expected_input_buffer: &Pubkey,
+) -> Result<ComputeHeader, ProgramError> {
+    if *compute_buffer_info.owner != crate::ID {
+        msg!("Bad compute buffer owner {}", compute_buffer_info.owner);
+        return Err(Curve25519Error::InvalidBufferOwner.into());
+    }
+
+    let compute_buffer_data = compute_buffer_info.try_borrow_data()?;
+    let compute_header = {
+        let mut compute_buffer_ptr: &[u8] = *compute_buffer_data;
+        ComputeHeader::deserialize(&mut compute_buffer_ptr)?
+    };
+
+    if compute_header.key != Key::ComputeBufferV1 {
+        msg!("Invalid compute buffer type");
+        return Err(Curve25519Error::InvalidBufferType.into());
+    }
+    if compute_header.instruction_buffer != *expected_instruction_buffer {
+        msg!("Mismatched instruction buffer");
+        return Err(Curve25519Error::MismatchedBuffer.into());
+    }
+    if compute_header.input_buffer != *expected_input_buffer {
+        msg!("Mismatched input buffer");
+        return Err(Curve25519Error::MismatchedBuffer.into());
+    }
+    if !compute_header.completed {
+        msg!("Compute buffer not completed");
+        return Err(Curve25519Error::ComputeBufferNotCompleted.into());
+    }
+    if compute_header.verdict != Verdict::Accepted {
+        msg!("Compute buffer verdict {:?}", compute_header.verdict);
+        return Err(Curve25519Error::VerdictRejected.into());
+    }
+
+    Ok(compute_header)
+}
@@ -1,6 +1,6 @@
 #![allow(non_snake_case)]
 
-use core::ops::{Add, Neg};
+use core::ops::{Add, AddAssign, Mul, Neg, Sub};
 use core::borrow::Borrow;
 use core::fmt::Debug;
 
@@ -13,8 +13,12 @@ use crate::backend::serial::scalar_mul;
 use crate::backend::serial::u64::constants;
 use crate::field::FieldElement;
 use crate::scalar::Scalar;
+use crate::traits::BasepointTable;
 use crate::traits::Identity;
 use crate::traits::MultiscalarMul;
+use crate::traits::VartimeMultiscalarMul;
+use crate::traits::ValidityCheck;
+use crate::window::LookupTable;
 
 // ------------------------------------------------------------------------
 // Internal point representations
@@ -44,6 +48,19 @@ pub struct ProjectiveNielsPoint {
     pub T2d:       FieldElement,
 }
 
+/// A pre-computed point in the affine model for the curve, represented as
+/// \\((y+x, y-x, 2dxy)\\) in "Niels coordinates".
+///
+/// More details on the relationships between the different curve models
+/// can be found in the module-level documentation.
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct AffineNielsPoint {
+    pub y_plus_x:  FieldElement,
+    pub y_minus_x: FieldElement,
+    pub xy2d:      FieldElement,
+}
+
 /// A `ProjectivePoint` is a point \\((X:Y:Z)\\) on the \\(\mathbb
 /// P\^2\\) model of the curve.
 /// A point \\((x,y)\\) in the affine model corresponds to
@@ -101,6 +118,22 @@ impl Identity for ProjectiveNielsPoint {
     }
 }
 
+impl Identity for AffineNielsPoint {
+    fn identity() -> AffineNielsPoint {
+        AffineNielsPoint{
+            y_plus_x:  FieldElement::one(),
+            y_minus_x: FieldElement::one(),
+            xy2d:      FieldElement::zero(),
+        }
+    }
+}
+
+impl Default for AffineNielsPoint {
+    fn default() -> AffineNielsPoint {
+        AffineNielsPoint::identity()
+    }
+}
+
 impl Zeroize for ProjectiveNielsPoint {
     fn zeroize(&mut self) {
         self.Y_plus_X.zeroize();
@@ -131,6 +164,19 @@ impl EdwardsPoint {
         }
     }
 
+    /// Convert to an AffineNielsPoint. Costs an inversion
+    pub(crate) fn to_affine_niels(&self) -> AffineNielsPoint {
+        let recip = self.Z.invert();
+        let x = &self.X * &recip;
+        let y = &self.Y * &recip;
+        let xy2d = &(&x * &y) * &constants::EDWARDS_D2;
+        AffineNielsPoint{
+            y_plus_x:  &y + &x,
+            y_minus_x: &y - &x,
+            xy2d,
+        }
+    }
+
     /// Convert the representation of this point from extended
     /// coordinates to projective coordinates.
     ///
@@ -254,6 +300,22 @@ impl ConditionallySelectable for ProjectiveNielsPoint {
     }
 }
 
+impl ConditionallySelectable for AffineNielsPoint {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        AffineNielsPoint {
+            y_plus_x: FieldElement::conditional_select(&a.y_plus_x, &b.y_plus_x, choice),
+            y_minus_x: FieldElement::conditional_select(&a.y_minus_x, &b.y_minus_x, choice),
+            xy2d: FieldElement::conditional_select(&a.xy2d, &b.xy2d, choice),
+        }
+    }
+
+    fn conditional_assign(&mut self, other: &Self, choice: Choice) {
+        self.y_plus_x.conditional_assign(&other.y_plus_x, choice);
+        self.y_minus_x.conditional_assign(&other.y_minus_x, choice);
+        self.xy2d.conditional_assign(&other.xy2d, choice);
+    }
+}
+
 // ------------------------------------------------------------------------
 // Doubling
 // ------------------------------------------------------------------------
@@ -278,6 +340,13 @@ impl ProjectivePoint {
     }
 }
 
+impl EdwardsPoint {
+    /// Add this point to itself.
+    pub(crate) fn double(&self) -> EdwardsPoint {
+        self.to_projective().double().to_extended()
+    }
+}
+
 impl<'a, 'b> Add<&'b ProjectiveNielsPoint> for &'a EdwardsPoint {
     type Output = CompletedPoint;
 
@@ -299,6 +368,76 @@ impl<'a, 'b> Add<&'b ProjectiveNielsPoint> for &'a EdwardsPoint {
     }
 }
 
+impl<'a, 'b> Sub<&'b ProjectiveNielsPoint> for &'a EdwardsPoint {
+    type Output = CompletedPoint;
+
+    fn sub(self, other: &'b ProjectiveNielsPoint) -> CompletedPoint {
+        let Y_plus_X  = &self.Y + &self.X;
+        let Y_minus_X = &self.Y - &self.X;
+        let PM = &Y_plus_X * &other.Y_minus_X;
+        let MP = &Y_minus_X  * &other.Y_plus_X;
+        let TT2d = &self.T * &other.T2d;
+        let ZZ   = &self.Z * &other.Z;
+        let ZZ2  = &ZZ + &ZZ;
+
+        CompletedPoint{
+            X: &PM - &MP,
+            Y: &PM + &MP,
+            Z: &ZZ2 - &TT2d,
+            T: &ZZ2 + &TT2d
+        }
+    }
+}
+
+impl<'a, 'b> Add<&'b EdwardsPoint> for &'a EdwardsPoint {
+    type Output = EdwardsPoint;
+    fn add(self, other: &'b EdwardsPoint) -> EdwardsPoint {
+        (self + &other.to_projective_niels()).to_extended()
+    }
+}
+
+define_add_variants!(LHS = EdwardsPoint, RHS = EdwardsPoint, Output = EdwardsPoint);
+
+impl<'b> AddAssign<&'b EdwardsPoint> for EdwardsPoint {
+    fn add_assign(&mut self, _rhs: &'b EdwardsPoint) {
+        *self = (self as &EdwardsPoint) + _rhs;
+    }
+}
+
+define_add_assign_variants!(LHS = EdwardsPoint, RHS = EdwardsPoint);
+
+impl<'a, 'b> Add<&'b AffineNielsPoint> for &'a EdwardsPoint {
+    type Output = CompletedPoint;
+
+    fn add(self, other: &'b AffineNielsPoint) -> CompletedPoint {
+        let Y_plus_X  = &self.Y + &self.X;
+        let Y_minus_X = &self.Y - &self.X;
+        let PP        = &Y_plus_X  * &other.y_plus_x;
+        let MM        = &Y_minus_X * &other.y_minus_x;
+        let Txy2d     = &self.T * &other.xy2d;
+        let Z2        = &self.Z + &self.Z;
+
+        CompletedPoint{
+            X: &PP - &MM,
+            Y: &PP + &MM,
+            Z: &Z2 + &Txy2d,
+            T: &Z2 - &Txy2d
+        }
+    }
+}
+
+impl<'a> Neg for &'a AffineNielsPoint {
+    type Output = AffineNielsPoint;
+
+    fn neg(self) -> AffineNielsPoint {
+        AffineNielsPoint{
+            y_plus_x:   self.y_minus_x,
+            y_minus_x:  self.y_plus_x,
+            xy2d:       -(&self.xy2d)
+        }
+    }
+}
+
 // used for ConditionallyNegatable?
 impl<'a> Neg for &'a ProjectiveNielsPoint {
     type Output = ProjectiveNielsPoint;
@@ -364,6 +503,166 @@ impl MultiscalarMul for EdwardsPoint {
     }
 }
 
+impl VartimeMultiscalarMul for EdwardsPoint {
+    type Point = EdwardsPoint;
+
+    fn optional_multiscalar_mul<I, J>(scalars: I, points: J) -> Option<EdwardsPoint>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Scalar>,
+        J: IntoIterator<Item = Option<EdwardsPoint>>,
+    {
+        // Sanity-check lengths of input iterators
+        let mut scalars = scalars.into_iter();
+        let mut points = points.into_iter();
+
+        // Lower and upper bounds on iterators
+        let (s_lo, s_hi) = scalars.by_ref().size_hint();
+        let (p_lo, p_hi) = points.by_ref().size_hint();
+
+        // They should all be equal
+        assert_eq!(s_lo, p_lo);
+        assert_eq!(s_hi, Some(s_lo));
+        assert_eq!(p_hi, Some(p_lo));
+
+        // Now we know there's a single size.
+        // Use this as the hint to decide which algorithm to use.
+        let size = s_lo;
+
+        if size < 190 {
+            scalar_mul::straus::Straus::optional_multiscalar_mul(scalars, points)
+        } else {
+            scalar_mul::pippenger::Pippenger::optional_multiscalar_mul(scalars, points)
+        }
+    }
+}
+
+// ------------------------------------------------------------------------
+// Fixed-base scalar multiplication
+// ------------------------------------------------------------------------
+
+/// A precomputed table of multiples of a basepoint, for accelerating
+/// fixed-base scalar multiplication. One table, for the Ed25519 basepoint,
+/// is provided in the `constants` module.
+///
+/// For each \\(i = 0 \ldots 31\\) the table holds
+/// \\([16\^{2i} B, \ldots, 8\cdot16\^{2i} B]\\) so that a scalar
+/// multiplication takes 64 additions and no table construction.
+#[derive(Clone)]
+pub struct EdwardsBasepointTable(pub(crate) [LookupTable<AffineNielsPoint>; 32]);
+
+impl EdwardsBasepointTable {
+    /// Number of additions in a full `basepoint_mul`.
+    pub const MUL_STEPS: u8 = 64;
+
+    /// Run additions `start..end` of `basepoint_mul` with the radix-16 digits
+    /// `a` and accumulator `P`, which starts as the identity. Steps 0..32
+    /// add the odd digits. Step 32 multiplies the accumulator by 16 before
+    /// its addition, and steps 32..64 add the even digits.
+    pub fn mul_steps(
+        &self,
+        a: &[i8; 64],
+        P: &EdwardsPoint,
+        start: u8,
+        end: u8,
+    ) -> EdwardsPoint {
+        let mut P = *P;
+        for step in start..end {
+            let i = if step < 32 {
+                2 * step as usize + 1
+            } else {
+                if step == 32 {
+                    P = P.mul_by_pow_2(4);
+                }
+                2 * (step as usize - 32)
+            };
+            P = (&P + &self.0[i/2].select(a[i])).to_extended();
+        }
+        P
+    }
+}
+
+impl BasepointTable for EdwardsBasepointTable {
+    type Point = EdwardsPoint;
+
+    /// Create a table of precomputed multiples of `basepoint`.
+    fn create(basepoint: &EdwardsPoint) -> EdwardsBasepointTable {
+        let mut table = EdwardsBasepointTable([LookupTable::default(); 32]);
+        let mut P = *basepoint;
+        for i in 0..32 {
+            // P = (16^2)^i * B
+            table.0[i] = LookupTable::from(&P);
+            P = P.mul_by_pow_2(8);
+        }
+        table
+    }
+
+    /// Get the basepoint for this table as an `EdwardsPoint`.
+    fn basepoint(&self) -> EdwardsPoint {
+        // self.0[0].select(1) = 1*(16^2)^0*B
+        // but as an `AffineNielsPoint`, so add identity to convert to extended.
+        (&EdwardsPoint::identity() + &self.0[0].select(1)).to_extended()
+    }
+
+    /// Pippenger's algorithm for radix-16 as described on page 13 of the
+    /// Ed25519 paper. Writing \\(a = a\_0 + a\_1 16\^1 + \cdots + a\_{63} 16\^{63}\\)
+    /// with \\(-8 \leq a\_i < 8\\), the odd and even coefficients are
+    /// grouped so that
+    /// \\(a B = \sum a\_{2i} 16\^{2i} B + 16 \sum a\_{2i+1} 16\^{2i} B\\).
+    fn basepoint_mul(&self, scalar: &Scalar) -> EdwardsPoint {
+        let a = scalar.to_radix_16();
+        self.mul_steps(&a, &EdwardsPoint::identity(), 0, Self::MUL_STEPS)
+    }
+}
+
+impl<'a, 'b> Mul<&'b Scalar> for &'a EdwardsBasepointTable {
+    type Output = EdwardsPoint;
+
+    /// Construct an `EdwardsPoint` from a `Scalar` \\(a\\) by
+    /// computing the multiple \\(aB\\) of this basepoint \\(B\\).
+    fn mul(self, scalar: &'b Scalar) -> EdwardsPoint {
+        self.basepoint_mul(scalar)
+    }
+}
+
+impl<'a, 'b> Mul<&'a EdwardsBasepointTable> for &'b Scalar {
+    type Output = EdwardsPoint;
+
+    /// Construct an `EdwardsPoint` from a `Scalar` \\(a\\) by
+    /// computing the multiple \\(aB\\) of this basepoint \\(B\\).
+    fn mul(self, basepoint_table: &'a EdwardsBasepointTable) -> EdwardsPoint {
+        basepoint_table * self
+    }
+}
+
+// ------------------------------------------------------------------------
+// Validity checks (not CT)
+// ------------------------------------------------------------------------
+
+impl ValidityCheck for ProjectivePoint {
+    fn is_valid(&self) -> bool {
+        // Curve equation is    -x^2 + y^2 = 1 + d*x^2*y^2,
+        // homogenized as (-X^2 + Y^2)*Z^2 = Z^4 + d*X^2*Y^2
+        let XX = self.X.square();
+        let YY = self.Y.square();
+        let ZZ = self.Z.square();
+        let ZZZZ = ZZ.square();
+        let lhs = &(&YY - &XX) * &ZZ;
+        let rhs = &ZZZZ + &(&constants::EDWARDS_D * &(&XX * &YY));
+
+        lhs == rhs
+    }
+}
+
+impl ValidityCheck for EdwardsPoint {
+    fn is_valid(&self) -> bool {
+        let point_on_curve = self.to_projective().is_valid();
+        let on_segre_image = (&self.X * &self.Y) == (&self.Z * &self.T);
+
+        point_on_curve && on_segre_image
+    }
+}
+
 // ------------------------------------------------------------------------
 // Debug traits
 // ------------------------------------------------------------------------
@@ -375,6 +674,13 @@ impl Debug for EdwardsPoint {
     }
 }
 
+impl Debug for AffineNielsPoint {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "AffineNielsPoint{{\n\ty_plus_x: {:?},\n\ty_minus_x: {:?},\n\txy2d: {:?}\n}}",
+               &self.y_plus_x, &self.y_minus_x, &self.xy2d)
+    }
+}
+
 impl Debug for ProjectiveNielsPoint {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         write!(f, "ProjectiveNielsPoint{{\n\tY_plus_X: {:?},\n\tY_minus_X: {:?},\n\tZ: {:?},\n\tT2d: {:?}\n}}",
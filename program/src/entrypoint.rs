@@ -2,10 +2,12 @@
 
 #![cfg(all(target_arch = "bpf", not(feature = "no-entrypoint")))]
 
+use crate::error::Curve25519Error;
 use solana_program::{
     account_info::AccountInfo,
     entrypoint,
     entrypoint::ProgramResult,
+    program_error::PrintProgramError,
     pubkey::Pubkey,
 };
 
@@ -15,5 +17,10 @@ fn process_instruction(
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    crate::processor::process_instruction(program_id, accounts, instruction_data)
+    if let Err(error) = crate::processor::process_instruction(program_id, accounts, instruction_data) {
+        // catch the error so we can print it
+        error.print::<Curve25519Error>();
+        return Err(error);
+    }
+    Ok(())
 }
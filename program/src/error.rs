@@ -0,0 +1,126 @@
+//! Error types
+
+use {
+    num_derive::FromPrimitive,
+    solana_program::{
+        decode_error::DecodeError,
+        msg,
+        program_error::{PrintProgramError, ProgramError},
+    },
+    thiserror::Error,
+};
+
+/// Errors returned by the program as `ProgramError::Custom(error as u32)`.
+/// Codes are stable so new variants only go at the end.
+#[derive(Clone, Copy, Debug, Eq, Error, FromPrimitive, PartialEq)]
+pub enum Curve25519Error {
+    // buffer setup and ownership
+    #[error("Authority is not a signer")]
+    AuthorityNotSigner = 0,
+    #[error("Invalid buffer authority")]
+    InvalidAuthority = 1,
+    #[error("Buffer is not owned by this program")]
+    InvalidBufferOwner = 2,
+    #[error("Invalid buffer type")]
+    InvalidBufferType = 3,
+    #[error("Buffer not initialized")]
+    BufferNotInitialized = 4,
+    #[error("Buffer already initialized")]
+    BufferAlreadyInitialized = 5,
+    #[error("Buffer is not rent exempt")]
+    NotRentExempt = 6,
+    #[error("Buffer not finalized")]
+    BufferNotFinalized = 7,
+    #[error("Buffer already finalized")]
+    BufferFinalized = 8,
+    #[error("Buffer does not match the compute or instruction buffer")]
+    MismatchedBuffer = 9,
+
+    // cranking
+    #[error("Compute buffer already completed")]
+    ComputeBufferCompleted = 10,
+    #[error("Compute buffer is at a different instruction")]
+    MismatchedInstructionNum = 11,
+    #[error("Invalid step count")]
+    InvalidStepCount = 12,
+
+    // malformed DSL instructions
+    #[error("Offset is inside a buffer header")]
+    HeaderOffset = 13,
+    #[error("Invalid step")]
+    InvalidStep = 14,
+    #[error("Too many points")]
+    TooManyPoints = 15,
+    #[error("Invalid length")]
+    InvalidLength = 16,
+    #[error("Invalid radix")]
+    InvalidRadix = 17,
+    #[error("Invalid digit")]
+    InvalidDigit = 18,
+
+    // invalid inputs. These reject soft_fail compute buffers instead of
+    // failing the crank
+    #[error("Invalid point")]
+    InvalidPoint = 19,
+    #[error("Bad witness")]
+    BadWitness = 20,
+    #[error("Non-canonical scalar")]
+    NonCanonicalScalar = 21,
+
+    #[error("Invalid transcript state")]
+    InvalidTranscript = 22,
+    #[error("Cannot write bytes to table buffer")]
+    TableBufferWrite = 23,
+    #[error("Invalid sha512 state")]
+    InvalidSha512State = 24,
+
+    // cpi::verify_completed_compute_buffer
+    #[error("Compute buffer not completed")]
+    ComputeBufferNotCompleted = 25,
+    #[error("Compute buffer verdict is not accepted")]
+    VerdictRejected = 26,
+
+    // an invalid input like those above
+    #[error("Cannot invert a zero scalar")]
+    ZeroScalar = 27,
+
+    #[error("Tables are not aligned to or within the built tables")]
+    InvalidTableOffset = 28,
+    #[error("Buffer is still used by a compute buffer")]
+    BufferInUse = 29,
+}
+
+impl Curve25519Error {
+    /// Whether the error comes from the inputs (a point that doesn't decode, a
+    /// bad witness, ...) rather than from the instruction or accounts
+    pub fn is_invalid_input(&self) -> bool {
+        matches!(
+            self,
+            Curve25519Error::InvalidPoint
+            | Curve25519Error::BadWitness
+            | Curve25519Error::NonCanonicalScalar
+            | Curve25519Error::ZeroScalar
+        )
+    }
+}
+
+impl From<Curve25519Error> for ProgramError {
+    fn from(e: Curve25519Error) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for Curve25519Error {
+    fn type_of() -> &'static str {
+        "Curve25519Error"
+    }
+}
+
+impl PrintProgramError for Curve25519Error {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + num_traits::FromPrimitive,
+    {
+        msg!(&self.to_string());
+    }
+}
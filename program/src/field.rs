@@ -55,6 +55,24 @@ impl FieldElement {
         bytes.ct_eq(&zero)
     }
 
+    /// Reduce 48 big-endian bytes mod p, as in the RFC 9380 `hash_to_field`
+    /// for edwards25519.
+    pub fn from_uniform_bytes_be(bytes: &[u8; 48]) -> FieldElement {
+        let mut lo = [0u8; 32];
+        let mut hi = [0u8; 32];
+        for i in 0..24 {
+            lo[i] = bytes[47 - i];
+            hi[i] = bytes[23 - i];
+        }
+
+        // 2^192
+        let mut shift = [0u8; 32];
+        shift[24] = 1;
+
+        &FieldElement::from_bytes(&lo)
+            + &(&FieldElement::from_bytes(&hi) * &FieldElement::from_bytes(&shift))
+    }
+
     /// Compute (x^(2^5-1), x^11)
     #[inline(never)]
     pub fn pow251(
@@ -108,6 +126,14 @@ impl FieldElement {
         t19
     }
 
+    /// Compute (self)^(p-2) = 1/self. Returns zero for zero.
+    pub fn invert(&self) -> FieldElement {
+        let (t17, t13, t3) = FieldElement::pow22001(self);
+        let t19 = FieldElement::pow22501(&t17, &t13);
+
+        &t19.pow2k(5) * &t3
+    }
+
     /// Raise this field element to the power (p-5)/8 = 2^252 -3.
     pub fn pow_p58(
         x: &FieldElement,
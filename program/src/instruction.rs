@@ -11,8 +11,9 @@ use {
 #[cfg(not(target_arch = "bpf"))]
 use {
     crate::{
-        window::LookupTable,
-        edwards::ProjectiveNielsPoint,
+        window::{LookupTable, LookupTableRadix32, LookupTableRadix64, NafLookupTable5},
+        edwards::{AffineNielsPoint, EdwardsBasepointTable, EdwardsPoint, ProjectiveNielsPoint},
+        field::FieldElement,
     },
     num_traits::ToPrimitive,
     solana_program::{
@@ -31,6 +32,9 @@ pub enum Curve25519Instruction {
     CrankCompute,
     CloseBuffer,
     Noop,
+    VerifyComputeBuffer,
+    InitializeTableBuffer,
+    BuildTables,
 }
 
 // TODO: move to state
@@ -41,6 +45,16 @@ pub enum Key {
     InputBufferV1,
     ComputeBufferV1,
     InstructionBufferV1,
+    TableBufferV1,
+}
+
+// Set by the Assert* DSL instructions. Once rejected, stays rejected
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum Verdict {
+    Pending,
+    Accepted,
+    Rejected,
 }
 
 // All headers should be smaller than HEADER_SIZE
@@ -53,6 +67,15 @@ pub struct ComputeHeader {
     pub authority: Pubkey,
     pub instruction_buffer: Pubkey,
     pub input_buffer: Pubkey,
+    pub verdict: Verdict,
+    // set once the last instruction in the instruction buffer has run
+    pub completed: bool,
+    // set at initialization. DSL instructions that fail on invalid inputs
+    // reject and complete the compute buffer instead of failing the crank
+    pub soft_fail: bool,
+    // the first instruction that rejected the compute buffer, either a failed
+    // assert or an invalid input to a soft_fail compute buffer
+    pub rejected_instruction: Option<u32>,
 }
 #[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
 #[repr(C)]
@@ -60,6 +83,9 @@ pub struct InputHeader {
     pub key: Key,
     pub authority: Pubkey,
     pub finalized: bool,
+    // compute buffers pointing at this buffer. It can't be closed until they
+    // are
+    pub num_compute_buffers: u32,
 }
 #[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
 #[repr(C)]
@@ -67,6 +93,26 @@ pub struct InstructionHeader {
     pub key: Key,
     pub authority: Pubkey,
     pub finalized: bool,
+    // read by MultiscalarMulTableBuffer. Pubkey::default() if unused
+    pub table_buffer: Pubkey,
+    // DSL instructions written so far, counting a partial one. Fixed once
+    // finalized so padding past the last write never runs
+    pub num_instructions: u32,
+    // compute buffers pointing at this buffer. It can't be closed until they
+    // are
+    pub num_compute_buffers: u32,
+}
+// LookupTable<ProjectiveNielsPoint>s packed after the header. Only written by
+// BuildTables so every table comes from a point on the curve
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct TableHeader {
+    pub key: Key,
+    pub authority: Pubkey,
+    pub finalized: bool,
+    // tables built so far. BuildTables fills them in order so tables
+    // `0..num_tables` are exactly the ones that have been built
+    pub num_tables: u32,
 }
 
 pub const HEADER_SIZE: usize = 128;
@@ -89,20 +135,201 @@ pub enum DSLInstruction {
     MultiscalarMul(MultiscalarMulData),
 
     DecompressEdwards(RunSplitComputeData), // 2 steps
-    CompressEdwards(RunSplitComputeData), // 2 steps
+    CompressEdwards(RunSplitComputeData), // 2 steps. step 2 replaces step 1 once BatchInvert has inverted Z
     Elligator(RunSplitComputeData), // 2 steps
-    MontgomeryElligator(RunSplitComputeData), // 3 steps
+    MontgomeryElligator(RunSplitComputeData), // 3 steps. step 3 replaces step 2 for RFC 9380
     MontgomeryToEdwards(MontgomeryToEdwardsData), // 2 steps
     MulByCofactor(BuildLookupTableData), // 1 step. writes to table_offset
 
     // CompressedRistretto with witness for inverse sqrt
     DecompressWithWitness(RunDecompressData),
     WriteEdwardsIdentity(RunDecompressData),
+
+    // EdwardsPoint arithmetic on points already in the compute buffer
+    EdwardsAdd(EdwardsBinaryOpData),
+    EdwardsSub(EdwardsBinaryOpData),
+
+    // RistrettoPoint checks that set ComputeHeader::verdict
+    AssertIdentity(RunDecompressData),
+    AssertRistrettoEqual(AssertEqualData),
+
+    // SHA-512 over compute buffer ranges for on-chain challenges. State is
+    // sha512::STATE_SIZE bytes so updates can be spread across cranks
+    Sha512Init(RunDecompressData),
+    Sha512Update(Sha512UpdateData),
+    Sha512Fini(Sha512FiniData),
+    // 64 bytes -> Scalar::from_bytes_mod_order_wide -> packed radix 16
+    PackedScalarFromWide(ScalarFromWideData),
+
+    // constants baked into the instruction buffer, e.g. transcript labels
+    WriteConstant(WriteConstantData),
+
+    // merlin transcripts over compute buffer bytes. State is
+    // transcript::TRANSCRIPT_SIZE bytes
+    TranscriptInit(TranscriptData),
+    TranscriptAppendMessage(TranscriptData),
+    TranscriptChallengeBytes(TranscriptData),
+
+    // canonical 32-byte scalar from the input buffer -> packed radix 16
+    LoadScalar(LoadScalarData),
+
+    // arithmetic on canonical 32-byte scalars in the compute buffer. Results
+    // are canonical and can be packed for MultiscalarMul with PackScalar
+    ScalarAdd(ScalarBinaryOpData),
+    ScalarSub(ScalarBinaryOpData),
+    ScalarMul(ScalarBinaryOpData),
+    ScalarNeg(ScalarUnaryOpData),
+    ScalarFromWide(ScalarFromWideData),
+    PackScalar(ScalarUnaryOpData),
+
+    // UnpackedScalar::montgomery_invert split across SCALAR_INVERT_STEPS
+    ScalarInvert(RunSplitComputeData),
+    // Scalar::batch_invert passes around a single ScalarInvert
+    BatchScalarInvert(BatchScalarInvertData),
+
+    WriteEdwardsBasepoint(RunDecompressData),
+
+    // RistrettoPoint -> CompressedRistretto. Same layout as CompressEdwards
+    CompressRistretto(RunSplitComputeData), // 2 steps
+
+    // RFC 9380 expand_message_xmd with SHA-512. Init absorbs Z_pad into a
+    // sha512::STATE_SIZE state, the message goes through Sha512Update
+    ExpandMessageXmdInit(RunDecompressData),
+    ExpandMessageXmdFini(ExpandMessageXmdData),
+    // 48 big-endian bytes mod p -> FieldElement
+    HashToField(HashToFieldData),
+
+    // packed radix 16 scalar times the basepoint with the precomputed
+    // constants::ED25519_BASEPOINT_TABLE. Same as RISTRETTO_BASEPOINT_TABLE
+    BasepointMul(BasepointMulData),
+
+    // MultiscalarMul with tables_offset into the table buffer named by the
+    // InstructionHeader, passed to CrankCompute after the system program
+    MultiscalarMulTableBuffer(MultiscalarMulData),
+
+    // variable-time Straus for public inputs. NafScalar writes the 256 width-5
+    // NAF digits of a canonical scalar, BuildNafLookupTable the odd multiples
+    // 1P..15P and VartimeMultiscalarMul runs 4 digits per step like
+    // MultiscalarMul
+    NafScalar(ScalarUnaryOpData),
+    BuildNafLookupTable(BuildLookupTableData),
+    VartimeMultiscalarMul(MultiscalarMulData),
+
+    // canonical scalar -> 64 signed radix 2^w digits, Scalar::to_radix_2w
+    RadixScalar(RadixScalarData),
+
+    // variable-time Pippenger with PIPPENGER_WINDOW digits from RadixScalar.
+    // For each digit, PippengerAccumulate adds points start..end into the
+    // buckets and PippengerSumBuckets sums buckets end-1 down to start, folding
+    // into the result once start is 0
+    PippengerAccumulate(PippengerData),
+    PippengerSumBuckets(PippengerData),
+
+    // MultiscalarMul with radix 2^5 and 2^6 digits. Fewer steps than radix 16
+    // for bigger tables. PackScalarRadix2w writes Scalar::to_packed_radix_2w
+    // and start..end index the to_radix_2w_size_hint(w) digits
+    PackScalarRadix2w(RadixScalarData),
+    BuildLookupTableRadix32(BuildLookupTableData),
+    BuildLookupTableRadix64(BuildLookupTableData),
+    MultiscalarMulRadix32(MultiscalarMulData),
+    MultiscalarMulRadix64(MultiscalarMulData),
+
+    // LookupTable<ProjectiveNielsPoint> entries -> LookupTable<AffineNielsPoint>
+    // with one shared inversion. BatchNormalizePrefix multiplies the Zs into
+    // the state, Pow22501P1 and Pow22501P2 run on the product and InvertFini
+    // finishes the inverse for BatchNormalize
+    BatchNormalizePrefix(BatchNormalizeData),
+    InvertFini(RunDecompressData),
+    BatchNormalize(BatchNormalizeData),
+    // MultiscalarMul with LookupTable<AffineNielsPoint>s
+    MultiscalarMulAffine(MultiscalarMulData),
+
+    // Montgomery's trick over FieldElements in the compute buffer. Same passes
+    // as BatchNormalizePrefix and BatchNormalize with the shared inversion in
+    // between
+    BatchInvertPrefix(BatchInvertData),
+    BatchInvert(BatchInvertData),
+
+    // DecompressInit + InvSqrtInit and InvSqrtFini + DecompressFini over
+    // several DECOMPRESS_SIZE slots. The Pow22501 chain isn't shared: unlike
+    // inverses, square roots can't be recovered from a root of the product
+    // since every point needs its own square check, so Pow22501P1 and
+    // Pow22501P2 still run once per slot
+    DecompressInitBatch(BatchDecompressData),
+    DecompressFiniBatch(BatchDecompressData),
+
+    // single step versions of the split instructions above with the inverse
+    // or square root supplied after the input, like DecompressWithWitness.
+    // Square roots must be the nonnegative root returned by sqrt_ratio_i.
+    // Laid out as [ input, ..witnesses.., output ]
+    CompressEdwardsWithWitness(RunDecompressData), // 1/Z
+    DecompressEdwardsWithWitness(RunDecompressData), // sqrt((y²-1)/(dy²+1))
+    ElligatorWithWitness(RunDecompressData), // sqrt_ratio_i(N_s, D)
+    // 1/(1+2r²), sqrt_ratio_i(eps, 1). step 2 or 3 as for MontgomeryElligator
+    MontgomeryElligatorWithWitness(RunSplitComputeData),
+    MontgomeryToEdwardsWithWitness(MontgomeryToEdwardsData), // 1/(u+1). step is unused
 }
 
 // fits under the compute limits for deserialization + one iteration + serialization
 pub const MAX_MULTISCALAR_POINTS: usize = 11;
 
+// same for VartimeMultiscalarMul, which skips the table scans and adds for
+// zero NAF digits
+pub const MAX_VARTIME_MULTISCALAR_POINTS: usize = 16;
+
+// bytes of NafScalar output
+pub const NAF_SIZE: usize = 256;
+
+// same for MultiscalarMulRadix32 and MultiscalarMulRadix64, which scan 16 and
+// 32 entries per table
+pub const MAX_MULTISCALAR_RADIX_32_POINTS: usize = 8;
+pub const MAX_MULTISCALAR_RADIX_64_POINTS: usize = 5;
+
+// same for MultiscalarMulAffine, which has smaller tables and cheaper additions
+pub const MAX_MULTISCALAR_AFFINE_POINTS: usize = 13;
+
+// Zs multiplied per BatchNormalizePrefix and points normalised per
+// BatchNormalize in the instruction builders
+pub const BATCH_NORMALIZE_PREFIX_PER_CRANK: u8 = 32;
+pub const BATCH_NORMALIZE_PER_CRANK: u8 = 16;
+
+// same for BatchInvertPrefix and BatchInvert
+pub const BATCH_INVERT_PREFIX_PER_CRANK: u8 = 32;
+pub const BATCH_INVERT_PER_CRANK: u8 = 24;
+
+// scratch used to decompress a single CompressedRistretto. See decompress_point
+pub const DECOMPRESS_SIZE: usize = 32 * 12;
+
+// points run per DecompressInitBatch and DecompressFiniBatch in the
+// instruction builders
+pub const BATCH_DECOMPRESS_PER_CRANK: u8 = 6;
+
+// digit width for the on-chain Pippenger. Buckets hold 1P..32P
+pub const PIPPENGER_WINDOW: usize = 6;
+pub const PIPPENGER_BUCKETS: usize = 1 << (PIPPENGER_WINDOW - 1);
+
+// points added per PippengerAccumulate and buckets summed per
+// PippengerSumBuckets in the instruction builders
+pub const PIPPENGER_POINTS_PER_CRANK: u8 = 12;
+pub const PIPPENGER_BUCKETS_PER_CRANK: u8 = 8;
+
+// squarings and multiplications of the inversion chain run per ScalarInvert
+// step. Step 0 sets up the chain
+pub const SCALAR_INVERT_OPS_PER_STEP: usize = 40;
+pub const SCALAR_INVERT_STEPS: u8 = (
+    1 + (crate::scalar::MONTGOMERY_INVERT_OPS + SCALAR_INVERT_OPS_PER_STEP - 1)
+        / SCALAR_INVERT_OPS_PER_STEP
+) as u8;
+
+// EdwardsBasepointTable additions run per BasepointMul in the instruction builders
+pub const BASEPOINT_MUL_STEPS_PER_CRANK: u8 = 8;
+
+// EdwardsPoints (128 bytes each) sent in a single BuildTables
+pub const MAX_BUILD_TABLES_POINTS: usize = 6;
+
+// bytes absorbed by a single Sha512Update in the instruction builders
+pub const MAX_SHA512_UPDATE_BYTES: usize = 512;
+
 #[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
 #[repr(C)]
 pub struct CopyInputData { // 32 bytes at a time.. TODO: more flexible
@@ -139,6 +366,188 @@ pub struct BuildLookupTableData {
     pub table_offset: u32,
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct EdwardsBinaryOpData {
+    pub lhs_offset: u32,
+    pub rhs_offset: u32,
+    // may alias either input
+    pub result_offset: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct AssertEqualData {
+    pub lhs_offset: u32,
+    pub rhs_offset: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct Sha512UpdateData {
+    pub state_offset: u32,
+    pub data_offset: u32,
+    pub bytes: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct Sha512FiniData {
+    pub state_offset: u32,
+    pub digest_offset: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ScalarFromWideData {
+    pub wide_offset: u32,
+    pub scalar_offset: u32,
+}
+
+pub const MAX_CONSTANT_BYTES: usize = 10;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct WriteConstantData {
+    pub offset: u32,
+    pub len: u8,
+    pub bytes: [u8; MAX_CONSTANT_BYTES],
+}
+
+// label is the protocol label for TranscriptInit. data is the message for
+// TranscriptAppendMessage and the destination for TranscriptChallengeBytes
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct TranscriptData {
+    pub transcript_offset: u32,
+    pub label_offset: u32,
+    pub label_len: u8,
+    pub data_offset: u32,
+    pub data_len: u16,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct LoadScalarData {
+    pub input_offset: u32,
+    pub compute_offset: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ScalarBinaryOpData {
+    pub lhs_offset: u32,
+    pub rhs_offset: u32,
+    // may alias either input
+    pub result_offset: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ScalarUnaryOpData {
+    pub scalar_offset: u32,
+    pub result_offset: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct BatchScalarInvertData {
+    pub offset: u32,
+    pub num_scalars: u8,
+    pub step: u8,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ExpandMessageXmdData {
+    pub state_offset: u32,
+    pub dst_offset: u32,
+    pub dst_len: u8,
+    pub len_in_bytes: u16,
+    pub output_offset: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct HashToFieldData {
+    pub input_offset: u32,
+    pub result_offset: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct RadixScalarData {
+    pub scalar_offset: u32,
+    pub result_offset: u32,
+    pub w: u8,
+}
+
+// state is laid out as
+// [ result, intermediate_sum, buckets_sum, ..PIPPENGER_BUCKETS buckets.. ]
+// with EdwardsPoints. digits_offset and points_offset are unused by
+// PippengerSumBuckets
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct PippengerData {
+    pub state_offset: u32,
+    pub digits_offset: u32,
+    pub points_offset: u32,
+    pub digit_index: u8,
+    pub start: u8,
+    pub end: u8,
+}
+
+// state is laid out as
+// [ product, t17, t13, t3, t19, inverse, ..prefix products.. ]
+// with FieldElements, so Pow22501P1 runs at state_offset, Pow22501P2 at
+// state_offset + 32 and InvertFini at state_offset. BatchNormalizePrefix runs
+// points start..end and BatchNormalize end-1 down to start, writing
+// AffineNielsPoints to result_offset
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct BatchNormalizeData {
+    pub points_offset: u32,
+    pub state_offset: u32,
+    pub result_offset: u32,
+    pub start: u8,
+    pub end: u8,
+}
+
+// FieldElements at elements_offset + stride * i are replaced by their
+// inverses, zero by zero. state is laid out as for BatchNormalizeData
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct BatchInvertData {
+    pub elements_offset: u32,
+    pub state_offset: u32,
+    pub stride: u16,
+    pub start: u8,
+    pub end: u8,
+}
+
+// CompressedRistretto i is read from compressed_offset + 32 * i and
+// decompressed in the slot at scratch_offset + DECOMPRESS_SIZE * i, which is
+// laid out as for decompress_point. DecompressFiniBatch ignores
+// compressed_offset
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct BatchDecompressData {
+    pub compressed_offset: u32,
+    pub scratch_offset: u32,
+    pub start: u8,
+    pub end: u8,
+}
+
+// runs EdwardsBasepointTable::mul_steps start..end on the point at result_offset
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct BasepointMulData {
+    pub start: u8,
+    pub end: u8,
+    pub scalar_offset: u32,
+    pub result_offset: u32,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
 #[repr(C)]
 pub struct MultiscalarMulData {
@@ -255,6 +664,9 @@ pub fn write_input_points_with_witness(
     ]);
 }
 
+// scalars are written canonically with `to_bytes()`, not packed radix-16, so
+// `LoadScalar` can reject non-canonical ones. Each needs its own LoadScalar
+// instead of one CopyInput for the group
 #[cfg(not(target_arch = "bpf"))]
 pub fn write_input_scalars(
     input_buffer: Pubkey,
@@ -271,7 +683,7 @@ pub fn write_input_scalars(
             base.try_into().unwrap(),
             false,
             bytemuck::cast_slice::<[u8; 32], u8>(
-                scalars.iter().map(|s| s.to_packed_radix_16()).collect::<Vec<_>>().as_slice()),
+                scalars.iter().map(|s| s.to_bytes()).collect::<Vec<_>>().as_slice()),
         ),
     ];
 }
@@ -299,7 +711,7 @@ pub fn initialize_buffer(
     buffer_type: Key,
     inputkeys: Vec<Pubkey>,
 ) -> Instruction {
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(buffer, false),
         AccountMeta::new_readonly(authority, true),
         AccountMeta::new_readonly(solana_program::system_program::id(), false),
@@ -307,7 +719,10 @@ pub fn initialize_buffer(
 
     let instruction_type = match buffer_type {
         Key::InstructionBufferV1 => {
-            assert!(inputkeys.len() == 0);
+            assert!(
+                inputkeys.len() <= 1,
+                "InitializeInstructionBuffer takes at most the table_buffer as a pubkey",
+            );
             Curve25519Instruction::InitializeInstructionBuffer
         },
         Key::InputBufferV1 => {
@@ -319,8 +734,15 @@ pub fn initialize_buffer(
                 inputkeys.len() == 2,
                 "InitializeComputeBuffer needs input_buffer and instruction_buffer as pubkeys",
             );
+            // both are marked as used by the compute buffer
+            accounts.push(AccountMeta::new(inputkeys[0], false));
+            accounts.push(AccountMeta::new(inputkeys[1], false));
             Curve25519Instruction::InitializeComputeBuffer
         },
+        Key::TableBufferV1 => {
+            assert!(inputkeys.len() == 0);
+            Curve25519Instruction::InitializeTableBuffer
+        },
         _ => {
             assert!(false, "Invalid buffer type");
             unreachable!();
@@ -338,6 +760,67 @@ pub fn initialize_buffer(
     }
 }
 
+// a compute buffer that records invalid inputs (points that don't decompress,
+// bad witnesses, non-canonical scalars) as a Rejected verdict and the failing
+// instruction instead of failing the crank
+#[cfg(not(target_arch = "bpf"))]
+pub fn initialize_soft_fail_compute_buffer(
+    compute_buffer: Pubkey,
+    authority: Pubkey,
+    instruction_buffer: Pubkey,
+    input_buffer: Pubkey,
+) -> Instruction {
+    let mut instruction = initialize_buffer(
+        compute_buffer,
+        authority,
+        Key::ComputeBufferV1,
+        vec![instruction_buffer, input_buffer],
+    );
+    instruction.data.push(1);
+    instruction
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn table_buffer_len(
+    num_tables: usize,
+) -> usize {
+    HEADER_SIZE + num_tables * LookupTable::<ProjectiveNielsPoint>::TABLE_SIZE
+}
+
+// writes the lookup tables for `points` starting at table index `first_table`,
+// which must be the number of tables already built. Finalize with
+// `finalize_buffer` once every table is built
+#[cfg(not(target_arch = "bpf"))]
+pub fn build_tables(
+    table_buffer: Pubkey,
+    authority: Pubkey,
+    first_table: u32,
+    points: &[EdwardsPoint],
+) -> Vec<Instruction> {
+    let mut instructions = vec![];
+    let mut table_index = first_table;
+    for chunk in points.chunks(MAX_BUILD_TABLES_POINTS) {
+        let accounts = vec![
+            AccountMeta::new(table_buffer, false),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ];
+
+        let mut data = vec![ToPrimitive::to_u8(&Curve25519Instruction::BuildTables).unwrap()];
+        data.extend_from_slice(&table_index.to_le_bytes());
+        for point in chunk {
+            data.extend_from_slice(&point.to_bytes());
+        }
+        instructions.push(Instruction {
+            program_id: crate::ID,
+            accounts,
+            data,
+        });
+        table_index += chunk.len() as u32;
+    }
+    instructions
+}
+
 #[cfg(not(target_arch = "bpf"))]
 pub fn close_buffer(
     buffer: Pubkey,
@@ -356,11 +839,43 @@ pub fn close_buffer(
     }
 }
 
+// closing a compute buffer releases its instruction and input buffers so they
+// can be closed after it
+#[cfg(not(target_arch = "bpf"))]
+pub fn close_compute_buffer(
+    compute_buffer: Pubkey,
+    authority: Pubkey,
+    instruction_buffer: Pubkey,
+    input_buffer: Pubkey,
+) -> Instruction {
+    let mut instruction = close_buffer(compute_buffer, authority);
+    instruction.accounts.push(AccountMeta::new(instruction_buffer, false));
+    instruction.accounts.push(AccountMeta::new(input_buffer, false));
+    instruction
+}
+
+// runs the single DSL instruction `instruction_num`
 #[cfg(not(target_arch = "bpf"))]
 pub fn crank_compute(
     instruction_buffer: Pubkey,
     input_buffer: Pubkey,
     compute_buffer: Pubkey,
+    instruction_num: u32,
+) -> Instruction {
+    crank_compute_steps(instruction_buffer, input_buffer, compute_buffer, instruction_num, 1)
+}
+
+// runs up to `max_steps` DSL instructions, stopping early once the compute
+// buffer completes. The caller picks `max_steps` to fit the compute budget.
+// Fails unless the compute buffer is at `instruction_num` so retried or
+// duplicated cranks are rejected
+#[cfg(not(target_arch = "bpf"))]
+pub fn crank_compute_steps(
+    instruction_buffer: Pubkey,
+    input_buffer: Pubkey,
+    compute_buffer: Pubkey,
+    instruction_num: u32,
+    max_steps: u8,
 ) -> Instruction {
     let accounts = vec![
         AccountMeta::new_readonly(instruction_buffer, false),
@@ -369,10 +884,75 @@ pub fn crank_compute(
         AccountMeta::new_readonly(solana_program::system_program::id(), false),
     ];
 
+    let mut data = vec![ToPrimitive::to_u8(&Curve25519Instruction::CrankCompute).unwrap(), max_steps];
+    data.extend_from_slice(&instruction_num.to_le_bytes());
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data,
+    }
+}
+
+// CrankCompute instructions that run `num_instructions` DSL instructions from
+// the start, `steps_per_crank` at a time
+#[cfg(not(target_arch = "bpf"))]
+pub fn crank_compute_instructions(
+    instruction_buffer: Pubkey,
+    input_buffer: Pubkey,
+    compute_buffer: Pubkey,
+    num_instructions: usize,
+    steps_per_crank: u8,
+) -> Vec<Instruction> {
+    assert!(steps_per_crank > 0);
+    (0..num_instructions).step_by(steps_per_crank as usize).map(
+        |instruction_num| crank_compute_steps(
+            instruction_buffer,
+            input_buffer,
+            compute_buffer,
+            instruction_num.try_into().unwrap(),
+            steps_per_crank,
+        )
+    ).collect()
+}
+
+// for instruction buffers that run MultiscalarMulTableBuffer
+#[cfg(not(target_arch = "bpf"))]
+pub fn crank_compute_with_table_buffer(
+    instruction_buffer: Pubkey,
+    input_buffer: Pubkey,
+    compute_buffer: Pubkey,
+    table_buffer: Pubkey,
+    instruction_num: u32,
+    max_steps: u8,
+) -> Instruction {
+    let mut instruction = crank_compute_steps(
+        instruction_buffer, input_buffer, compute_buffer, instruction_num, max_steps);
+    instruction.accounts.push(AccountMeta::new_readonly(table_buffer, false));
+    instruction
+}
+
+// sets return data to [0] if the compute buffer passes
+// `cpi::verify_completed_compute_buffer` and otherwise to 1 followed by the
+// little-endian `Curve25519Error` code it failed with
+#[cfg(not(target_arch = "bpf"))]
+pub fn verify_compute_buffer(
+    instruction_buffer: Pubkey,
+    input_buffer: Pubkey,
+    compute_buffer: Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(compute_buffer, false),
+    ];
+
+    let mut data = vec![ToPrimitive::to_u8(&Curve25519Instruction::VerifyComputeBuffer).unwrap()];
+    data.extend_from_slice(&instruction_buffer.to_bytes());
+    data.extend_from_slice(&input_buffer.to_bytes());
+
     Instruction {
         program_id: crate::ID,
         accounts,
-        data: vec![ToPrimitive::to_u8(&Curve25519Instruction::CrankCompute).unwrap()],
+        data,
     }
 }
 
@@ -416,7 +996,11 @@ pub fn transfer_proof_instructions(
     // ]
     let result_space_size = proof_groups.len() * 32 * 4;
     let scratch_space = HEADER_SIZE + result_space_size;
-    let scratch_space_size = 32 * 12; // space needed for decompression
+    let scratch_space_size = if with_witness {
+        DECOMPRESS_SIZE
+    } else {
+        decompress_points_packed_scratch_len(num_proof_inputs)
+    };
 
     let scalars_offset = scratch_space + scratch_space_size;
     let tables_offset  = scalars_offset + 32 * num_proof_scalars;
@@ -426,30 +1010,31 @@ pub fn transfer_proof_instructions(
 
     // build the lookup tables
     let mut input_offset = HEADER_SIZE;
-    for input_num in 0..num_proof_inputs {
-        let table_offset = tables_offset + input_num * table_size;
-        let scratch_space = scratch_space.try_into().unwrap();
-        if with_witness {
+    if with_witness {
+        for input_num in 0..num_proof_inputs {
+            let table_offset = tables_offset + input_num * table_size;
+            let scratch_space = scratch_space.try_into().unwrap();
             instructions.extend_from_slice(
                 &decompress_point_with_witness(input_offset, scratch_space, table_offset),
             );
-        } else {
-            instructions.extend_from_slice(
-                &decompress_point(input_offset, scratch_space, table_offset),
-            );
+            input_offset += proof_point_size(with_witness);
         }
-        input_offset += proof_point_size(with_witness);
+    } else {
+        instructions.extend(
+            decompress_points_packed(num_proof_inputs, input_offset, scratch_space, tables_offset),
+        );
+        input_offset += num_proof_inputs * proof_point_size(with_witness);
     }
 
-    // copy the scalars
-    let scalar_bytes = 32 * num_proof_scalars;
-    instructions.push(
-        DSLInstruction::CopyInput(CopyInputData{
-            input_offset: input_offset.try_into().unwrap(),
-            compute_offset: scalars_offset.try_into().unwrap(),
-            bytes: scalar_bytes.try_into().unwrap(),
-        }),
-    );
+    // load and validate the scalars
+    for scalar_num in 0..num_proof_scalars {
+        instructions.push(
+            DSLInstruction::LoadScalar(LoadScalarData{
+                input_offset: (input_offset + 32 * scalar_num).try_into().unwrap(),
+                compute_offset: (scalars_offset + 32 * scalar_num).try_into().unwrap(),
+            }),
+        );
+    }
 
     // write the identity inputs
     let mut result_offset = HEADER_SIZE;
@@ -487,33 +1072,455 @@ pub fn transfer_proof_instructions(
     dsl_instructions_to_bytes(&instructions)
 }
 
-// CompressedRistretto -> Table<Ristretto>
+// `transfer_proof_instructions` followed by an AssertIdentity on each group's
+// result, so the compute buffer ends Accepted or Rejected instead of Pending
 #[cfg(not(target_arch = "bpf"))]
-pub fn decompress_point(
-    input_offset: usize,
-    scratch_space: u32,
-    table_offset: usize,
-) -> [DSLInstruction; 8] {
-    [
-        DSLInstruction::CopyInput(CopyInputData{
-            input_offset: input_offset.try_into().unwrap(),
-            compute_offset: scratch_space,
-            bytes: 32,
-        }),
-        DSLInstruction::DecompressInit(RunDecompressData{
-            offset: scratch_space,
-        }),
-        DSLInstruction::InvSqrtInit(RunDecompressData{
-            offset: scratch_space + 32,
-        }),
-        DSLInstruction::Pow22501P1(RunDecompressData{
-            offset: scratch_space + 32 * 2,
-        }),
-        DSLInstruction::Pow22501P2(RunDecompressData{
-            offset: scratch_space + 32 * 3,
+pub fn transfer_proof_instructions_with_asserts(
+    proof_groups: Vec<usize>,
+    with_witness: bool,
+) -> Vec<u8> {
+    let mut instructions = vec![];
+    let mut result_offset = HEADER_SIZE;
+    for _group_size in proof_groups.iter() {
+        instructions.push(
+            DSLInstruction::AssertIdentity(RunDecompressData{
+                offset: result_offset.try_into().unwrap(),
+            }),
+        );
+        result_offset += 32 * 4;
+    }
+
+    let mut dsl = transfer_proof_instructions(proof_groups, with_witness);
+    dsl.extend(dsl_instructions_to_bytes(&instructions));
+    dsl
+}
+
+// multiscalar multiplication of the packed scalars at `scalars_offset` with
+// tables `first_table..first_table+num_inputs` of the table buffer. Result is
+// written to `result_offset`
+#[cfg(not(target_arch = "bpf"))]
+pub fn multiscalar_mul_table_buffer_instructions(
+    num_inputs: usize,
+    scalars_offset: usize,
+    first_table: usize,
+    result_offset: usize,
+) -> Vec<DSLInstruction> {
+    assert!(num_inputs <= MAX_MULTISCALAR_POINTS);
+    let tables_offset = table_buffer_len(first_table);
+
+    let mut instructions = vec![
+        DSLInstruction::WriteEdwardsIdentity(RunDecompressData{
+            offset: result_offset.try_into().unwrap(),
         }),
-        DSLInstruction::InvSqrtFini(RunDecompressData{
-            offset: scratch_space + 32,
+    ];
+    for iter in (0..64).rev() {
+        instructions.push(
+            DSLInstruction::MultiscalarMulTableBuffer(MultiscalarMulData{
+                start: iter as u8,
+                end: iter + 1 as u8,
+                num_inputs: num_inputs.try_into().unwrap(),
+                scalars_offset: scalars_offset.try_into().unwrap(),
+                tables_offset: tables_offset.try_into().unwrap(),
+                result_offset: result_offset.try_into().unwrap(),
+            })
+        );
+    }
+    instructions
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn vartime_multiscalar_mul_scratch_len(
+    num_inputs: usize,
+) -> usize {
+    num_inputs * (NAF_SIZE + NafLookupTable5::<ProjectiveNielsPoint>::TABLE_SIZE)
+}
+
+// variable-time multiscalar multiplication of the canonical scalars at
+// `scalars_offset` with the EdwardsPoints at `points_offset`. Only for public
+// scalars and points. Result is written to `result_offset`
+#[cfg(not(target_arch = "bpf"))]
+pub fn vartime_multiscalar_mul_instructions(
+    num_inputs: usize,
+    scalars_offset: usize,
+    points_offset: usize,
+    scratch_space: usize,
+    result_offset: usize,
+) -> Vec<DSLInstruction> {
+    assert!(num_inputs <= MAX_VARTIME_MULTISCALAR_POINTS);
+    // scratch_space is laid out as [ ..nafs.., ..tables.. ]
+    let nafs_offset = scratch_space;
+    let tables_offset = nafs_offset + num_inputs * NAF_SIZE;
+    let table_size = NafLookupTable5::<ProjectiveNielsPoint>::TABLE_SIZE;
+
+    let mut instructions = vec![];
+    for i in 0..num_inputs {
+        instructions.push(
+            DSLInstruction::NafScalar(ScalarUnaryOpData{
+                scalar_offset: (scalars_offset + 32 * i).try_into().unwrap(),
+                result_offset: (nafs_offset + NAF_SIZE * i).try_into().unwrap(),
+            }),
+        );
+        instructions.push(
+            DSLInstruction::BuildNafLookupTable(BuildLookupTableData{
+                point_offset: (points_offset + 128 * i).try_into().unwrap(),
+                table_offset: (tables_offset + table_size * i).try_into().unwrap(),
+            }),
+        );
+    }
+
+    instructions.push(
+        DSLInstruction::WriteEdwardsIdentity(RunDecompressData{
+            offset: result_offset.try_into().unwrap(),
+        }),
+    );
+    for iter in (0..64).rev() {
+        instructions.push(
+            DSLInstruction::VartimeMultiscalarMul(MultiscalarMulData{
+                start: iter as u8,
+                end: iter + 1 as u8,
+                num_inputs: num_inputs.try_into().unwrap(),
+                scalars_offset: nafs_offset.try_into().unwrap(),
+                tables_offset: tables_offset.try_into().unwrap(),
+                result_offset: result_offset.try_into().unwrap(),
+            })
+        );
+    }
+    instructions
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn multiscalar_mul_radix_table_size(
+    w: usize,
+) -> usize {
+    match w {
+        5 => LookupTableRadix32::<ProjectiveNielsPoint>::TABLE_SIZE,
+        6 => LookupTableRadix64::<ProjectiveNielsPoint>::TABLE_SIZE,
+        _ => panic!("unsupported window {}", w),
+    }
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn multiscalar_mul_radix_scratch_len(
+    w: usize,
+    num_inputs: usize,
+) -> usize {
+    num_inputs * (crate::scalar::PACKED_RADIX_2W_SIZE + multiscalar_mul_radix_table_size(w))
+}
+
+// MultiscalarMul* steps, one per crank, for `num_points` points with radix
+// 2^w digits (w = 4 is the radix-16 MultiscalarMul). Points are split into
+// groups of at most the MAX_MULTISCALAR_*_POINTS for the width and each group
+// takes one step per digit, i.e. 64 * ceil(n / 11), 52 * ceil(n / 8) and
+// 43 * ceil(n / 5). The wider windows only pay off when the groups are full:
+// radix-64 is cheapest for up to 5 points and radix-32 for 6 to 8 and 12 to
+// 16. From 17 points radix-16 is cheaper at about 5.8 steps per point against
+// 6.5 and 8.6, apart from counts like 23 or 24 that fill radix-32 groups
+// exactly. Building the tables costs the same one step per point either way
+#[cfg(not(target_arch = "bpf"))]
+pub fn multiscalar_mul_steps(
+    w: usize,
+    num_points: usize,
+) -> usize {
+    let max_points = match w {
+        4 => MAX_MULTISCALAR_POINTS,
+        5 => MAX_MULTISCALAR_RADIX_32_POINTS,
+        6 => MAX_MULTISCALAR_RADIX_64_POINTS,
+        _ => panic!("unsupported window {}", w),
+    };
+    let digits_count = if w == 4 { 64 } else { crate::scalar::Scalar::to_radix_2w_size_hint(w) };
+    digits_count * ((num_points + max_points - 1) / max_points)
+}
+
+// constant-time multiscalar multiplication with radix 2^w digits for w = 5
+// or 6 of the canonical scalars at `scalars_offset` with the EdwardsPoints at
+// `points_offset`. Result is written to `result_offset`
+#[cfg(not(target_arch = "bpf"))]
+pub fn multiscalar_mul_radix_instructions(
+    w: usize,
+    num_inputs: usize,
+    scalars_offset: usize,
+    points_offset: usize,
+    scratch_space: usize,
+    result_offset: usize,
+) -> Vec<DSLInstruction> {
+    let max_points = match w {
+        5 => MAX_MULTISCALAR_RADIX_32_POINTS,
+        6 => MAX_MULTISCALAR_RADIX_64_POINTS,
+        _ => panic!("unsupported window {}", w),
+    };
+    assert!(num_inputs <= max_points);
+    // scratch_space is laid out as [ ..tables.., ..packed scalars.. ] so the
+    // tables stay aligned
+    let packed_size = crate::scalar::PACKED_RADIX_2W_SIZE;
+    let table_size = multiscalar_mul_radix_table_size(w);
+    let tables_offset = scratch_space;
+    let packed_offset = tables_offset + num_inputs * table_size;
+
+    let mut instructions = vec![];
+    for i in 0..num_inputs {
+        instructions.push(
+            DSLInstruction::PackScalarRadix2w(RadixScalarData{
+                scalar_offset: (scalars_offset + 32 * i).try_into().unwrap(),
+                result_offset: (packed_offset + packed_size * i).try_into().unwrap(),
+                w: w as u8,
+            }),
+        );
+        let data = BuildLookupTableData{
+            point_offset: (points_offset + 128 * i).try_into().unwrap(),
+            table_offset: (tables_offset + table_size * i).try_into().unwrap(),
+        };
+        instructions.push(
+            if w == 5 {
+                DSLInstruction::BuildLookupTableRadix32(data)
+            } else {
+                DSLInstruction::BuildLookupTableRadix64(data)
+            }
+        );
+    }
+
+    instructions.push(
+        DSLInstruction::WriteEdwardsIdentity(RunDecompressData{
+            offset: result_offset.try_into().unwrap(),
+        }),
+    );
+    let digits_count = crate::scalar::Scalar::to_radix_2w_size_hint(w);
+    for iter in (0..digits_count).rev() {
+        let data = MultiscalarMulData{
+            start: iter as u8,
+            end: (iter + 1) as u8,
+            num_inputs: num_inputs.try_into().unwrap(),
+            scalars_offset: packed_offset.try_into().unwrap(),
+            tables_offset: tables_offset.try_into().unwrap(),
+            result_offset: result_offset.try_into().unwrap(),
+        };
+        instructions.push(
+            if w == 5 {
+                DSLInstruction::MultiscalarMulRadix32(data)
+            } else {
+                DSLInstruction::MultiscalarMulRadix64(data)
+            }
+        );
+    }
+    instructions
+}
+
+// scratch_space is laid out as [ ..projective tables.., ..state.. ] with
+// 1280 bytes of LookupTable<ProjectiveNielsPoint> and 256 of prefix products
+// per input. The affine tables are written over the last 960 bytes per input
+// of the projective tables, which BatchNormalize has already read by then. So
+// building them peaks at 1536 bytes per input, more than the 1280 of the
+// projective tables, and leaves 960 of affine tables. Everything outside
+// `affine_lookup_tables_offset`..+960 per input is free again afterwards
+#[cfg(not(target_arch = "bpf"))]
+pub fn affine_lookup_tables_scratch_len(
+    num_inputs: usize,
+) -> usize {
+    num_inputs * (LookupTable::<ProjectiveNielsPoint>::TABLE_SIZE + 32 * 8) + 32 * 6
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn affine_lookup_tables_offset(
+    num_inputs: usize,
+    scratch_space: usize,
+) -> usize {
+    scratch_space + num_inputs * (
+        LookupTable::<ProjectiveNielsPoint>::TABLE_SIZE
+        - LookupTable::<AffineNielsPoint>::TABLE_SIZE
+    )
+}
+
+// LookupTable<AffineNielsPoint>s of the EdwardsPoints at `points_offset`,
+// packed at `affine_lookup_tables_offset` for MultiscalarMulAffine
+#[cfg(not(target_arch = "bpf"))]
+pub fn build_affine_lookup_tables_instructions(
+    num_inputs: usize,
+    points_offset: usize,
+    scratch_space: usize,
+) -> Vec<DSLInstruction> {
+    let num_points = 8 * num_inputs;
+    assert!(num_points > 0 && num_points <= u8::MAX as usize);
+    let table_size = LookupTable::<ProjectiveNielsPoint>::TABLE_SIZE;
+    let projective_offset = scratch_space;
+    let state_offset = projective_offset + num_inputs * table_size;
+    // BatchNormalize walks down from the last point and affine point i starts
+    // after projective point i - 1 ends, so it never overwrites one still unread
+    let tables_offset = affine_lookup_tables_offset(num_inputs, scratch_space);
+
+    let mut instructions = vec![];
+    for i in 0..num_inputs {
+        instructions.push(
+            DSLInstruction::BuildLookupTable(BuildLookupTableData{
+                point_offset: (points_offset + 128 * i).try_into().unwrap(),
+                table_offset: (projective_offset + table_size * i).try_into().unwrap(),
+            }),
+        );
+    }
+
+    let data = |start: usize, end: usize| BatchNormalizeData{
+        points_offset: projective_offset.try_into().unwrap(),
+        state_offset: state_offset.try_into().unwrap(),
+        result_offset: tables_offset.try_into().unwrap(),
+        start: start as u8,
+        end: end as u8,
+    };
+    let mut start = 0;
+    while start < num_points {
+        let end = (start + BATCH_NORMALIZE_PREFIX_PER_CRANK as usize).min(num_points);
+        instructions.push(DSLInstruction::BatchNormalizePrefix(data(start, end)));
+        start = end;
+    }
+
+    instructions.push(
+        DSLInstruction::Pow22501P1(RunDecompressData{
+            offset: state_offset.try_into().unwrap(),
+        }),
+    );
+    instructions.push(
+        DSLInstruction::Pow22501P2(RunDecompressData{
+            offset: (state_offset + 32).try_into().unwrap(),
+        }),
+    );
+    instructions.push(
+        DSLInstruction::InvertFini(RunDecompressData{
+            offset: state_offset.try_into().unwrap(),
+        }),
+    );
+
+    let mut end = num_points;
+    while end > 0 {
+        let start = end.saturating_sub(BATCH_NORMALIZE_PER_CRANK as usize);
+        instructions.push(DSLInstruction::BatchNormalize(data(start, end)));
+        end = start;
+    }
+    instructions
+}
+
+// multiscalar multiplication of the packed scalars at `scalars_offset` with
+// the LookupTable<AffineNielsPoint>s at `tables_offset`. Result is written to
+// `result_offset`
+#[cfg(not(target_arch = "bpf"))]
+pub fn multiscalar_mul_affine_instructions(
+    num_inputs: usize,
+    scalars_offset: usize,
+    tables_offset: usize,
+    result_offset: usize,
+) -> Vec<DSLInstruction> {
+    assert!(num_inputs <= MAX_MULTISCALAR_AFFINE_POINTS);
+
+    let mut instructions = vec![
+        DSLInstruction::WriteEdwardsIdentity(RunDecompressData{
+            offset: result_offset.try_into().unwrap(),
+        }),
+    ];
+    for iter in (0..64).rev() {
+        instructions.push(
+            DSLInstruction::MultiscalarMulAffine(MultiscalarMulData{
+                start: iter as u8,
+                end: iter + 1 as u8,
+                num_inputs: num_inputs.try_into().unwrap(),
+                scalars_offset: scalars_offset.try_into().unwrap(),
+                tables_offset: tables_offset.try_into().unwrap(),
+                result_offset: result_offset.try_into().unwrap(),
+            })
+        );
+    }
+    instructions
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn pippenger_scratch_len(
+    num_inputs: usize,
+) -> usize {
+    128 * (3 + PIPPENGER_BUCKETS) + 64 * num_inputs
+}
+
+// variable-time Pippenger of the canonical scalars at `scalars_offset` with
+// the EdwardsPoints at `points_offset`. Only for public scalars and points.
+// Result is written to `scratch_space`
+#[cfg(not(target_arch = "bpf"))]
+pub fn pippenger_instructions(
+    num_inputs: usize,
+    scalars_offset: usize,
+    points_offset: usize,
+    scratch_space: usize,
+) -> Vec<DSLInstruction> {
+    assert!(num_inputs > 0 && num_inputs <= u8::MAX as usize);
+    // scratch_space is laid out as [ ..state.., ..digits.. ]
+    let state_offset = scratch_space;
+    let digits_offset = state_offset + 128 * (3 + PIPPENGER_BUCKETS);
+
+    let mut instructions = vec![];
+    for i in 0..num_inputs {
+        instructions.push(
+            DSLInstruction::RadixScalar(RadixScalarData{
+                scalar_offset: (scalars_offset + 32 * i).try_into().unwrap(),
+                result_offset: (digits_offset + 64 * i).try_into().unwrap(),
+                w: PIPPENGER_WINDOW as u8,
+            }),
+        );
+    }
+
+    instructions.push(
+        DSLInstruction::WriteEdwardsIdentity(RunDecompressData{
+            offset: state_offset.try_into().unwrap(),
+        }),
+    );
+
+    let data = |digit_index: usize, start: usize, end: usize| PippengerData{
+        state_offset: state_offset.try_into().unwrap(),
+        digits_offset: digits_offset.try_into().unwrap(),
+        points_offset: points_offset.try_into().unwrap(),
+        digit_index: digit_index as u8,
+        start: start as u8,
+        end: end as u8,
+    };
+    let digits_count = crate::scalar::Scalar::to_radix_2w_size_hint(PIPPENGER_WINDOW);
+    for digit_index in (0..digits_count).rev() {
+        let mut start = 0;
+        while start < num_inputs {
+            let end = (start + PIPPENGER_POINTS_PER_CRANK as usize).min(num_inputs);
+            instructions.push(
+                DSLInstruction::PippengerAccumulate(data(digit_index, start, end)));
+            start = end;
+        }
+        let mut end = PIPPENGER_BUCKETS;
+        while end > 0 {
+            let start = end.saturating_sub(PIPPENGER_BUCKETS_PER_CRANK as usize);
+            instructions.push(
+                DSLInstruction::PippengerSumBuckets(data(digit_index, start, end)));
+            end = start;
+        }
+    }
+    instructions
+}
+
+// CompressedRistretto -> Table<Ristretto>
+#[cfg(not(target_arch = "bpf"))]
+pub fn decompress_point(
+    input_offset: usize,
+    scratch_space: u32,
+    table_offset: usize,
+) -> [DSLInstruction; 8] {
+    [
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset: input_offset.try_into().unwrap(),
+            compute_offset: scratch_space,
+            bytes: 32,
+        }),
+        DSLInstruction::DecompressInit(RunDecompressData{
+            offset: scratch_space,
+        }),
+        DSLInstruction::InvSqrtInit(RunDecompressData{
+            offset: scratch_space + 32,
+        }),
+        DSLInstruction::Pow22501P1(RunDecompressData{
+            offset: scratch_space + 32 * 2,
+        }),
+        DSLInstruction::Pow22501P2(RunDecompressData{
+            offset: scratch_space + 32 * 3,
+        }),
+        DSLInstruction::InvSqrtFini(RunDecompressData{
+            offset: scratch_space + 32,
         }),
         DSLInstruction::DecompressFini(RunDecompressData{
             offset: scratch_space,
@@ -525,6 +1532,82 @@ pub fn decompress_point(
     ]
 }
 
+#[cfg(not(target_arch = "bpf"))]
+pub fn decompress_points_packed_scratch_len(
+    num_points: usize,
+) -> usize {
+    (32 + DECOMPRESS_SIZE) * num_points
+}
+
+// CompressedRistrettos at `input_offset` -> packed Table<Ristretto>s at
+// `tables_offset`. Same as decompress_point for each point but the init and
+// fini steps of BATCH_DECOMPRESS_PER_CRANK points are packed into one crank.
+// Nothing else is shared: every point still runs its own Pow22501P1 and
+// Pow22501P2, so K points take 3K + 2 * ceil(K / BATCH_DECOMPRESS_PER_CRANK)
+// cranks after the copy instead of 7K
+#[cfg(not(target_arch = "bpf"))]
+pub fn decompress_points_packed(
+    num_points: usize,
+    input_offset: usize,
+    scratch_space: usize,
+    tables_offset: usize,
+) -> Vec<DSLInstruction> {
+    assert!(num_points <= u8::MAX as usize);
+    // scratch_space is laid out as [ ..compressed.., ..slots.. ]
+    let compressed_offset = scratch_space;
+    let slots_offset = compressed_offset + 32 * num_points;
+    let slot_offset = |i: usize| -> u32 {
+        (slots_offset + DECOMPRESS_SIZE * i).try_into().unwrap()
+    };
+    let table_size = LookupTable::<ProjectiveNielsPoint>::TABLE_SIZE;
+
+    let mut instructions = vec![
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset: input_offset.try_into().unwrap(),
+            compute_offset: compressed_offset.try_into().unwrap(),
+            bytes: (32 * num_points).try_into().unwrap(),
+        }),
+    ];
+
+    let data = |start: usize, end: usize| BatchDecompressData{
+        compressed_offset: compressed_offset.try_into().unwrap(),
+        scratch_offset: slots_offset.try_into().unwrap(),
+        start: start as u8,
+        end: end as u8,
+    };
+    let batches = (0..num_points)
+        .step_by(BATCH_DECOMPRESS_PER_CRANK as usize)
+        .map(|start| (start, (start + BATCH_DECOMPRESS_PER_CRANK as usize).min(num_points)));
+
+    for (start, end) in batches.clone() {
+        instructions.push(DSLInstruction::DecompressInitBatch(data(start, end)));
+    }
+    for i in 0..num_points {
+        instructions.push(
+            DSLInstruction::Pow22501P1(RunDecompressData{
+                offset: slot_offset(i) + 32 * 2,
+            }),
+        );
+        instructions.push(
+            DSLInstruction::Pow22501P2(RunDecompressData{
+                offset: slot_offset(i) + 32 * 3,
+            }),
+        );
+    }
+    for (start, end) in batches {
+        instructions.push(DSLInstruction::DecompressFiniBatch(data(start, end)));
+    }
+    for i in 0..num_points {
+        instructions.push(
+            DSLInstruction::BuildLookupTable(BuildLookupTableData{
+                point_offset: slot_offset(i) + 32 * 8,
+                table_offset: (tables_offset + table_size * i).try_into().unwrap(),
+            }),
+        );
+    }
+    instructions
+}
+
 // CompressedRistretto, 2^225-1 witness -> Table<Ristretto>
 // more input copying, less compute
 #[cfg(not(target_arch = "bpf"))]
@@ -577,6 +1660,28 @@ pub fn elligator_to_curve_instructions(
     ]
 }
 
+// RistrettoPoint::from_uniform_bytes. 64 input bytes -> RistrettoPoint at
+// `scratch_space + 32 * 20`
+#[cfg(not(target_arch = "bpf"))]
+pub fn ristretto_from_uniform_bytes_instructions(
+    input_offset: u32,
+    scratch_space: u32,
+) -> Vec<DSLInstruction> {
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &elligator_to_curve_instructions(input_offset, scratch_space));
+    instructions.extend_from_slice(
+        &elligator_to_curve_instructions(input_offset + 32, scratch_space + 32 * 10));
+    instructions.push(
+        DSLInstruction::EdwardsAdd(EdwardsBinaryOpData{
+            lhs_offset: scratch_space + 32 * 6,
+            rhs_offset: scratch_space + 32 * 16,
+            result_offset: scratch_space + 32 * 20,
+        }),
+    );
+    instructions
+}
+
 #[cfg(not(target_arch = "bpf"))]
 pub fn edwards_elligator_to_curve_instructions(
     input_offset: u32,
@@ -654,62 +1759,1071 @@ pub fn edwards_elligator_to_curve_instructions(
     ]
 }
 
+// RFC 9380 expand_message_xmd over the concatenated compute buffer ranges
+// (offset, len). Uses STATE_SIZE + dst.len() bytes of scratch space
 #[cfg(not(target_arch = "bpf"))]
-pub fn decompress_edwards_instructions(
-    input_offset: u32,
+pub fn expand_message_xmd_instructions(
+    ranges: &[(u32, u32)],
+    dst: &[u8],
+    len_in_bytes: u16,
     scratch_space: u32,
-) -> [DSLInstruction; 5] {
-    [
-        DSLInstruction::CopyInput(CopyInputData{
-            input_offset: input_offset.try_into().unwrap(),
-            compute_offset: scratch_space,
-            bytes: 32,
-        }),
-        DSLInstruction::DecompressEdwards(RunSplitComputeData{
-            offset: scratch_space,
-            step: 0,
-        }),
-        DSLInstruction::Pow22501P1(RunDecompressData{
-            offset: scratch_space + 32,
-        }),
-        DSLInstruction::Pow22501P2(RunDecompressData{
-            offset: scratch_space + 64,
+    output_offset: u32,
+) -> Vec<DSLInstruction> {
+    assert!(dst.len() <= 255);
+    let state_offset = scratch_space;
+    let dst_offset = scratch_space + crate::sha512::STATE_SIZE as u32;
+
+    let mut instructions = vec![];
+    for (i, chunk) in dst.chunks(MAX_CONSTANT_BYTES).enumerate() {
+        let mut bytes = [0; MAX_CONSTANT_BYTES];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        instructions.push(
+            DSLInstruction::WriteConstant(WriteConstantData{
+                offset: dst_offset + (i * MAX_CONSTANT_BYTES) as u32,
+                len: chunk.len() as u8,
+                bytes,
+            }),
+        );
+    }
+
+    instructions.push(
+        DSLInstruction::ExpandMessageXmdInit(RunDecompressData{
+            offset: state_offset,
         }),
-        DSLInstruction::DecompressEdwards(RunSplitComputeData{
-            offset: scratch_space,
-            step: 1,
+    );
+
+    for &(range_offset, range_len) in ranges.iter() {
+        let mut data_offset = range_offset;
+        let range_end = range_offset + range_len;
+        while data_offset < range_end {
+            let bytes = (range_end - data_offset).min(MAX_SHA512_UPDATE_BYTES as u32);
+            instructions.push(
+                DSLInstruction::Sha512Update(Sha512UpdateData{
+                    state_offset,
+                    data_offset,
+                    bytes,
+                }),
+            );
+            data_offset += bytes;
+        }
+    }
+
+    instructions.push(
+        DSLInstruction::ExpandMessageXmdFini(ExpandMessageXmdData{
+            state_offset,
+            dst_offset,
+            dst_len: dst.len() as u8,
+            len_in_bytes,
+            output_offset,
         }),
-    ]
+    );
+
+    instructions
 }
 
+// map_to_curve_elligator2_edwards25519 of the FieldElement at `offset`.
+// EdwardsPoint at `offset + 32 * 12`
 #[cfg(not(target_arch = "bpf"))]
-pub fn compress_edwards_instructions(
-    input_offset: u32,
-    scratch_space: u32,
-) -> [DSLInstruction; 5] {
+fn elligator2_edwards25519_instructions(
+    offset: u32,
+) -> [DSLInstruction; 7] {
     [
-        DSLInstruction::CopyInput(CopyInputData{
-            input_offset: input_offset.try_into().unwrap(),
-            compute_offset: scratch_space,
-            bytes: 128,
-        }),
-        DSLInstruction::CompressEdwards(RunSplitComputeData{
-            offset: scratch_space,
+        DSLInstruction::MontgomeryElligator(RunSplitComputeData{
+            offset,
             step: 0,
         }),
         DSLInstruction::Pow22501P1(RunDecompressData{
-            offset: scratch_space + 32 * 4,
+            offset: offset + 32,
         }),
         DSLInstruction::Pow22501P2(RunDecompressData{
-            offset: scratch_space + 32 * 5,
+            offset: offset + 32 * 2,
         }),
-        DSLInstruction::CompressEdwards(RunSplitComputeData{
-            offset: scratch_space,
+        DSLInstruction::MontgomeryElligator(RunSplitComputeData{
+            offset,
             step: 1,
         }),
+        DSLInstruction::Pow22501P1(RunDecompressData{
+            offset: offset + 32 * 6,
+        }),
+        DSLInstruction::Pow22501P2(RunDecompressData{
+            offset: offset + 32 * 7,
+        }),
+        DSLInstruction::MontgomeryElligator(RunSplitComputeData{
+            offset,
+            step: 3,
+        }),
     ]
 }
 
+// scratch space for each map_to_curve in hash_to_curve_instructions and
+// encode_to_curve_instructions
+#[cfg(not(target_arch = "bpf"))]
+const HASH_TO_CURVE_MAP_SIZE: u32 = 32 * 16;
+
+#[cfg(not(target_arch = "bpf"))]
+fn hash_to_curve_common(
+    ranges: &[(u32, u32)],
+    dst: &[u8],
+    count: u32,
+    scratch_space: u32,
+) -> Vec<DSLInstruction> {
+    let maps_offset = scratch_space + 128;
+    let uniform_offset = maps_offset + HASH_TO_CURVE_MAP_SIZE * count;
+
+    let mut instructions = expand_message_xmd_instructions(
+        ranges,
+        dst,
+        (48 * count) as u16,
+        uniform_offset + 48 * count,
+        uniform_offset,
+    );
+
+    for i in 0..count {
+        let map_offset = maps_offset + HASH_TO_CURVE_MAP_SIZE * i;
+        instructions.push(
+            DSLInstruction::HashToField(HashToFieldData{
+                input_offset: uniform_offset + 48 * i,
+                result_offset: map_offset,
+            }),
+        );
+        instructions.extend_from_slice(
+            &elligator2_edwards25519_instructions(map_offset));
+    }
+
+    instructions
+}
+
+// RFC 9380 edwards25519_XMD:SHA-512_ELL2_RO_ of the concatenated compute
+// buffer ranges (offset, len). EdwardsPoint at `scratch_space`. Uses
+// hash_to_curve_scratch_len(dst, true) bytes of scratch space
+#[cfg(not(target_arch = "bpf"))]
+pub fn hash_to_curve_instructions(
+    ranges: &[(u32, u32)],
+    dst: &[u8],
+    scratch_space: u32,
+) -> Vec<DSLInstruction> {
+    let mut instructions = hash_to_curve_common(ranges, dst, 2, scratch_space);
+    instructions.push(
+        DSLInstruction::EdwardsAdd(EdwardsBinaryOpData{
+            lhs_offset: scratch_space + 128 + 32 * 12,
+            rhs_offset: scratch_space + 128 + HASH_TO_CURVE_MAP_SIZE + 32 * 12,
+            result_offset: scratch_space,
+        }),
+    );
+    instructions.push(
+        DSLInstruction::MulByCofactor(BuildLookupTableData{
+            point_offset: scratch_space,
+            table_offset: scratch_space,
+        }),
+    );
+    instructions
+}
+
+// RFC 9380 edwards25519_XMD:SHA-512_ELL2_NU_. Same layout as
+// hash_to_curve_instructions with hash_to_curve_scratch_len(dst, false)
+#[cfg(not(target_arch = "bpf"))]
+pub fn encode_to_curve_instructions(
+    ranges: &[(u32, u32)],
+    dst: &[u8],
+    scratch_space: u32,
+) -> Vec<DSLInstruction> {
+    let mut instructions = hash_to_curve_common(ranges, dst, 1, scratch_space);
+    instructions.push(
+        DSLInstruction::MulByCofactor(BuildLookupTableData{
+            point_offset: scratch_space + 128 + 32 * 12,
+            table_offset: scratch_space,
+        }),
+    );
+    instructions
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn hash_to_curve_scratch_len(
+    dst: &[u8],
+    random_oracle: bool,
+) -> usize {
+    let count = if random_oracle { 2 } else { 1 };
+    128 + (HASH_TO_CURVE_MAP_SIZE as usize + 48) * count
+        + crate::sha512::STATE_SIZE + dst.len()
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn decompress_edwards_instructions(
+    input_offset: u32,
+    scratch_space: u32,
+) -> [DSLInstruction; 5] {
+    [
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset: input_offset.try_into().unwrap(),
+            compute_offset: scratch_space,
+            bytes: 32,
+        }),
+        DSLInstruction::DecompressEdwards(RunSplitComputeData{
+            offset: scratch_space,
+            step: 0,
+        }),
+        DSLInstruction::Pow22501P1(RunDecompressData{
+            offset: scratch_space + 32,
+        }),
+        DSLInstruction::Pow22501P2(RunDecompressData{
+            offset: scratch_space + 64,
+        }),
+        DSLInstruction::DecompressEdwards(RunSplitComputeData{
+            offset: scratch_space,
+            step: 1,
+        }),
+    ]
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn compress_edwards_instructions(
+    input_offset: u32,
+    scratch_space: u32,
+) -> [DSLInstruction; 5] {
+    [
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset: input_offset.try_into().unwrap(),
+            compute_offset: scratch_space,
+            bytes: 128,
+        }),
+        DSLInstruction::CompressEdwards(RunSplitComputeData{
+            offset: scratch_space,
+            step: 0,
+        }),
+        DSLInstruction::Pow22501P1(RunDecompressData{
+            offset: scratch_space + 32 * 4,
+        }),
+        DSLInstruction::Pow22501P2(RunDecompressData{
+            offset: scratch_space + 32 * 5,
+        }),
+        DSLInstruction::CompressEdwards(RunSplitComputeData{
+            offset: scratch_space,
+            step: 1,
+        }),
+    ]
+}
+
+#[cfg(not(target_arch = "bpf"))]
+fn sqrt_ratio_i(
+    u: &FieldElement,
+    v: &FieldElement,
+) -> (bool, FieldElement) {
+    let x = FieldElement::sqrt_ratio_i_pow_p58_input(u, v);
+    let (t17, t13, _) = FieldElement::pow22001(&x);
+    let (ok, r) = FieldElement::sqrt_ratio_i_pow_p58_output(
+        u, v, &FieldElement::pow22501(&t17, &t13));
+    (ok.unwrap_u8() == 1, r)
+}
+
+// EdwardsPoint -> [ point, 1/Z ]. None if Z is zero
+#[cfg(not(target_arch = "bpf"))]
+pub fn compress_edwards_witness_input(
+    point: &[u8; 128],
+) -> Option<[u8; 160]> {
+    let point = EdwardsPoint::from_bytes(point);
+    if point.Z.is_zero().unwrap_u8() == 1 { return None; }
+
+    let mut res = [0u8; 160];
+    res[..128].copy_from_slice(&point.to_bytes());
+    res[128..].copy_from_slice(&point.Z.invert().to_bytes());
+    Some(res)
+}
+
+// CompressedEdwardsY -> [ compressed, x ]. None if it does not decompress
+#[cfg(not(target_arch = "bpf"))]
+pub fn decompress_edwards_witness_input(
+    compressed: &[u8; 32],
+) -> Option<[u8; 64]> {
+    let y = FieldElement::from_bytes(compressed);
+    let yy = y.square();
+    let u = &yy - &FieldElement::one();
+    let v = &(&yy * &crate::field::constants::EDWARDS_D) + &FieldElement::one();
+    let (ok, x) = sqrt_ratio_i(&u, &v);
+    if !ok { return None; }
+
+    let mut res = [0u8; 64];
+    res[..32].copy_from_slice(compressed);
+    res[32..].copy_from_slice(&x.to_bytes());
+    Some(res)
+}
+
+// r_0 -> [ r_0, sqrt_ratio_i(N_s, D) ] for elligator_to_curve_with_witness_instructions
+#[cfg(not(target_arch = "bpf"))]
+pub fn elligator_witness_input(
+    r_0: &[u8; 32],
+) -> [u8; 64] {
+    #[allow(non_snake_case)]
+    let (_, N_s, D) = crate::processor::elligator_ratio(&FieldElement::from_bytes(r_0));
+    let (_, s) = sqrt_ratio_i(&N_s, &D);
+
+    let mut res = [0u8; 64];
+    res[..32].copy_from_slice(r_0);
+    res[32..].copy_from_slice(&s.to_bytes());
+    res
+}
+
+// r_0 -> [ r_0, 1/(1+2r²), sqrt_ratio_i(eps, 1), 1/(u+1), x ] for
+// edwards_elligator_to_curve_with_witness_instructions. None if u is -1
+#[cfg(not(target_arch = "bpf"))]
+pub fn edwards_elligator_witness_input(
+    r_0: &[u8; 32],
+) -> Option<[u8; 32 * 5]> {
+    let one = FieldElement::one();
+
+    let d_1_inv = (&one + &FieldElement::from_bytes(r_0).square2()).invert();
+    let (d, eps) = crate::processor::montgomery_elligator_eps(&d_1_inv);
+    let (eps_is_sq, eps_sqrt) = sqrt_ratio_i(&eps, &one);
+
+    let u = crate::processor::montgomery_elligator_u(&d, (eps_is_sq as u8).into());
+    let u_plus_one = &u + &one;
+    if u_plus_one.is_zero().unwrap_u8() == 1 { return None; }
+    let u_plus_one_inv = u_plus_one.invert();
+
+    let y = &(&u - &one) * &u_plus_one_inv;
+    let decompress = decompress_edwards_witness_input(&y.to_bytes())?;
+
+    let mut res = [0u8; 32 * 5];
+    res[..32].copy_from_slice(r_0);
+    res[32..64].copy_from_slice(&d_1_inv.to_bytes());
+    res[64..96].copy_from_slice(&eps_sqrt.to_bytes());
+    res[96..128].copy_from_slice(&u_plus_one_inv.to_bytes());
+    res[128..].copy_from_slice(&decompress[32..]);
+    Some(res)
+}
+
+// compress_edwards_witness_input -> CompressedEdwardsY at `scratch_space + 32 * 5`
+#[cfg(not(target_arch = "bpf"))]
+pub fn compress_edwards_with_witness_instructions(
+    input_offset: u32,
+    scratch_space: u32,
+) -> [DSLInstruction; 2] {
+    [
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset,
+            compute_offset: scratch_space,
+            bytes: 160,
+        }),
+        DSLInstruction::CompressEdwardsWithWitness(RunDecompressData{
+            offset: scratch_space,
+        }),
+    ]
+}
+
+// decompress_edwards_witness_input -> EdwardsPoint at `scratch_space + 32 * 2`
+#[cfg(not(target_arch = "bpf"))]
+pub fn decompress_edwards_with_witness_instructions(
+    input_offset: u32,
+    scratch_space: u32,
+) -> [DSLInstruction; 2] {
+    [
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset,
+            compute_offset: scratch_space,
+            bytes: 64,
+        }),
+        DSLInstruction::DecompressEdwardsWithWitness(RunDecompressData{
+            offset: scratch_space,
+        }),
+    ]
+}
+
+// elligator_witness_input -> RistrettoPoint at `scratch_space + 32 * 2`
+#[cfg(not(target_arch = "bpf"))]
+pub fn elligator_to_curve_with_witness_instructions(
+    input_offset: u32,
+    scratch_space: u32,
+) -> [DSLInstruction; 2] {
+    [
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset,
+            compute_offset: scratch_space,
+            bytes: 64,
+        }),
+        DSLInstruction::ElligatorWithWitness(RunDecompressData{
+            offset: scratch_space,
+        }),
+    ]
+}
+
+// edwards_elligator_witness_input -> EdwardsPoint at `scratch_space + 32 * 7`.
+// Same result as edwards_elligator_to_curve_instructions
+#[cfg(not(target_arch = "bpf"))]
+pub fn edwards_elligator_to_curve_with_witness_instructions(
+    input_offset: u32,
+    scratch_space: u32,
+) -> [DSLInstruction; 7] {
+    [
+        // [ r_0, 1/(1+2r²), sqrt_ratio_i(eps, 1) ], u at +32 * 3
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset,
+            compute_offset: scratch_space,
+            bytes: 32 * 3,
+        }),
+        // [ u, 1/(u+1) ], y at +32 * 5
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset: input_offset + 32 * 3,
+            compute_offset: scratch_space + 32 * 4,
+            bytes: 32,
+        }),
+        // [ y, x ], point at +32 * 7
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset: input_offset + 32 * 4,
+            compute_offset: scratch_space + 32 * 6,
+            bytes: 32,
+        }),
+        DSLInstruction::MontgomeryElligatorWithWitness(RunSplitComputeData{
+            offset: scratch_space,
+            step: 2,
+        }),
+        DSLInstruction::MontgomeryToEdwardsWithWitness(MontgomeryToEdwardsData{
+            offset: scratch_space + 32 * 3,
+            sign_offset: scratch_space + 31,
+            step: 0,
+        }),
+        DSLInstruction::DecompressEdwardsWithWitness(RunDecompressData{
+            offset: scratch_space + 32 * 5,
+        }),
+        // in place
+        DSLInstruction::MulByCofactor(BuildLookupTableData{
+            point_offset: scratch_space + 32 * 7,
+            table_offset: scratch_space + 32 * 7,
+        }),
+    ]
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn batch_invert_state_len(
+    num_elements: usize,
+) -> usize {
+    32 * (6 + num_elements)
+}
+
+// inverts the FieldElements at `elements_offset + stride * i` in place with
+// a single Pow22501P1 and Pow22501P2
+#[cfg(not(target_arch = "bpf"))]
+pub fn batch_invert_instructions(
+    num_elements: usize,
+    elements_offset: usize,
+    stride: usize,
+    state_offset: usize,
+) -> Vec<DSLInstruction> {
+    assert!(num_elements > 0 && num_elements <= u8::MAX as usize);
+    assert!(stride >= 32);
+
+    let data = |start: usize, end: usize| BatchInvertData{
+        elements_offset: elements_offset.try_into().unwrap(),
+        state_offset: state_offset.try_into().unwrap(),
+        stride: stride.try_into().unwrap(),
+        start: start as u8,
+        end: end as u8,
+    };
+
+    let mut instructions = vec![];
+    let mut start = 0;
+    while start < num_elements {
+        let end = (start + BATCH_INVERT_PREFIX_PER_CRANK as usize).min(num_elements);
+        instructions.push(DSLInstruction::BatchInvertPrefix(data(start, end)));
+        start = end;
+    }
+
+    instructions.push(
+        DSLInstruction::Pow22501P1(RunDecompressData{
+            offset: state_offset.try_into().unwrap(),
+        }),
+    );
+    instructions.push(
+        DSLInstruction::Pow22501P2(RunDecompressData{
+            offset: (state_offset + 32).try_into().unwrap(),
+        }),
+    );
+    instructions.push(
+        DSLInstruction::InvertFini(RunDecompressData{
+            offset: state_offset.try_into().unwrap(),
+        }),
+    );
+
+    let mut end = num_elements;
+    while end > 0 {
+        let start = end.saturating_sub(BATCH_INVERT_PER_CRANK as usize);
+        instructions.push(DSLInstruction::BatchInvert(data(start, end)));
+        end = start;
+    }
+    instructions
+}
+
+// bytes used by each point in batch_compress_edwards_instructions. Same layout
+// as compress_edwards_instructions
+#[cfg(not(target_arch = "bpf"))]
+pub const COMPRESS_EDWARDS_SIZE: usize = 32 * 10;
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn batch_compress_edwards_scratch_len(
+    num_points: usize,
+) -> usize {
+    COMPRESS_EDWARDS_SIZE * num_points + batch_invert_state_len(num_points)
+}
+
+// EdwardsPoints at `input_offset` -> CompressedEdwardsY at
+// `scratch_space + COMPRESS_EDWARDS_SIZE * i + 32 * 9`
+#[cfg(not(target_arch = "bpf"))]
+pub fn batch_compress_edwards_instructions(
+    num_points: usize,
+    input_offset: usize,
+    scratch_space: usize,
+) -> Vec<DSLInstruction> {
+    let point_offset = |i: usize| -> u32 {
+        (scratch_space + COMPRESS_EDWARDS_SIZE * i).try_into().unwrap()
+    };
+
+    let mut instructions = vec![];
+    for i in 0..num_points {
+        instructions.push(
+            DSLInstruction::CopyInput(CopyInputData{
+                input_offset: (input_offset + 128 * i).try_into().unwrap(),
+                compute_offset: point_offset(i),
+                bytes: 128,
+            }),
+        );
+        instructions.push(
+            DSLInstruction::CompressEdwards(RunSplitComputeData{
+                offset: point_offset(i),
+                step: 0,
+            }),
+        );
+    }
+
+    instructions.extend(
+        batch_invert_instructions(
+            num_points,
+            scratch_space + 32 * 4,
+            COMPRESS_EDWARDS_SIZE,
+            scratch_space + COMPRESS_EDWARDS_SIZE * num_points,
+        )
+    );
+
+    for i in 0..num_points {
+        instructions.push(
+            DSLInstruction::CompressEdwards(RunSplitComputeData{
+                offset: point_offset(i),
+                step: 2,
+            }),
+        );
+    }
+    instructions
+}
+
+// packed radix 16 scalar at `scalar_offset` times the basepoint -> EdwardsPoint
+// at `result_offset`
+#[cfg(not(target_arch = "bpf"))]
+pub fn basepoint_mul_instructions(
+    scalar_offset: u32,
+    result_offset: u32,
+) -> Vec<DSLInstruction> {
+    let mut instructions = vec![
+        DSLInstruction::WriteEdwardsIdentity(RunDecompressData{
+            offset: result_offset,
+        }),
+    ];
+    let mut start = 0;
+    while start < EdwardsBasepointTable::MUL_STEPS {
+        let end = (start + BASEPOINT_MUL_STEPS_PER_CRANK).min(EdwardsBasepointTable::MUL_STEPS);
+        instructions.push(
+            DSLInstruction::BasepointMul(BasepointMulData{
+                start,
+                end,
+                scalar_offset,
+                result_offset,
+            }),
+        );
+        start = end;
+    }
+    instructions
+}
+
+// RistrettoPoint -> CompressedRistretto at `scratch_space + 32 * 9`
+#[cfg(not(target_arch = "bpf"))]
+pub fn compress_ristretto_instructions(
+    input_offset: u32,
+    scratch_space: u32,
+) -> [DSLInstruction; 5] {
+    [
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset: input_offset.try_into().unwrap(),
+            compute_offset: scratch_space,
+            bytes: 128,
+        }),
+        DSLInstruction::CompressRistretto(RunSplitComputeData{
+            offset: scratch_space,
+            step: 0,
+        }),
+        DSLInstruction::Pow22501P1(RunDecompressData{
+            offset: scratch_space + 32 * 4,
+        }),
+        DSLInstruction::Pow22501P2(RunDecompressData{
+            offset: scratch_space + 32 * 5,
+        }),
+        DSLInstruction::CompressRistretto(RunSplitComputeData{
+            offset: scratch_space,
+            step: 1,
+        }),
+    ]
+}
+
+// canonical scalar at `offset` -> inverse at `offset + 32 * 11`
+#[cfg(not(target_arch = "bpf"))]
+pub fn scalar_invert_instructions(
+    offset: u32,
+) -> Vec<DSLInstruction> {
+    (0..SCALAR_INVERT_STEPS).map(|step| {
+        DSLInstruction::ScalarInvert(RunSplitComputeData{
+            offset,
+            step,
+        })
+    }).collect()
+}
+
+// `num_scalars` canonical nonzero scalars at `offset` are replaced by their
+// inverses. Uses 32 * (2 * num_scalars + 12) bytes starting at `offset`
+#[cfg(not(target_arch = "bpf"))]
+pub fn batch_scalar_invert_instructions(
+    offset: u32,
+    num_scalars: u8,
+) -> Vec<DSLInstruction> {
+    let mut instructions = vec![
+        DSLInstruction::BatchScalarInvert(BatchScalarInvertData{
+            offset,
+            num_scalars,
+            step: 0,
+        }),
+    ];
+    instructions.extend_from_slice(
+        &scalar_invert_instructions(offset + 32 * 2 * num_scalars as u32),
+    );
+    instructions.push(
+        DSLInstruction::BatchScalarInvert(BatchScalarInvertData{
+            offset,
+            num_scalars,
+            step: 1,
+        }),
+    );
+    instructions
+}
+
+// SHA-512 of the concatenated compute buffer ranges (offset, len) written to
+// `scratch_space + STATE_SIZE`. Uses STATE_SIZE + DIGEST_SIZE bytes of scratch
+// space
+#[cfg(not(target_arch = "bpf"))]
+pub fn sha512_instructions(
+    ranges: &[(u32, u32)],
+    scratch_space: u32,
+) -> Vec<DSLInstruction> {
+    let state_offset = scratch_space;
+    let digest_offset = scratch_space + crate::sha512::STATE_SIZE as u32;
+
+    let mut instructions = vec![
+        DSLInstruction::Sha512Init(RunDecompressData{
+            offset: state_offset,
+        }),
+    ];
+
+    for &(range_offset, range_len) in ranges.iter() {
+        let mut data_offset = range_offset;
+        let range_end = range_offset + range_len;
+        while data_offset < range_end {
+            let bytes = (range_end - data_offset).min(MAX_SHA512_UPDATE_BYTES as u32);
+            instructions.push(
+                DSLInstruction::Sha512Update(Sha512UpdateData{
+                    state_offset,
+                    data_offset,
+                    bytes,
+                }),
+            );
+            data_offset += bytes;
+        }
+    }
+
+    instructions.push(
+        DSLInstruction::Sha512Fini(Sha512FiniData{
+            state_offset,
+            digest_offset,
+        }),
+    );
+
+    instructions
+}
+
+// SHA-512 of the concatenated compute buffer ranges (offset, len) reduced to a
+// packed radix 16 scalar at `scalar_offset`. Uses STATE_SIZE + DIGEST_SIZE
+// bytes of scratch space
+#[cfg(not(target_arch = "bpf"))]
+pub fn sha512_challenge_instructions(
+    ranges: &[(u32, u32)],
+    scratch_space: u32,
+    scalar_offset: u32,
+) -> Vec<DSLInstruction> {
+    let mut instructions = sha512_instructions(ranges, scratch_space);
+    instructions.push(
+        DSLInstruction::PackedScalarFromWide(ScalarFromWideData{
+            wide_offset: scratch_space + crate::sha512::STATE_SIZE as u32,
+            scalar_offset,
+        }),
+    );
+
+    instructions
+}
+
+// compute buffer is laid out as
+// [
+//   ..header..,
+//   ..result..,
+//   S, -k,                     // packed radix 16
+//   ..tables..,                // B, A
+//   ..R_decompress..,
+//   ..A_decompress..,
+//   B,
+//   k,
+//   ..sha512_scratch..,
+//   R, A, ..message..          // hash input
+// ]
+#[cfg(not(target_arch = "bpf"))]
+struct Ed25519VerifyLayout {
+    result: u32,
+    scalars: u32,
+    tables: u32,
+    r_scratch: u32,
+    a_scratch: u32,
+    basepoint: u32,
+    k: u32,
+    sha512_scratch: u32,
+    hash_input: u32,
+    end: u32,
+}
+
+#[cfg(not(target_arch = "bpf"))]
+impl Ed25519VerifyLayout {
+    fn new(msg_len: u32) -> Self {
+        let table_size = LookupTable::<ProjectiveNielsPoint>::TABLE_SIZE as u32;
+        let result = HEADER_SIZE as u32;
+        let scalars = result + 128;
+        let tables = scalars + 32 * 2;
+        let r_scratch = tables + table_size * 2;
+        let a_scratch = r_scratch + 32 * 10;
+        let basepoint = a_scratch + 32 * 10;
+        let k = basepoint + 128;
+        let sha512_scratch = k + 32;
+        let hash_input = sha512_scratch
+            + (crate::sha512::STATE_SIZE + crate::sha512::DIGEST_SIZE) as u32;
+        let end = hash_input + 64 + msg_len;
+        Self {
+            result,
+            scalars,
+            tables,
+            r_scratch,
+            a_scratch,
+            basepoint,
+            k,
+            sha512_scratch,
+            hash_input,
+            end,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub fn ed25519_verify_compute_buffer_len(
+    msg_len: u32,
+) -> usize {
+    Ed25519VerifyLayout::new(msg_len).end as usize
+}
+
+// Checks [8][S]B == [8]R + [8][k]A with k = SHA-512(R || A || M) for the
+// public key A at `pubkey_offset`, signature (R, S) at `sig_offset` and
+// message M at `msg_range` of the input buffer. The result is written to the
+// compute header verdict
+#[cfg(not(target_arch = "bpf"))]
+pub fn ed25519_verify_instructions(
+    pubkey_offset: u32,
+    sig_offset: u32,
+    msg_range: std::ops::Range<u32>,
+) -> Vec<DSLInstruction> {
+    let msg_len = msg_range.end - msg_range.start;
+    let layout = Ed25519VerifyLayout::new(msg_len);
+    let table_size = LookupTable::<ProjectiveNielsPoint>::TABLE_SIZE as u32;
+
+    // decompress_edwards_instructions output
+    let r_point = layout.r_scratch + 32 * 6;
+    let a_point = layout.a_scratch + 32 * 6;
+
+    let mut instructions = vec![];
+
+    // hash input
+    instructions.push(
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset: sig_offset,
+            compute_offset: layout.hash_input,
+            bytes: 32,
+        }),
+    );
+    instructions.push(
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset: pubkey_offset,
+            compute_offset: layout.hash_input + 32,
+            bytes: 32,
+        }),
+    );
+    instructions.push(
+        DSLInstruction::CopyInput(CopyInputData{
+            input_offset: msg_range.start,
+            compute_offset: layout.hash_input + 64,
+            bytes: msg_len,
+        }),
+    );
+
+    // points and tables
+    instructions.extend_from_slice(
+        &decompress_edwards_instructions(sig_offset, layout.r_scratch),
+    );
+    instructions.extend_from_slice(
+        &decompress_edwards_instructions(pubkey_offset, layout.a_scratch),
+    );
+    instructions.push(
+        DSLInstruction::WriteEdwardsBasepoint(RunDecompressData{
+            offset: layout.basepoint,
+        }),
+    );
+    instructions.push(
+        DSLInstruction::BuildLookupTable(BuildLookupTableData{
+            point_offset: layout.basepoint,
+            table_offset: layout.tables,
+        }),
+    );
+    instructions.push(
+        DSLInstruction::BuildLookupTable(BuildLookupTableData{
+            point_offset: a_point,
+            table_offset: layout.tables + table_size,
+        }),
+    );
+
+    // scalars. S must be canonical
+    instructions.push(
+        DSLInstruction::LoadScalar(LoadScalarData{
+            input_offset: sig_offset + 32,
+            compute_offset: layout.scalars,
+        }),
+    );
+    instructions.extend_from_slice(
+        &sha512_instructions(&[(layout.hash_input, 64 + msg_len)], layout.sha512_scratch),
+    );
+    instructions.push(
+        DSLInstruction::ScalarFromWide(ScalarFromWideData{
+            wide_offset: layout.sha512_scratch + crate::sha512::STATE_SIZE as u32,
+            scalar_offset: layout.k,
+        }),
+    );
+    instructions.push(
+        DSLInstruction::ScalarNeg(ScalarUnaryOpData{
+            scalar_offset: layout.k,
+            result_offset: layout.k,
+        }),
+    );
+    instructions.push(
+        DSLInstruction::PackScalar(ScalarUnaryOpData{
+            scalar_offset: layout.k,
+            result_offset: layout.scalars + 32,
+        }),
+    );
+
+    // [S]B - [k]A
+    instructions.push(
+        DSLInstruction::WriteEdwardsIdentity(RunDecompressData{
+            offset: layout.result,
+        }),
+    );
+    for iter in (0..64).rev() {
+        instructions.push(
+            DSLInstruction::MultiscalarMul(MultiscalarMulData{
+                start: iter as u8,
+                end: iter + 1 as u8,
+                num_inputs: 2,
+                scalars_offset: layout.scalars,
+                tables_offset: layout.tables,
+                result_offset: layout.result,
+            })
+        );
+    }
+
+    // [8]([S]B - [k]A - R) should be the identity. this is in the prime order
+    // subgroup so the ristretto identity check is exact
+    instructions.push(
+        DSLInstruction::EdwardsSub(EdwardsBinaryOpData{
+            lhs_offset: layout.result,
+            rhs_offset: r_point,
+            result_offset: layout.result,
+        }),
+    );
+    instructions.push(
+        DSLInstruction::MulByCofactor(BuildLookupTableData{
+            point_offset: layout.result,
+            table_offset: layout.result,
+        }),
+    );
+    instructions.push(
+        DSLInstruction::AssertIdentity(RunDecompressData{
+            offset: layout.result,
+        }),
+    );
+
+    instructions
+}
+
+// Lays out DSL instructions mirroring the calls a prover makes on a
+// `transcript::Transcript`. Labels are written into `label_space` with
+// WriteConstant the first time they're used
+#[cfg(not(target_arch = "bpf"))]
+pub struct DSLTranscript {
+    pub instructions: Vec<DSLInstruction>,
+    transcript_offset: u32,
+    label_space: u32,
+    labels: Vec<(Vec<u8>, u32)>,
+}
+
+#[cfg(not(target_arch = "bpf"))]
+impl DSLTranscript {
+    // needs TRANSCRIPT_SIZE bytes at `transcript_offset`
+    pub fn new(
+        label: &[u8],
+        transcript_offset: u32,
+        label_space: u32,
+    ) -> Self {
+        let mut transcript = Self {
+            instructions: vec![],
+            transcript_offset,
+            label_space,
+            labels: vec![],
+        };
+        let label_offset = transcript.write_label(label);
+        transcript.instructions.push(
+            DSLInstruction::TranscriptInit(TranscriptData{
+                transcript_offset,
+                label_offset,
+                label_len: label.len().try_into().unwrap(),
+                data_offset: 0,
+                data_len: 0,
+            }),
+        );
+        transcript
+    }
+
+    // end of the labels written so far
+    pub fn label_space_end(&self) -> u32 {
+        self.label_space
+    }
+
+    pub fn append_message(
+        &mut self,
+        label: &[u8],
+        data_offset: u32,
+        data_len: u16,
+    ) {
+        let label_offset = self.write_label(label);
+        self.instructions.push(
+            DSLInstruction::TranscriptAppendMessage(TranscriptData{
+                transcript_offset: self.transcript_offset,
+                label_offset,
+                label_len: label.len().try_into().unwrap(),
+                data_offset,
+                data_len,
+            }),
+        );
+    }
+
+    pub fn append_domain_separator(
+        &mut self,
+        label: &[u8],
+    ) {
+        let label_offset = self.write_label(label);
+        self.append_message(b"dom-sep", label_offset, label.len().try_into().unwrap());
+    }
+
+    // compressed point bytes
+    pub fn append_point(
+        &mut self,
+        label: &[u8],
+        point_offset: u32,
+    ) {
+        self.append_message(label, point_offset, 32);
+    }
+
+    // canonical scalar bytes
+    pub fn append_scalar(
+        &mut self,
+        label: &[u8],
+        scalar_offset: u32,
+    ) {
+        self.append_message(label, scalar_offset, 32);
+    }
+
+    pub fn challenge_bytes(
+        &mut self,
+        label: &[u8],
+        dest_offset: u32,
+        dest_len: u16,
+    ) {
+        let label_offset = self.write_label(label);
+        self.instructions.push(
+            DSLInstruction::TranscriptChallengeBytes(TranscriptData{
+                transcript_offset: self.transcript_offset,
+                label_offset,
+                label_len: label.len().try_into().unwrap(),
+                data_offset: dest_offset,
+                data_len: dest_len,
+            }),
+        );
+    }
+
+    // writes the packed radix 16 challenge to `scalar_offset`. Uses 64 bytes
+    // of scratch space
+    pub fn challenge_scalar(
+        &mut self,
+        label: &[u8],
+        scratch_space: u32,
+        scalar_offset: u32,
+    ) {
+        self.challenge_bytes(label, scratch_space, 64);
+        self.instructions.push(
+            DSLInstruction::PackedScalarFromWide(ScalarFromWideData{
+                wide_offset: scratch_space,
+                scalar_offset,
+            }),
+        );
+    }
+
+    fn write_label(
+        &mut self,
+        label: &[u8],
+    ) -> u32 {
+        if let Some((_, offset)) = self.labels.iter().find(|(l, _)| l.as_slice() == label) {
+            return *offset;
+        }
+
+        let offset = self.label_space;
+        for (i, chunk) in label.chunks(MAX_CONSTANT_BYTES).enumerate() {
+            let mut bytes = [0; MAX_CONSTANT_BYTES];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            self.instructions.push(
+                DSLInstruction::WriteConstant(WriteConstantData{
+                    offset: offset + (i * MAX_CONSTANT_BYTES) as u32,
+                    len: chunk.len() as u8,
+                    bytes,
+                }),
+            );
+        }
+        self.label_space += label.len() as u32;
+        self.labels.push((label.to_vec(), offset));
+        offset
+    }
+}
+
 #[cfg(not(target_arch = "bpf"))]
 pub fn dsl_instructions_to_bytes(
     instructions: &[DSLInstruction]
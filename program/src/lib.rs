@@ -1,6 +1,8 @@
 mod entrypoint;
+pub mod error;
 pub mod processor;
 pub mod instruction;
+pub mod cpi;
 
 // Internal macros. Must come first!
 #[macro_use]
@@ -12,6 +14,8 @@ pub mod edwards;
 pub mod field;
 pub mod ristretto;
 pub mod scalar;
+pub mod sha512;
+pub mod transcript;
 pub mod traits;
 pub mod window;
 
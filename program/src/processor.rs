@@ -1,11 +1,15 @@
 #![allow(non_snake_case)]
 
 use crate::{
+    error::Curve25519Error,
     instruction::*,
     field::*,
     ristretto::*,
     window::*,
     edwards::*,
+    scalar::{self, Scalar},
+    sha512,
+    transcript,
 };
 
 use solana_program::{
@@ -46,6 +50,13 @@ pub fn process_instruction(
                     key: Key::InstructionBufferV1,
                     authority,
                     finalized: false,
+                    table_buffer: if input.len() >= 33 {
+                        Pubkey::new(&input[1..33])
+                    } else {
+                        Pubkey::default()
+                    },
+                    num_instructions: 0,
+                    num_compute_buffers: 0,
                 },
             )
         }
@@ -57,20 +68,33 @@ pub fn process_instruction(
                     key: Key::InputBufferV1,
                     authority,
                     finalized: false,
+                    num_compute_buffers: 0,
                 },
             )
         }
         Curve25519Instruction::InitializeComputeBuffer => {
             msg!("InitializeComputeBuffer");
+            let instruction_buffer = Pubkey::new(&input[1..33]);
+            let input_buffer = Pubkey::new(&input[33..65]);
             process_initialize_buffer(
                 accounts,
                 |authority| ComputeHeader {
                     key: Key::ComputeBufferV1,
                     instruction_num: 0,
                     authority,
-                    instruction_buffer: Pubkey::new(&input[1..33]),
-                    input_buffer: Pubkey::new(&input[33..65]),
+                    instruction_buffer,
+                    input_buffer,
+                    verdict: Verdict::Pending,
+                    completed: false,
+                    soft_fail: input.get(65).map_or(false, |b| *b != 0),
+                    rejected_instruction: None,
                 },
+            )?;
+            update_compute_buffer_refs(
+                accounts.get(3..).unwrap_or(&[]),
+                &instruction_buffer,
+                &input_buffer,
+                true,
             )
         }
         Curve25519Instruction::CloseBuffer => {
@@ -90,56 +114,131 @@ pub fn process_instruction(
         }
         Curve25519Instruction::CrankCompute => {
             msg!("CrankCompute");
+            // the expected instruction_num is all or nothing so that a
+            // truncated one can't turn off the check
+            if input.len() > 2 && input.len() < 6 {
+                msg!("Truncated expected instruction num");
+                return Err(ProgramError::InvalidInstructionData);
+            }
             process_dsl_instruction(
                 accounts,
+                *input.get(1).unwrap_or(&1), // max steps. 1 if unset
+                input.get(2..6).map(bytes_as_u32).transpose()?, // expected instruction_num
             )
         }
         Curve25519Instruction::Noop => {
             msg!("Noop");
             Ok(())
         }
+        Curve25519Instruction::VerifyComputeBuffer => {
+            msg!("VerifyComputeBuffer");
+            process_verify_compute_buffer(
+                accounts,
+                &Pubkey::new(&input[1..33]),
+                &Pubkey::new(&input[33..65]),
+            )
+        }
+        Curve25519Instruction::InitializeTableBuffer => {
+            msg!("InitializeTableBuffer");
+            process_initialize_buffer(
+                accounts,
+                |authority| TableHeader {
+                    key: Key::TableBufferV1,
+                    authority,
+                    finalized: false,
+                    num_tables: 0,
+                },
+            )
+        }
+        Curve25519Instruction::BuildTables => {
+            msg!("BuildTables");
+            process_build_tables(
+                accounts,
+                bytes_as_u32(&input[1..5])?,
+                &input[5..],
+            )
+        }
     }
 }
 
+fn process_verify_compute_buffer(
+    accounts: &[AccountInfo],
+    expected_instruction_buffer: &Pubkey,
+    expected_input_buffer: &Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let compute_buffer_info = next_account_info(account_info_iter)?;
+
+    // [0] if verified, otherwise 1 followed by the little-endian
+    // Curve25519Error code. Other errors fail the instruction
+    let result = match crate::cpi::verify_completed_compute_buffer(
+        compute_buffer_info,
+        expected_instruction_buffer,
+        expected_input_buffer,
+    ) {
+        Ok(_) => vec![0],
+        Err(ProgramError::Custom(code)) => {
+            let mut result = vec![1];
+            result.extend_from_slice(&code.to_le_bytes());
+            result
+        }
+        Err(err) => return Err(err),
+    };
+
+    solana_program::program::set_return_data(&result);
+
+    Ok(())
+}
+
 fn process_dsl_instruction(
     accounts: &[AccountInfo],
+    max_steps: u8,
+    expected_instruction_num: Option<u32>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let instruction_buffer_info = next_account_info(account_info_iter)?;
     // kind of sucks that this always needs to be passed in...
     let input_buffer_info = next_account_info(account_info_iter)?;
     let compute_buffer_info = next_account_info(account_info_iter)?;
+    let _system_program_info = account_info_iter.next();
+    // only needed for MultiscalarMulTableBuffer
+    let table_buffer_info = account_info_iter.next();
+
+    if max_steps == 0 {
+        msg!("Invalid step count");
+        return Err(Curve25519Error::InvalidStepCount.into());
+    }
 
     if *instruction_buffer_info.owner != crate::ID || instruction_buffer_info.is_writable {
         msg!("Bad instruction buffer {} vs {}", instruction_buffer_info.owner, crate::ID);
-        return Err(ProgramError::InvalidArgument);
+        return Err(Curve25519Error::InvalidBufferOwner.into());
     }
     if *input_buffer_info.owner != crate::ID {
         msg!("Bad input buffer");
-        return Err(ProgramError::InvalidArgument);
+        return Err(Curve25519Error::InvalidBufferOwner.into());
     }
     if *compute_buffer_info.owner != crate::ID {
         msg!("Bad compute buffer");
-        return Err(ProgramError::InvalidArgument);
+        return Err(Curve25519Error::InvalidBufferOwner.into());
     }
 
     // deserialize headers and verify
-    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
-    let mut compute_header = {
+    let compute_buffer_data = compute_buffer_info.try_borrow_data()?;
+    let compute_header = {
         let mut compute_buffer_ptr: &[u8] = *compute_buffer_data;
         ComputeHeader::deserialize(&mut compute_buffer_ptr)?
     };
     if compute_header.key != Key::ComputeBufferV1 {
         msg!("Invalid compute buffer type");
-        return Err(ProgramError::InvalidArgument);
+        return Err(Curve25519Error::InvalidBufferType.into());
     }
     if compute_header.instruction_buffer != *instruction_buffer_info.key {
         msg!("Mismatched instruction buffer {} vs {}", compute_header.instruction_buffer, *instruction_buffer_info.key);
-        return Err(ProgramError::InvalidArgument);
+        return Err(Curve25519Error::MismatchedBuffer.into());
     }
     if compute_header.input_buffer != *input_buffer_info.key {
         msg!("Mismatched input buffer");
-        return Err(ProgramError::InvalidArgument);
+        return Err(Curve25519Error::MismatchedBuffer.into());
     }
 
     let instruction_buffer_data = instruction_buffer_info.try_borrow_data()?;
@@ -149,29 +248,109 @@ fn process_dsl_instruction(
     };
     if instruction_header.key != Key::InstructionBufferV1 {
         msg!("Invalid instruction buffer type");
-        return Err(ProgramError::InvalidArgument);
+        return Err(Curve25519Error::InvalidBufferType.into());
     }
     if !instruction_header.finalized {
         msg!("Instruction buffer not finalized");
-        return Err(ProgramError::InvalidArgument);
+        return Err(Curve25519Error::BufferNotFinalized.into());
     }
 
 
-    // find instruction and increment counter
-    let instruction_offset = HEADER_SIZE + INSTRUCTION_SIZE * compute_header.instruction_num as usize;
-    let mut instruction_data = &instruction_buffer_data[
-        instruction_offset..instruction_offset+INSTRUCTION_SIZE
-    ];
-
-    compute_header.instruction_num += 1;
-    // TODO: directly doing serialize like
-    //   compute_header.serialize(&mut *compute_buffer_data)?;
-    // seems to do weird things...
-    let compute_header_bytes = compute_header.try_to_vec()?;
-    compute_buffer_data[..compute_header_bytes.len()].copy_from_slice(
-        compute_header_bytes.as_slice());
+    let num_instructions = instruction_header.num_instructions as usize;
+    if HEADER_SIZE + num_instructions * INSTRUCTION_SIZE > instruction_buffer_data.len() {
+        msg!("Instruction buffer too short");
+        return Err(Curve25519Error::InvalidLength.into());
+    }
+    if compute_header.completed || compute_header.instruction_num as usize >= num_instructions {
+        msg!("Compute buffer already completed");
+        return Err(Curve25519Error::ComputeBufferCompleted.into());
+    }
+    // so that retried or duplicated cranks don't run extra instructions
+    if let Some(expected_instruction_num) = expected_instruction_num {
+        if compute_header.instruction_num != expected_instruction_num {
+            msg!("Mismatched instruction num {} vs {}", compute_header.instruction_num, expected_instruction_num);
+            return Err(Curve25519Error::MismatchedInstructionNum.into());
+        }
+    }
     drop(compute_buffer_data);
 
+    // run until `max_steps` instructions have run or the buffer completes
+    for _ in 0..max_steps {
+        // the header is re-read each step since the Assert* instructions
+        // update the verdict
+        let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+        let mut compute_header = {
+            let mut compute_buffer_ptr: &[u8] = *compute_buffer_data;
+            ComputeHeader::deserialize(&mut compute_buffer_ptr)?
+        };
+        if compute_header.completed {
+            break;
+        }
+        let instruction_num = compute_header.instruction_num;
+
+        // find instruction and increment counter
+        let instruction_offset = HEADER_SIZE + INSTRUCTION_SIZE * compute_header.instruction_num as usize;
+        let instruction_data = &instruction_buffer_data[
+            instruction_offset..instruction_offset+INSTRUCTION_SIZE
+        ];
+
+        compute_header.instruction_num += 1;
+        compute_header.completed = compute_header.instruction_num as usize == num_instructions;
+        // TODO: directly doing serialize like
+        //   compute_header.serialize(&mut *compute_buffer_data)?;
+        // seems to do weird things...
+        let compute_header_bytes = compute_header.try_to_vec()?;
+        compute_buffer_data[..compute_header_bytes.len()].copy_from_slice(
+            compute_header_bytes.as_slice());
+        drop(compute_buffer_data);
+
+        match process_dsl_step(
+            instruction_data,
+            instruction_num,
+            &instruction_header,
+            input_buffer_info,
+            compute_buffer_info,
+            table_buffer_info,
+        ) {
+            Err(err) if compute_header.soft_fail && is_invalid_input(&err) => {
+                msg!("Rejected at instruction {}", instruction_num);
+                let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+                let mut compute_header = {
+                    let mut compute_buffer_ptr: &[u8] = *compute_buffer_data;
+                    ComputeHeader::deserialize(&mut compute_buffer_ptr)?
+                };
+                compute_header.verdict = Verdict::Rejected;
+                compute_header.rejected_instruction = Some(instruction_num);
+                compute_header.completed = true;
+                let compute_header_bytes = compute_header.try_to_vec()?;
+                compute_buffer_data[..compute_header_bytes.len()].copy_from_slice(
+                    compute_header_bytes.as_slice());
+                break;
+            }
+            res => res?,
+        }
+    }
+
+    Ok(())
+}
+
+// soft_fail compute buffers are rejected on these instead of failing the crank
+fn is_invalid_input(err: &ProgramError) -> bool {
+    match err {
+        ProgramError::Custom(code) => Curve25519Error::from_u32(*code)
+            .map_or(false, |e| e.is_invalid_input()),
+        _ => false,
+    }
+}
+
+fn process_dsl_step<'a>(
+    mut instruction_data: &[u8],
+    instruction_num: u32,
+    instruction_header: &InstructionHeader,
+    input_buffer_info: &AccountInfo<'a>,
+    compute_buffer_info: &AccountInfo<'a>,
+    table_buffer_info: Option<&AccountInfo<'a>>,
+) -> ProgramResult {
     match DSLInstruction::deserialize(&mut instruction_data)? {
         DSLInstruction::CopyInput(offsets) => {
             msg!("CopyInput");
@@ -181,6 +360,14 @@ fn process_dsl_instruction(
                 &offsets,
             )
         }
+        DSLInstruction::LoadScalar(data) => {
+            msg!("LoadScalar");
+            process_load_scalar(
+                input_buffer_info,
+                compute_buffer_info,
+                &data,
+            )
+        }
         DSLInstruction::WriteEdwardsIdentity(RunDecompressData { offset }) => {
             msg!("WriteEdwardsIdentity");
             process_write_edwards_identity(
@@ -188,6 +375,13 @@ fn process_dsl_instruction(
                 offset,
             )
         }
+        DSLInstruction::WriteEdwardsBasepoint(RunDecompressData { offset }) => {
+            msg!("WriteEdwardsBasepoint");
+            process_write_edwards_basepoint(
+                compute_buffer_info,
+                offset,
+            )
+        }
 
         // [
         //   x,
@@ -261,6 +455,14 @@ fn process_dsl_instruction(
                 step,
             )
         }
+        DSLInstruction::CompressRistretto(RunSplitComputeData{ offset, step }) => {
+            msg!("CompressRistretto {}", step);
+            process_compress_ristretto(
+                compute_buffer_info,
+                offset,
+                step,
+            )
+        }
         DSLInstruction::Elligator(RunSplitComputeData{ offset, step }) => {
             msg!("Elligator {}", step);
             process_elligator(
@@ -308,6 +510,7 @@ fn process_dsl_instruction(
             msg!("MultiscalarMul");
             process_multiscalar_mul(
                 compute_buffer_info,
+                None,
                 &data,
             )
         }
@@ -319,744 +522,2936 @@ fn process_dsl_instruction(
                 offset,
             )
         }
-    }
-}
-
-fn process_initialize_buffer<F, T: BorshSerialize>(
-    accounts: &[AccountInfo],
-    header_fn: F,
-) -> ProgramResult
-where
-    F: FnOnce(Pubkey) -> T,
-{
-    let account_info_iter = &mut accounts.iter();
-    let buffer_info = next_account_info(account_info_iter)?;
-    let authority_info = next_account_info(account_info_iter)?;
-    let _system_program_info = next_account_info(account_info_iter)?;
-
-    if !authority_info.is_signer {
-        msg!("Authority is not a signer");
-        return Err(ProgramError::InvalidArgument);
-    }
-
-    use solana_program::sysvar::Sysvar;
-    let rent = solana_program::rent::Rent::get()?;
-    if !rent.is_exempt(buffer_info.lamports(), buffer_info.data_len()) {
-        msg!("Buffer is not rent exempt");
-        return Err(ProgramError::InvalidArgument);
-    }
-
-    let mut buffer_data = buffer_info.try_borrow_mut_data()?;
-
-    if buffer_data[0] != Key::Uninitialized as u8 {
-        msg!("Buffer already initialized");
-        return Err(ProgramError::InvalidArgument);
-    }
-
-    // TODO: does this write correctly?
-    header_fn(*authority_info.key).serialize(&mut *buffer_data)?;
-
-    Ok(())
-}
-
-fn process_close_buffer(
-    accounts: &[AccountInfo],
-) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let buffer_info = next_account_info(account_info_iter)?;
-    let authority_info = next_account_info(account_info_iter)?;
-    let _system_program_info = next_account_info(account_info_iter)?;
 
-    if !authority_info.is_signer {
-        msg!("Authority is not a signer");
-        return Err(ProgramError::InvalidArgument);
-    }
+        DSLInstruction::EdwardsAdd(data) => {
+            msg!("EdwardsAdd");
+            process_edwards_add(
+                compute_buffer_info,
+                &data,
+                false,
+            )
+        }
+        DSLInstruction::EdwardsSub(data) => {
+            msg!("EdwardsSub");
+            process_edwards_add(
+                compute_buffer_info,
+                &data,
+                true,
+            )
+        }
 
-    let buffer_data = buffer_info.try_borrow_data()?;
-    let mut buffer_ptr: &[u8] = *buffer_data;
+        DSLInstruction::AssertIdentity(RunDecompressData { offset }) => {
+            msg!("AssertIdentity");
+            process_assert_ristretto_equal(
+                compute_buffer_info,
+                instruction_num,
+                offset,
+                None,
+            )
+        }
+        DSLInstruction::AssertRistrettoEqual(data) => {
+            msg!("AssertRistrettoEqual");
+            process_assert_ristretto_equal(
+                compute_buffer_info,
+                instruction_num,
+                data.lhs_offset,
+                Some(data.rhs_offset),
+            )
+        }
 
-    match Key::from_u8(buffer_data[0]).ok_or(ProgramError::InvalidArgument)? {
-        Key::InputBufferV1 => {
-            let header = InputHeader::deserialize(&mut buffer_ptr)?;
-            if header.authority != *authority_info.key {
-                msg!("Invalid input buffer authority");
-                return Err(ProgramError::InvalidArgument);
-            }
+        DSLInstruction::Sha512Init(RunDecompressData { offset }) => {
+            msg!("Sha512Init");
+            process_sha512_init(
+                compute_buffer_info,
+                offset,
+            )
         }
-        Key::ComputeBufferV1 => {
-            let header = ComputeHeader::deserialize(&mut buffer_ptr)?;
-            if header.authority != *authority_info.key {
-                msg!("Invalid compute buffer authority");
-                return Err(ProgramError::InvalidArgument);
-            }
+        DSLInstruction::Sha512Update(data) => {
+            msg!("Sha512Update");
+            process_sha512_update(
+                compute_buffer_info,
+                &data,
+            )
         }
-        Key::InstructionBufferV1 => {
-            let header = InstructionHeader::deserialize(&mut buffer_ptr)?;
-            if header.authority != *authority_info.key {
-                msg!("Invalid instruction buffer authority");
-                return Err(ProgramError::InvalidArgument);
-            }
+        DSLInstruction::Sha512Fini(data) => {
+            msg!("Sha512Fini");
+            process_sha512_fini(
+                compute_buffer_info,
+                &data,
+            )
         }
-        Key::Uninitialized => {
-            msg!("Buffer not initialized");
-            return Err(ProgramError::InvalidArgument);
+        DSLInstruction::PackedScalarFromWide(data) => {
+            msg!("PackedScalarFromWide");
+            process_scalar_from_wide(
+                compute_buffer_info,
+                &data,
+                true,
+            )
         }
-    }
-
-    let dest_starting_lamports = authority_info.lamports();
-    **authority_info.lamports.borrow_mut() = dest_starting_lamports
-        .checked_add(buffer_info.lamports())
-        .ok_or(ProgramError::InvalidArgument)?;
-
-    **buffer_info.lamports.borrow_mut() = 0;
-
-    Ok(())
-}
-
-fn process_write_bytes(
-    accounts: &[AccountInfo],
-    offset: u32,
-    finalized: bool,
-    bytes: &[u8],
-) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let buffer_info = next_account_info(account_info_iter)?;
-    let authority_info = next_account_info(account_info_iter)?;
-    let _system_program_info = next_account_info(account_info_iter)?;
-
-    if !authority_info.is_signer {
-        msg!("Authority is not a signer");
-        return Err(ProgramError::InvalidArgument);
-    }
-
-    let offset = offset as usize;
-    let mut buffer_data = buffer_info.try_borrow_mut_data()?;
-
-    match Key::from_u8(buffer_data[0]).ok_or(ProgramError::InvalidArgument)? {
-        Key::InputBufferV1 => {
-            let mut header = {
-                let mut buffer_ptr: &[u8] = buffer_data.borrow();
-                InputHeader::deserialize(&mut buffer_ptr)?
-            };
-            if header.authority != *authority_info.key {
-                msg!("Invalid input buffer authority");
-                return Err(ProgramError::InvalidArgument);
-            }
-
-            if header.finalized {
-                msg!("Input buffer already finalized");
-                return Err(ProgramError::InvalidArgument);
-            }
-
-            header.finalized = finalized;
 
-            use std::borrow::BorrowMut;
-            let mut buffer_ptr: &mut [u8] = buffer_data.borrow_mut();
-            header.serialize(&mut buffer_ptr)?;
+        DSLInstruction::WriteConstant(data) => {
+            msg!("WriteConstant");
+            process_write_constant(
+                compute_buffer_info,
+                &data,
+            )
         }
-        Key::InstructionBufferV1 => {
-            let mut header = {
-                let mut buffer_ptr: &[u8] = buffer_data.borrow();
-                InstructionHeader::deserialize(&mut buffer_ptr)?
-            };
-            if header.authority != *authority_info.key {
-                msg!("Invalid instruction buffer authority");
-                return Err(ProgramError::InvalidArgument);
-            }
-
-            if header.finalized {
-                msg!("Input buffer already finalized");
-                return Err(ProgramError::InvalidArgument);
-            }
-
-            header.finalized = finalized;
-
-            use std::borrow::BorrowMut;
-            let mut buffer_ptr: &mut [u8] = buffer_data.borrow_mut();
-            header.serialize(&mut buffer_ptr)?;
+        DSLInstruction::TranscriptInit(data) => {
+            msg!("TranscriptInit");
+            process_transcript_init(
+                compute_buffer_info,
+                &data,
+            )
         }
-        _ => {
-            msg!("Invalid buffer type");
-            return Err(ProgramError::InvalidArgument);
+        DSLInstruction::TranscriptAppendMessage(data) => {
+            msg!("TranscriptAppendMessage");
+            process_transcript_append_message(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::TranscriptChallengeBytes(data) => {
+            msg!("TranscriptChallengeBytes");
+            process_transcript_challenge_bytes(
+                compute_buffer_info,
+                &data,
+            )
         }
-    };
-
-    if offset < HEADER_SIZE {
-        msg!("Cannot write to header");
-        return Err(ProgramError::InvalidArgument);
-    }
-
-    buffer_data[offset..offset+bytes.len()].copy_from_slice(bytes);
-
-    Ok(())
-}
-
-fn process_copy_input(
-    input_buffer_info: &AccountInfo,
-    compute_buffer_info: &AccountInfo,
-    offsets: &CopyInputData,
-) -> ProgramResult {
-    let input_buffer_data = input_buffer_info.try_borrow_data()?;
-
-    let mut input_buffer_ptr: &[u8] = input_buffer_data.borrow();
-    let input_header = InputHeader::deserialize(&mut input_buffer_ptr)?;
+
+        DSLInstruction::ScalarAdd(data) => {
+            msg!("ScalarAdd");
+            process_scalar_binary_op(
+                compute_buffer_info,
+                &data,
+                |a, b| a + b,
+            )
+        }
+        DSLInstruction::ScalarSub(data) => {
+            msg!("ScalarSub");
+            process_scalar_binary_op(
+                compute_buffer_info,
+                &data,
+                |a, b| a - b,
+            )
+        }
+        DSLInstruction::ScalarMul(data) => {
+            msg!("ScalarMul");
+            process_scalar_binary_op(
+                compute_buffer_info,
+                &data,
+                |a, b| a * b,
+            )
+        }
+        DSLInstruction::ScalarNeg(data) => {
+            msg!("ScalarNeg");
+            process_scalar_unary_op(
+                compute_buffer_info,
+                &data,
+                |a| (-a).to_bytes(),
+            )
+        }
+        DSLInstruction::ScalarFromWide(data) => {
+            msg!("ScalarFromWide");
+            process_scalar_from_wide(
+                compute_buffer_info,
+                &data,
+                false,
+            )
+        }
+        DSLInstruction::PackScalar(data) => {
+            msg!("PackScalar");
+            process_scalar_unary_op(
+                compute_buffer_info,
+                &data,
+                |a| a.to_packed_radix_16(),
+            )
+        }
+
+        // [
+        //   x,
+        //   _1, _10, _100, _11, _101, _111, _1001, _1011, _1111,  // step 0
+        //   y,                                                    // chain
+        //   x^-1,                                                 // last step
+        // ]
+        DSLInstruction::ScalarInvert(RunSplitComputeData { offset, step }) => {
+            msg!("ScalarInvert");
+            process_scalar_invert(
+                compute_buffer_info,
+                offset,
+                step,
+            )
+        }
+        // [
+        //   x_0, .., x_{n-1},               // replaced by inverses in step 1
+        //   scratch_0, .., scratch_{n-1},   // step 0
+        //   ..ScalarInvert of the product..
+        // ]
+        DSLInstruction::BatchScalarInvert(data) => {
+            msg!("BatchScalarInvert");
+            process_batch_scalar_invert(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::BasepointMul(data) => {
+            msg!("BasepointMul");
+            process_basepoint_mul(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::ExpandMessageXmdInit(RunDecompressData { offset }) => {
+            msg!("ExpandMessageXmdInit");
+            process_expand_message_xmd_init(
+                compute_buffer_info,
+                offset,
+            )
+        }
+        DSLInstruction::ExpandMessageXmdFini(data) => {
+            msg!("ExpandMessageXmdFini");
+            process_expand_message_xmd_fini(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::HashToField(data) => {
+            msg!("HashToField");
+            process_hash_to_field(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::MultiscalarMulTableBuffer(data) => {
+            msg!("MultiscalarMulTableBuffer");
+            let table_buffer_info = table_buffer_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            check_table_buffer(table_buffer_info, &instruction_header.table_buffer, &data)?;
+            process_multiscalar_mul(
+                compute_buffer_info,
+                Some(table_buffer_info),
+                &data,
+            )
+        }
+        DSLInstruction::NafScalar(data) => {
+            msg!("NafScalar");
+            process_naf_scalar(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::BuildNafLookupTable(data) => {
+            msg!("BuildNafLookupTable");
+            process_build_naf_lookup_table(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::VartimeMultiscalarMul(data) => {
+            msg!("VartimeMultiscalarMul");
+            process_vartime_multiscalar_mul(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::RadixScalar(data) => {
+            msg!("RadixScalar");
+            process_radix_scalar(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::PippengerAccumulate(data) => {
+            msg!("PippengerAccumulate {}", data.digit_index);
+            process_pippenger_accumulate(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::PippengerSumBuckets(data) => {
+            msg!("PippengerSumBuckets {}", data.digit_index);
+            process_pippenger_sum_buckets(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::PackScalarRadix2w(data) => {
+            msg!("PackScalarRadix2w");
+            process_pack_scalar_radix_2w(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::BuildLookupTableRadix32(data) => {
+            msg!("BuildLookupTableRadix32");
+            process_build_lookup_table_radix(
+                compute_buffer_info,
+                &data,
+                16,
+            )
+        }
+        DSLInstruction::BuildLookupTableRadix64(data) => {
+            msg!("BuildLookupTableRadix64");
+            process_build_lookup_table_radix(
+                compute_buffer_info,
+                &data,
+                32,
+            )
+        }
+        DSLInstruction::MultiscalarMulRadix32(data) => {
+            msg!("MultiscalarMulRadix32");
+            process_multiscalar_mul_radix(
+                compute_buffer_info,
+                &data,
+                5,
+                MAX_MULTISCALAR_RADIX_32_POINTS,
+                LookupTableRadix32::<ProjectiveNielsPoint>::select,
+            )
+        }
+        DSLInstruction::MultiscalarMulRadix64(data) => {
+            msg!("MultiscalarMulRadix64");
+            process_multiscalar_mul_radix(
+                compute_buffer_info,
+                &data,
+                6,
+                MAX_MULTISCALAR_RADIX_64_POINTS,
+                LookupTableRadix64::<ProjectiveNielsPoint>::select,
+            )
+        }
+        DSLInstruction::BatchNormalizePrefix(data) => {
+            msg!("BatchNormalizePrefix");
+            process_batch_normalize_prefix(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        // reads 32 bytes at 96 and 128, and writes 32 at 160
+        DSLInstruction::InvertFini(RunDecompressData{ offset }) => {
+            msg!("InvertFini");
+            process_invert_fini(
+                compute_buffer_info,
+                offset,
+            )
+        }
+        DSLInstruction::BatchNormalize(data) => {
+            msg!("BatchNormalize");
+            process_batch_normalize(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::MultiscalarMulAffine(data) => {
+            msg!("MultiscalarMulAffine");
+            process_multiscalar_mul_affine(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::BatchInvertPrefix(data) => {
+            msg!("BatchInvertPrefix");
+            process_batch_invert_prefix(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::BatchInvert(data) => {
+            msg!("BatchInvert");
+            process_batch_invert(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::DecompressInitBatch(data) => {
+            msg!("DecompressInitBatch");
+            process_decompress_init_batch(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::DecompressFiniBatch(data) => {
+            msg!("DecompressFiniBatch");
+            process_decompress_fini_batch(
+                compute_buffer_info,
+                &data,
+            )
+        }
+        DSLInstruction::CompressEdwardsWithWitness(RunDecompressData{ offset }) => {
+            msg!("CompressEdwardsWithWitness");
+            process_compress_edwards_with_witness(
+                compute_buffer_info,
+                offset,
+            )
+        }
+        DSLInstruction::DecompressEdwardsWithWitness(RunDecompressData{ offset }) => {
+            msg!("DecompressEdwardsWithWitness");
+            process_decompress_edwards_with_witness(
+                compute_buffer_info,
+                offset,
+            )
+        }
+        DSLInstruction::ElligatorWithWitness(RunDecompressData{ offset }) => {
+            msg!("ElligatorWithWitness");
+            process_elligator_with_witness(
+                compute_buffer_info,
+                offset,
+            )
+        }
+        DSLInstruction::MontgomeryElligatorWithWitness(RunSplitComputeData{ offset, step }) => {
+            msg!("MontgomeryElligatorWithWitness {}", step);
+            process_montgomery_elligator_with_witness(
+                compute_buffer_info,
+                offset,
+                step,
+            )
+        }
+        DSLInstruction::MontgomeryToEdwardsWithWitness(MontgomeryToEdwardsData{ offset, sign_offset, .. }) => {
+            msg!("MontgomeryToEdwardsWithWitness");
+            process_montgomery_to_edwards_with_witness(
+                compute_buffer_info,
+                offset,
+                sign_offset,
+            )
+        }
+    }
+}
+
+fn process_initialize_buffer<F, T: BorshSerialize>(
+    accounts: &[AccountInfo],
+    header_fn: F,
+) -> ProgramResult
+where
+    F: FnOnce(Pubkey) -> T,
+{
+    let account_info_iter = &mut accounts.iter();
+    let buffer_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    if !authority_info.is_signer {
+        msg!("Authority is not a signer");
+        return Err(Curve25519Error::AuthorityNotSigner.into());
+    }
+
+    use solana_program::sysvar::Sysvar;
+    let rent = solana_program::rent::Rent::get()?;
+    if !rent.is_exempt(buffer_info.lamports(), buffer_info.data_len()) {
+        msg!("Buffer is not rent exempt");
+        return Err(Curve25519Error::NotRentExempt.into());
+    }
+
+    let mut buffer_data = buffer_info.try_borrow_mut_data()?;
+
+    if buffer_data[0] != Key::Uninitialized as u8 {
+        msg!("Buffer already initialized");
+        return Err(Curve25519Error::BufferAlreadyInitialized.into());
+    }
+
+    // TODO: does this write correctly?
+    header_fn(*authority_info.key).serialize(&mut *buffer_data)?;
+
+    Ok(())
+}
+
+fn process_close_buffer(
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buffer_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    if !authority_info.is_signer {
+        msg!("Authority is not a signer");
+        return Err(Curve25519Error::AuthorityNotSigner.into());
+    }
+
+    let mut buffer_data = buffer_info.try_borrow_mut_data()?;
+    let mut buffer_ptr: &[u8] = *buffer_data;
+
+    match Key::from_u8(buffer_data[0]).ok_or(Curve25519Error::InvalidBufferType)? {
+        Key::InputBufferV1 => {
+            let header = InputHeader::deserialize(&mut buffer_ptr)?;
+            if header.authority != *authority_info.key {
+                msg!("Invalid input buffer authority");
+                return Err(Curve25519Error::InvalidAuthority.into());
+            }
+            if header.num_compute_buffers != 0 {
+                msg!("Input buffer used by {} compute buffers", header.num_compute_buffers);
+                return Err(Curve25519Error::BufferInUse.into());
+            }
+        }
+        Key::ComputeBufferV1 => {
+            let header = ComputeHeader::deserialize(&mut buffer_ptr)?;
+            if header.authority != *authority_info.key {
+                msg!("Invalid compute buffer authority");
+                return Err(Curve25519Error::InvalidAuthority.into());
+            }
+            update_compute_buffer_refs(
+                account_info_iter.as_slice(),
+                &header.instruction_buffer,
+                &header.input_buffer,
+                false,
+            )?;
+        }
+        Key::InstructionBufferV1 => {
+            let header = InstructionHeader::deserialize(&mut buffer_ptr)?;
+            if header.authority != *authority_info.key {
+                msg!("Invalid instruction buffer authority");
+                return Err(Curve25519Error::InvalidAuthority.into());
+            }
+            if header.num_compute_buffers != 0 {
+                msg!("Instruction buffer used by {} compute buffers", header.num_compute_buffers);
+                return Err(Curve25519Error::BufferInUse.into());
+            }
+        }
+        Key::TableBufferV1 => {
+            let header = TableHeader::deserialize(&mut buffer_ptr)?;
+            if header.authority != *authority_info.key {
+                msg!("Invalid table buffer authority");
+                return Err(Curve25519Error::InvalidAuthority.into());
+            }
+        }
+        Key::Uninitialized => {
+            msg!("Buffer not initialized");
+            return Err(Curve25519Error::BufferNotInitialized.into());
+        }
+    }
+
+    // the data lives until the end of the transaction. Clear the key so the
+    // buffer can't be closed again and release its references twice
+    buffer_data[0] = Key::Uninitialized as u8;
+
+    let dest_starting_lamports = authority_info.lamports();
+    **authority_info.lamports.borrow_mut() = dest_starting_lamports
+        .checked_add(buffer_info.lamports())
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    **buffer_info.lamports.borrow_mut() = 0;
+
+    Ok(())
+}
+
+// compute buffers count as references on their instruction and input buffers,
+// which can't be closed while referenced. Otherwise they could be closed and
+// recreated at the same address with other contents once the compute buffer
+// completes, and cpi::verify_completed_compute_buffer only checks pubkeys
+fn update_compute_buffer_refs(
+    accounts: &[AccountInfo],
+    instruction_buffer: &Pubkey,
+    input_buffer: &Pubkey,
+    add: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let instruction_buffer_info = next_account_info(account_info_iter)?;
+    let input_buffer_info = next_account_info(account_info_iter)?;
+
+    if *instruction_buffer_info.key != *instruction_buffer
+            || *input_buffer_info.key != *input_buffer {
+        msg!("Mismatched instruction or input buffer");
+        return Err(Curve25519Error::MismatchedBuffer.into());
+    }
+    if *instruction_buffer_info.owner != crate::ID || *input_buffer_info.owner != crate::ID {
+        msg!("Bad instruction or input buffer");
+        return Err(Curve25519Error::InvalidBufferOwner.into());
+    }
+
+    let update = |num_compute_buffers: u32| {
+        if add {
+            num_compute_buffers.checked_add(1)
+        } else {
+            num_compute_buffers.checked_sub(1)
+        }.ok_or(ProgramError::InvalidAccountData)
+    };
+
+    use std::borrow::BorrowMut;
+    let mut instruction_buffer_data = instruction_buffer_info.try_borrow_mut_data()?;
+    let mut instruction_header = {
+        let mut instruction_buffer_ptr: &[u8] = instruction_buffer_data.borrow();
+        InstructionHeader::deserialize(&mut instruction_buffer_ptr)?
+    };
+    if instruction_header.key != Key::InstructionBufferV1 {
+        msg!("Invalid instruction buffer type");
+        return Err(Curve25519Error::InvalidBufferType.into());
+    }
+    instruction_header.num_compute_buffers = update(instruction_header.num_compute_buffers)?;
+    let mut instruction_buffer_ptr: &mut [u8] = instruction_buffer_data.borrow_mut();
+    instruction_header.serialize(&mut instruction_buffer_ptr)?;
+
+    let mut input_buffer_data = input_buffer_info.try_borrow_mut_data()?;
+    let mut input_header = {
+        let mut input_buffer_ptr: &[u8] = input_buffer_data.borrow();
+        InputHeader::deserialize(&mut input_buffer_ptr)?
+    };
+    if input_header.key != Key::InputBufferV1 {
+        msg!("Invalid input buffer type");
+        return Err(Curve25519Error::InvalidBufferType.into());
+    }
+    input_header.num_compute_buffers = update(input_header.num_compute_buffers)?;
+    let mut input_buffer_ptr: &mut [u8] = input_buffer_data.borrow_mut();
+    input_header.serialize(&mut input_buffer_ptr)?;
+
+    Ok(())
+}
+
+fn process_write_bytes(
+    accounts: &[AccountInfo],
+    offset: u32,
+    finalized: bool,
+    bytes: &[u8],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buffer_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    if !authority_info.is_signer {
+        msg!("Authority is not a signer");
+        return Err(Curve25519Error::AuthorityNotSigner.into());
+    }
+
+    let offset = offset as usize;
+    let mut buffer_data = buffer_info.try_borrow_mut_data()?;
+
+    match Key::from_u8(buffer_data[0]).ok_or(Curve25519Error::InvalidBufferType)? {
+        Key::InputBufferV1 => {
+            let mut header = {
+                let mut buffer_ptr: &[u8] = buffer_data.borrow();
+                InputHeader::deserialize(&mut buffer_ptr)?
+            };
+            if header.authority != *authority_info.key {
+                msg!("Invalid input buffer authority");
+                return Err(Curve25519Error::InvalidAuthority.into());
+            }
+
+            if header.finalized {
+                msg!("Input buffer already finalized");
+                return Err(Curve25519Error::BufferFinalized.into());
+            }
+
+            header.finalized = finalized;
+
+            use std::borrow::BorrowMut;
+            let mut buffer_ptr: &mut [u8] = buffer_data.borrow_mut();
+            header.serialize(&mut buffer_ptr)?;
+        }
+        Key::InstructionBufferV1 => {
+            let mut header = {
+                let mut buffer_ptr: &[u8] = buffer_data.borrow();
+                InstructionHeader::deserialize(&mut buffer_ptr)?
+            };
+            if header.authority != *authority_info.key {
+                msg!("Invalid instruction buffer authority");
+                return Err(Curve25519Error::InvalidAuthority.into());
+            }
+
+            if header.finalized {
+                msg!("Input buffer already finalized");
+                return Err(Curve25519Error::BufferFinalized.into());
+            }
+
+            let end = offset.saturating_sub(HEADER_SIZE) + bytes.len();
+            let written = (end + INSTRUCTION_SIZE - 1) / INSTRUCTION_SIZE;
+            header.num_instructions = header.num_instructions.max(written as u32);
+            header.finalized = finalized;
+
+            use std::borrow::BorrowMut;
+            let mut buffer_ptr: &mut [u8] = buffer_data.borrow_mut();
+            header.serialize(&mut buffer_ptr)?;
+        }
+        Key::TableBufferV1 => {
+            let mut header = {
+                let mut buffer_ptr: &[u8] = buffer_data.borrow();
+                TableHeader::deserialize(&mut buffer_ptr)?
+            };
+            if header.authority != *authority_info.key {
+                msg!("Invalid table buffer authority");
+                return Err(Curve25519Error::InvalidAuthority.into());
+            }
+
+            if header.finalized {
+                msg!("Table buffer already finalized");
+                return Err(Curve25519Error::BufferFinalized.into());
+            }
+
+            // tables only come from BuildTables. This just finalizes
+            if !bytes.is_empty() {
+                msg!("Cannot write bytes to table buffer");
+                return Err(Curve25519Error::TableBufferWrite.into());
+            }
+
+            header.finalized = finalized;
+
+            use std::borrow::BorrowMut;
+            let mut buffer_ptr: &mut [u8] = buffer_data.borrow_mut();
+            header.serialize(&mut buffer_ptr)?;
+        }
+        _ => {
+            msg!("Invalid buffer type");
+            return Err(Curve25519Error::InvalidBufferType.into());
+        }
+    };
+
+    if offset < HEADER_SIZE {
+        msg!("Cannot write to header");
+        return Err(Curve25519Error::HeaderOffset.into());
+    }
+
+    buffer_data[offset..offset+bytes.len()].copy_from_slice(bytes);
+
+    Ok(())
+}
+
+fn process_build_tables(
+    accounts: &[AccountInfo],
+    first_table: u32,
+    points: &[u8],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let table_buffer_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    if !authority_info.is_signer {
+        msg!("Authority is not a signer");
+        return Err(Curve25519Error::AuthorityNotSigner.into());
+    }
+
+    if *table_buffer_info.owner != crate::ID {
+        msg!("Bad table buffer");
+        return Err(Curve25519Error::InvalidBufferOwner.into());
+    }
+
+    let mut table_buffer_data = table_buffer_info.try_borrow_mut_data()?;
+    let mut table_header = {
+        let mut table_buffer_ptr: &[u8] = table_buffer_data.borrow();
+        TableHeader::deserialize(&mut table_buffer_ptr)?
+    };
+    if table_header.key != Key::TableBufferV1 {
+        msg!("Invalid table buffer type");
+        return Err(Curve25519Error::InvalidBufferType.into());
+    }
+    if table_header.authority != *authority_info.key {
+        msg!("Invalid table buffer authority");
+        return Err(Curve25519Error::InvalidAuthority.into());
+    }
+    if table_header.finalized {
+        msg!("Table buffer already finalized");
+        return Err(Curve25519Error::BufferFinalized.into());
+    }
+
+    if points.len() % 128 != 0 {
+        msg!("Invalid points length");
+        return Err(Curve25519Error::InvalidLength.into());
+    }
+
+    // tables are filled in order so that `num_tables` covers exactly the
+    // built ones
+    if first_table != table_header.num_tables {
+        msg!("Expected table {} but got {}", table_header.num_tables, first_table);
+        return Err(Curve25519Error::InvalidTableOffset.into());
+    }
+
+    type LUT = LookupTable::<ProjectiveNielsPoint>;
+    let mut table_offset = HEADER_SIZE + first_table as usize * LUT::TABLE_SIZE;
+    if table_offset + points.len() / 128 * LUT::TABLE_SIZE > table_buffer_data.len() {
+        msg!("Tables do not fit in table buffer");
+        return Err(Curve25519Error::InvalidLength.into());
+    }
+
+    use crate::traits::ValidityCheck;
+    for point_bytes in points.chunks_exact(128) {
+        let point = EdwardsPoint::from_bytes(point_bytes);
+        if !point.is_valid() || point.Z == FieldElement::zero() {
+            msg!("Point is not on the curve");
+            return Err(Curve25519Error::InvalidPoint.into());
+        }
+
+        let table = LUT::from(&point);
+        table_buffer_data[table_offset..table_offset + LUT::TABLE_SIZE].copy_from_slice(
+            bytemuck::cast_slice::<LUT, u8>(std::slice::from_ref(&table)));
+        table_offset += LUT::TABLE_SIZE;
+    }
+
+    table_header.num_tables += (points.len() / 128) as u32;
+    use std::borrow::BorrowMut;
+    let mut table_buffer_ptr: &mut [u8] = table_buffer_data.borrow_mut();
+    table_header.serialize(&mut table_buffer_ptr)?;
+
+    Ok(())
+}
+
+// also checks that `data` only reads whole tables that BuildTables has built.
+// Anything else is zeroed or misaligned bytes that `select` happily returns
+fn check_table_buffer(
+    table_buffer_info: &AccountInfo,
+    expected_table_buffer: &Pubkey,
+    data: &MultiscalarMulData,
+) -> ProgramResult {
+    if *table_buffer_info.key != *expected_table_buffer
+            || *expected_table_buffer == Pubkey::default() {
+        msg!("Mismatched table buffer");
+        return Err(Curve25519Error::MismatchedBuffer.into());
+    }
+    if *table_buffer_info.owner != crate::ID {
+        msg!("Bad table buffer");
+        return Err(Curve25519Error::InvalidBufferOwner.into());
+    }
+
+    let table_buffer_data = table_buffer_info.try_borrow_data()?;
+    let mut table_buffer_ptr: &[u8] = *table_buffer_data;
+    let table_header = TableHeader::deserialize(&mut table_buffer_ptr)?;
+
+    if table_header.key != Key::TableBufferV1 {
+        msg!("Invalid table buffer type");
+        return Err(Curve25519Error::InvalidBufferType.into());
+    }
+    if !table_header.finalized {
+        msg!("Table buffer not finalized");
+        return Err(Curve25519Error::BufferNotFinalized.into());
+    }
+
+    type LUT = LookupTable::<ProjectiveNielsPoint>;
+    let tables_offset = data.tables_offset as usize;
+    let tables_end = tables_offset + data.num_inputs as usize * LUT::TABLE_SIZE;
+    if tables_offset < HEADER_SIZE
+            || (tables_offset - HEADER_SIZE) % LUT::TABLE_SIZE != 0
+            || tables_end > HEADER_SIZE + table_header.num_tables as usize * LUT::TABLE_SIZE {
+        msg!("Tables outside the {} built tables", table_header.num_tables);
+        return Err(Curve25519Error::InvalidTableOffset.into());
+    }
+
+    Ok(())
+}
+
+fn check_input_buffer(
+    input_buffer_data: &[u8],
+) -> ProgramResult {
+    let mut input_buffer_ptr: &[u8] = input_buffer_data;
+    let input_header = InputHeader::deserialize(&mut input_buffer_ptr)?;
 
     if input_header.key != Key::InputBufferV1 {
         msg!("Invalid buffer type");
-        return Err(ProgramError::InvalidArgument);
+        return Err(Curve25519Error::InvalidBufferType.into());
+    }
+    if !input_header.finalized {
+        msg!("Input buffer not finalized");
+        return Err(Curve25519Error::BufferNotFinalized.into());
+    }
+
+    Ok(())
+}
+
+fn process_copy_input(
+    input_buffer_info: &AccountInfo,
+    compute_buffer_info: &AccountInfo,
+    offsets: &CopyInputData,
+) -> ProgramResult {
+    let input_buffer_data = input_buffer_info.try_borrow_data()?;
+    check_input_buffer(&input_buffer_data)?;
+
+    let input_offset = offsets.input_offset as usize;
+    if input_offset < HEADER_SIZE {
+        msg!("Cannot copy from header");
+        return Err(Curve25519Error::HeaderOffset.into());
+    }
+
+    let compute_offset = offsets.compute_offset as usize;
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+    let copy_bytes = offsets.bytes as usize;
+    compute_slice_mut(
+        &mut compute_buffer_data, compute_offset, copy_bytes,
+    )?.copy_from_slice(&input_buffer_data[
+        input_offset..input_offset+copy_bytes
+    ]);
+
+    Ok(())
+}
+
+fn process_load_scalar(
+    input_buffer_info: &AccountInfo,
+    compute_buffer_info: &AccountInfo,
+    data: &LoadScalarData,
+) -> ProgramResult {
+    let input_buffer_data = input_buffer_info.try_borrow_data()?;
+    check_input_buffer(&input_buffer_data)?;
+
+    let input_offset = data.input_offset as usize;
+    if input_offset < HEADER_SIZE {
+        msg!("Cannot copy from header");
+        return Err(Curve25519Error::HeaderOffset.into());
+    }
+
+    let scalar = Scalar::from_canonical_bytes(
+        input_buffer_data[input_offset..input_offset+32].try_into().unwrap(),
+    ).ok_or_else(|| {
+        msg!("Non-canonical scalar");
+        ProgramError::from(Curve25519Error::NonCanonicalScalar)
+    })?;
+
+    let compute_offset = data.compute_offset as usize;
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+    compute_slice_mut(&mut compute_buffer_data, compute_offset, 32)?.copy_from_slice(
+        &scalar.to_packed_radix_16());
+
+    Ok(())
+}
+
+fn process_write_edwards_identity(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+) -> ProgramResult {
+    let offset = offset as usize;
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    use crate::traits::Identity;
+    compute_slice_mut(&mut compute_buffer_data, offset, 128)?.copy_from_slice(
+        &EdwardsPoint::identity().to_bytes());
+
+    Ok(())
+}
+
+fn process_write_edwards_basepoint(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+) -> ProgramResult {
+    let offset = offset as usize;
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    compute_slice_mut(&mut compute_buffer_data, offset, 128)?.copy_from_slice(
+        &constants::ED25519_BASEPOINT_POINT.to_bytes());
+
+    Ok(())
+}
+
+fn process_invsqrt_init(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+
+    let u = FieldElement::one();
+    let v = read_field_element(&compute_buffer_data, offset)?;
+
+    let offset = offset + 32;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+        &FieldElement::sqrt_ratio_i_pow_p58_input(&u, &v).to_bytes(),
+    );
+
+    Ok(())
+}
+
+fn process_invsqrt_fini(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+
+    let u = FieldElement::one();
+    let v = read_field_element(&compute_buffer_data, offset)?;
+    let pow_p22501_output = read_field_element(&compute_buffer_data, offset + 32 * 5)?;
+
+    let (ok, r) = FieldElement::sqrt_ratio_i_pow_p58_output(&u, &v, &pow_p22501_output);
+
+    if ok.unwrap_u8() == 0u8 {
+        return Err(Curve25519Error::InvalidPoint.into());
+    }
+
+    let offset = offset + 32 * 6;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(&r.to_bytes());
+
+    Ok(())
+}
+
+fn process_pow22501_p1(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+    let element = read_field_element(&compute_buffer_data, offset)?;
+
+    let (t17, t13, t3) = FieldElement::pow22001(&element);
+
+    let offset = offset + 32;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(&t17.to_bytes());
+
+    let offset = offset + 32;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(&t13.to_bytes());
+
+    let offset = offset + 32;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(&t3.to_bytes());
+
+    Ok(())
+}
+
+fn process_pow22501_p2(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+    let t17 = read_field_element(&compute_buffer_data, offset)?;
+    let t13 = read_field_element(&compute_buffer_data, offset + 32)?;
+
+    let t19 = FieldElement::pow22501(&t17, &t13);
+
+    let offset = offset + 32 * 3; // skip t3
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(&t19.to_bytes());
+
+    Ok(())
+}
+
+fn process_decompress_init(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+    let point = CompressedRistretto::from_slice(
+        &compute_buffer_data[offset..offset+32]
+    );
+
+    let offset = offset + 32;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+        &point.decompress_init().ok_or(Curve25519Error::InvalidPoint)?.to_bytes()
+    );
+
+    Ok(())
+}
+
+fn process_decompress_fini(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+    let point = CompressedRistretto::from_slice(
+        &compute_buffer_data[offset..offset+32]
+    );
+
+    let offset = offset + 32 * 7;
+    let element = read_field_element(&compute_buffer_data, offset)?;
+
+    let res = point.decompress_fini(&element).ok_or(Curve25519Error::InvalidPoint)?;
+
+    let offset = offset + 32;
+    compute_slice_mut(&mut compute_buffer_data, offset, 128)?.copy_from_slice(
+        &res.0.to_bytes());
+
+    Ok(())
+}
+
+fn check_batch_decompress_data(
+    data: &BatchDecompressData,
+) -> ProgramResult {
+    if data.start > data.end {
+        msg!("Invalid batch decompress steps");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    Ok(())
+}
+
+fn process_decompress_init_batch(
+    compute_buffer_info: &AccountInfo,
+    data: &BatchDecompressData,
+) -> ProgramResult {
+    check_batch_decompress_data(data)?;
+
+    for i in data.start as usize..data.end as usize {
+        let slot_offset = data.scratch_offset as usize + DECOMPRESS_SIZE * i;
+
+        let compressed_offset = data.compressed_offset as usize + 32 * i;
+        let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+        let compressed: [u8; 32] = compute_buffer_data[compressed_offset..compressed_offset+32]
+            .try_into().map_err(|_| ProgramError::InvalidArgument)?;
+        compute_slice_mut(&mut compute_buffer_data, slot_offset, 32)?.copy_from_slice(&compressed);
+        drop(compute_buffer_data);
+
+        process_decompress_init(compute_buffer_info, slot_offset as u32)?;
+        process_invsqrt_init(compute_buffer_info, (slot_offset + 32) as u32)?;
+    }
+
+    Ok(())
+}
+
+fn process_decompress_fini_batch(
+    compute_buffer_info: &AccountInfo,
+    data: &BatchDecompressData,
+) -> ProgramResult {
+    check_batch_decompress_data(data)?;
+
+    for i in data.start as usize..data.end as usize {
+        let slot_offset = data.scratch_offset as usize + DECOMPRESS_SIZE * i;
+
+        process_invsqrt_fini(compute_buffer_info, (slot_offset + 32) as u32)?;
+        process_decompress_fini(compute_buffer_info, slot_offset as u32)?;
+    }
+
+    Ok(())
+}
+
+fn process_decompress_edwards(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+    step: u8,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+    let compressed_bytes = &compute_buffer_data[offset..offset+32];
+    let Y = read_field_element(&compute_buffer_data, offset)?;
+    let Z = FieldElement::one();
+    let YY = Y.square();
+    let u = &YY - &Z;                            // u =  y²-1
+    let v = &(&YY * &constants::EDWARDS_D) + &Z; // v = dy²+1
+
+    if step == 0 {
+        let offset = offset + 32;
+        compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+            &FieldElement::sqrt_ratio_i_pow_p58_input(&u, &v).to_bytes(),
+        );
+        return Ok(());
+    }
+
+    let pow_p22501_output = read_field_element(&compute_buffer_data, offset + 32 * 5)?;
+    let (is_valid_y_coord, mut X) = FieldElement::sqrt_ratio_i_pow_p58_output(&u, &v, &pow_p22501_output);
+
+    if is_valid_y_coord.unwrap_u8() != 1u8 {
+        msg!("Invalid y coordinate");
+        return Err(Curve25519Error::InvalidPoint.into());
+    }
+
+    use subtle::{Choice, ConditionallyNegatable};
+    let compressed_sign_bit = Choice::from(compressed_bytes[31] >> 7);
+    X.conditional_negate(compressed_sign_bit);
+
+    let res = EdwardsPoint{ X, Y, Z, T: &X * &Y };
+
+    let offset = offset + 32 * 6;
+    compute_slice_mut(&mut compute_buffer_data, offset, 128)?.copy_from_slice(
+        &res.to_bytes());
+
+    Ok(())
+}
+
+fn process_compress_edwards(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+    step: u8,
+) -> ProgramResult {
+    if step > 2 {
+        msg!("Invalid step");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+    let point = EdwardsPoint::from_bytes(
+        &compute_buffer_data[offset..offset+128]
+    );
+
+    if step == 0 {
+        // could be probably skip this if adding some kind of result offset to every
+        // instruction...
+        let offset = offset + 32 * 4;
+        compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+            &point.Z.to_bytes()
+        );
+        return Ok(());
+    }
+
+    let recip = if step == 2 {
+        // inverted in place by BatchInvert
+        read_field_element(&compute_buffer_data, offset + 32 * 4)?
+    } else {
+        // invert
+        let t3 = read_field_element(&compute_buffer_data, offset + 32 * 7)?;
+        let t19 = read_field_element(&compute_buffer_data, offset + 32 * 8)?;
+        &t19.pow2k(5) * &t3
+    };
+
+    let x = &point.X * &recip;
+    let y = &point.Y * &recip;
+    let mut s: [u8; 32];
+
+    s = y.to_bytes();
+    s[31] ^= x.is_negative().unwrap_u8() << 7;
+
+    let offset = offset + 32 * 9;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(&s);
+
+    Ok(())
+}
+
+// [
+//   point (128 bytes),
+//   pow22501 input,
+//   t17, t13, t3,
+//   t19,
+//   compressed,
+// ]
+fn process_compress_ristretto(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+    step: u8,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+    let point = RistrettoPoint(EdwardsPoint::from_bytes(
+        &compute_buffer_data[offset..offset+128]
+    ));
+
+    if step == 0 {
+        let offset = offset + 32 * 4;
+        compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+            &point.compress_init().to_bytes()
+        );
+        return Ok(());
+    }
+
+    let pow_p22501_output = read_field_element(&compute_buffer_data, offset + 32 * 8)?;
+    let compressed = point.compress_fini(&pow_p22501_output);
+
+    let offset = offset + 32 * 9;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+        compressed.as_bytes());
+
+    Ok(())
+}
+
+fn process_elligator(
+compute_buffer_info: &AccountInfo,
+    offset: u32,
+    step: u8,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+
+    let r_0 = read_field_element(&compute_buffer_data, offset)?;
+    let (r, N_s, D) = elligator_ratio(&r_0);
+
+    if step == 0 {
+        let offset = offset + 32;
+        compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+            &FieldElement::sqrt_ratio_i_pow_p58_input(&N_s, &D).to_bytes(),
+        );
+        return Ok(());
+    }
+
+    let pow_p22501_output = read_field_element(&compute_buffer_data, offset + 32 * 5)?;
+    let (Ns_D_is_sq, s) = FieldElement::sqrt_ratio_i_pow_p58_output(&N_s, &D, &pow_p22501_output);
+
+    let res = elligator_point(&r_0, &r, &D, Ns_D_is_sq, s);
+
+    let offset = offset + 32 * 6;
+    compute_slice_mut(&mut compute_buffer_data, offset, 128)?.copy_from_slice(
+        &res.0.to_bytes());
+
+    Ok(())
+}
+
+// r = i r_0^2, N_s and D for the ristretto elligator map
+pub(crate) fn elligator_ratio(
+    r_0: &FieldElement,
+) -> (FieldElement, FieldElement, FieldElement) {
+    let i = &constants::SQRT_M1;
+    let d = &constants::EDWARDS_D;
+    let one_minus_d_sq = &constants::ONE_MINUS_EDWARDS_D_SQUARED;
+    let c = constants::MINUS_ONE;
+
+    let one = FieldElement::one();
+
+    let r = i * &r_0.square();
+    let N_s = &(&r + &one) * &one_minus_d_sq;
+    let D = &(&c - &(d * &r)) * &(&r + d);
+
+    (r, N_s, D)
+}
+
+// the rest of the ristretto elligator map given sqrt_ratio_i(N_s, D)
+fn elligator_point(
+    r_0: &FieldElement,
+    r: &FieldElement,
+    D: &FieldElement,
+    Ns_D_is_sq: subtle::Choice,
+    mut s: FieldElement,
+) -> RistrettoPoint {
+    let d_minus_one_sq = &constants::EDWARDS_D_MINUS_ONE_SQUARED;
+    let mut c = constants::MINUS_ONE;
+
+    let one = FieldElement::one();
+
+    use subtle::{ConditionallySelectable, ConditionallyNegatable};
+    let mut s_prime = &s * r_0;
+    let s_prime_is_pos = !s_prime.is_negative();
+    s_prime.conditional_negate(s_prime_is_pos);
+
+    s.conditional_assign(&s_prime, !Ns_D_is_sq);
+    c.conditional_assign(r, !Ns_D_is_sq);
+
+    let N_t = &(&(&c * &(r - &one)) * &d_minus_one_sq) - D;
+    let s_sq = s.square();
+
+    // The conversion from W_i is exactly the conversion from P1xP1.
+    RistrettoPoint(CompletedPoint{
+        X: &(&s + &s) * D,
+        Z: &N_t * &constants::SQRT_AD_MINUS_ONE,
+        Y: &FieldElement::one() - &s_sq,
+        T: &FieldElement::one() + &s_sq,
+    }.to_extended())
+}
+
+fn process_montgomery_elligator(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+    step: u8,
+) -> ProgramResult {
+    if step > 3 {
+        msg!("Invalid step");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+
+    let one = FieldElement::one();
+
+    if step == 0 {
+        let r_0 = read_field_element(&compute_buffer_data, offset)?;
+        let d_1 = &one + &r_0.square2(); /* 2r^2 */
+
+        let offset = offset + 32;
+        compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+            &d_1.to_bytes()
+        );
+        return Ok(());
+    }
+
+    // invert
+    let t3 = read_field_element(&compute_buffer_data, offset + 32 * 4)?;
+    let t19 = read_field_element(&compute_buffer_data, offset + 32 * 5)?;
+    let d_1_inv = &t19.pow2k(5) * &t3;
+
+    let (d, eps) = montgomery_elligator_eps(&d_1_inv);
+
+    if step == 1 {
+        let offset = offset + 32 * 6;
+        compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+            &FieldElement::sqrt_ratio_i_pow_p58_input(&eps, &one).to_bytes(),
+        );
+        return Ok(());
+    }
+
+    let pow_p22501_output = read_field_element(&compute_buffer_data, offset + 32 * 10)?;
+    let (eps_is_sq, eps_sqrt) = FieldElement::sqrt_ratio_i_pow_p58_output(&eps, &one, &pow_p22501_output);
+
+    let u = montgomery_elligator_u(&d, eps_is_sq);
+
+    if step == 2 {
+        // write the compressed MontgomeryPoint
+        let offset = offset + 32 * 11;
+        compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+            &u.to_bytes());
+
+        return Ok(());
+    }
+
+    let r_0 = read_field_element(&compute_buffer_data, offset)?;
+    let res = montgomery_elligator_edwards(&r_0, &u, eps_is_sq, eps_sqrt);
+
+    let offset = offset + 32 * 12;
+    compute_slice_mut(&mut compute_buffer_data, offset, 128)?.copy_from_slice(
+        &res.to_bytes());
+
+    Ok(())
+}
+
+// d = -A/(1+2r^2) and eps = d^3 + Ad^2 + d given 1/(1+2r^2)
+pub(crate) fn montgomery_elligator_eps(
+    d_1_inv: &FieldElement,
+) -> (FieldElement, FieldElement) {
+    let one = FieldElement::one();
+
+    let d = &constants::MONTGOMERY_A_NEG * d_1_inv; /* A/(1+2r^2) */
+
+    let d_sq = &d.square();
+    let au = &constants::MONTGOMERY_A * &d;
+
+    let inner = &(d_sq + &au) + &one;
+    let eps = &d * &inner; /* eps = d^3 + Ad^2 + d */
+
+    (d, eps)
+}
+
+pub(crate) fn montgomery_elligator_u(
+    d: &FieldElement,
+    eps_is_sq: subtle::Choice,
+) -> FieldElement {
+    use subtle::{ConditionallySelectable, ConditionallyNegatable};
+
+    let zero = FieldElement::zero();
+    let Atemp = FieldElement::conditional_select(&constants::MONTGOMERY_A, &zero, eps_is_sq); /* 0, or A if nonsquare*/
+
+    let mut u = d + &Atemp; /* d, or d+A if nonsquare */
+    u.conditional_negate(!eps_is_sq); /* d, or -d-A if nonsquare */
+
+    u
+}
+
+// RFC 9380 map_to_curve_elligator2_edwards25519 given the Montgomery u and
+// sqrt_ratio_i(eps, 1)
+fn montgomery_elligator_edwards(
+    r_0: &FieldElement,
+    u: &FieldElement,
+    eps_is_sq: subtle::Choice,
+    eps_sqrt: FieldElement,
+) -> EdwardsPoint {
+    use subtle::{ConditionallySelectable, ConditionallyNegatable};
+
+    let one = FieldElement::one();
+
+    // The Montgomery v is sqrt(eps) or, when eps is nonsquare,
+    // r (1 - i) sqrt(i eps) = sqrt(2 r^2 eps). sgn0(v) is 1 iff eps is square
+    let mut v = eps_sqrt;
+    let v_nonsquare = &(r_0 * &(&one - &constants::SQRT_M1)) * &eps_sqrt;
+    v.conditional_assign(&v_nonsquare, !eps_is_sq);
+    let v_is_negative = v.is_negative();
+    v.conditional_negate(v_is_negative ^ eps_is_sq);
+
+    // (x, y) = (sqrt(-A-2) u / v, (u - 1) / (u + 1)) or the identity if either
+    // denominator is zero
+    let u_plus_one = u + &one;
+    let u_minus_one = u - &one;
+    let c1_u = &constants::SQRT_MINUS_APLUS2 * u;
+    let mut res = EdwardsPoint{
+        X: &c1_u * &u_plus_one,
+        Y: &u_minus_one * &v,
+        Z: &v * &u_plus_one,
+        T: &c1_u * &u_minus_one,
+    };
+    if res.Z.is_zero().unwrap_u8() == 1u8 {
+        use crate::traits::Identity;
+        res = EdwardsPoint::identity();
+    }
+
+    res
+}
+
+fn process_montgomery_to_edwards(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+    sign_offset: u32,
+    step: u8,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+
+    let u = read_field_element(&compute_buffer_data, offset)?;
+    if u == FieldElement::minus_one() {
+        return Err(Curve25519Error::InvalidPoint.into());
+    }
+
+    let one = FieldElement::one();
+
+    if step == 0 {
+        let offset = offset + 32;
+        compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+            &(&u + &one).to_bytes()
+        );
+        return Ok(());
+    }
+
+    // invert
+    let t3 = read_field_element(&compute_buffer_data, offset + 32 * 4)?;
+    let t19 = read_field_element(&compute_buffer_data, offset + 32 * 5)?;
+    let up1_inv = &t19.pow2k(5) * &t3;
+
+    let y = &(&u - &one) * &up1_inv;
+
+    let sign = (compute_buffer_data[sign_offset as usize] & 0x80) >> 7;
+
+    let mut y_bytes = y.to_bytes();
+    y_bytes[31] ^= sign << 7;
+
+    let offset = offset + 32 * 6;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(&y_bytes);
+
+    Ok(())
+}
+
+fn process_mul_by_cofactor(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+    result_offset: u32,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+    let point = EdwardsPoint::from_bytes(
+        &compute_buffer_data[offset..offset+128]
+    );
+
+    let result_offset = result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 128)?.copy_from_slice(
+        &point.mul_by_cofactor().to_bytes()
+    );
+
+    Ok(())
+}
+
+fn process_build_lookup_table(
+    compute_buffer_info: &AccountInfo,
+    data: &BuildLookupTableData,
+) -> ProgramResult {
+    let compute_buffer_data = compute_buffer_info.try_borrow_data()?;
+
+    let point_offset = data.point_offset as usize;
+    let point = EdwardsPoint::from_bytes(
+        &compute_buffer_data[point_offset..point_offset+128]
+    );
+
+    let table = LookupTable::<ProjectiveNielsPoint>::from(&point);
+
+
+    drop(compute_buffer_data);
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+    let table_offset = data.table_offset as usize;
+    type LUT = LookupTable::<ProjectiveNielsPoint>;
+    compute_slice_mut(&mut compute_buffer_data, table_offset, LUT::TABLE_SIZE)?.copy_from_slice(
+        bytemuck::cast_slice::<LUT, u8>(std::slice::from_ref(&table)));
+
+    Ok(())
+}
+
+// reads the lookup tables from `table_buffer_info` if passed and from the
+// compute buffer otherwise
+fn process_multiscalar_mul<'a>(
+    compute_buffer_info: &AccountInfo<'a>,
+    table_buffer_info: Option<&AccountInfo<'a>>,
+    data: &MultiscalarMulData,
+) -> ProgramResult {
+    let num_inputs = data.num_inputs as usize;
+    if num_inputs > MAX_MULTISCALAR_POINTS {
+        msg!("Too many points");
+        return Err(Curve25519Error::TooManyPoints.into());
+    }
+
+    // deserialize lookup tables
+    let compute_buffer_data = compute_buffer_info.try_borrow_data()?;
+    let table_buffer_data = table_buffer_info.unwrap_or(compute_buffer_info).try_borrow_data()?;
+    let table_offset = u32::from(data.tables_offset) as usize;
+    if table_offset < HEADER_SIZE {
+        msg!("Cannot read tables from header");
+        return Err(Curve25519Error::HeaderOffset.into());
+    }
+    type LUT = LookupTable::<ProjectiveNielsPoint>;
+    let lookup_tables = bytemuck::cast_slice::<u8, LUT>(
+        &table_buffer_data[table_offset..table_offset + LUT::TABLE_SIZE * num_inputs]);
+
+    // deserialize scalars
+    // TODO: just encode the radix_16 values directly?
+    let scalar_offset = u32::from(data.scalars_offset) as usize;
+    let packed_scalar_digits = bytemuck::cast_slice::<u8, [u8; 32]>(
+        &compute_buffer_data[scalar_offset..scalar_offset + 32 * num_inputs]);
+
+    // deserialize point computation
+    let result_offset = u32::from(data.result_offset) as usize;
+    let mut Q = EdwardsPoint::from_bytes(
+        &compute_buffer_data[result_offset..result_offset+128]
+    );
+
+    // run compute
+    for j in (data.start..data.end).rev() {
+        Q = Q.mul_by_pow_2(4);
+        let it = packed_scalar_digits.iter().zip(lookup_tables.iter());
+        for (s_i, lookup_table_i) in it {
+            // R_i = s_{i,j} * P_i
+            let packed_radix = if j & 1 == 1 {
+                (s_i[(j >> 1) as usize] as i8) >> 4
+            } else {
+                (s_i[(j >> 1) as usize] as i8) << 4 >> 4
+            };
+            let R_i = lookup_table_i.select(packed_radix);
+            // Q = Q + R_i
+            Q = (&Q + &R_i).to_extended();
+        }
+    }
+
+    // serialize
+    drop(table_buffer_data);
+    drop(compute_buffer_data);
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 128)?.copy_from_slice(
+        &Q.to_bytes());
+
+    Ok(())
+}
+
+fn process_naf_scalar(
+    compute_buffer_info: &AccountInfo,
+    data: &ScalarUnaryOpData,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let scalar = read_scalar(&compute_buffer_data, data.scalar_offset)?;
+    let naf = scalar.non_adjacent_form(5);
+
+    let result_offset = data.result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, NAF_SIZE)?.copy_from_slice(
+        bytemuck::cast_slice::<i8, u8>(&naf));
+
+    Ok(())
+}
+
+fn process_build_naf_lookup_table(
+    compute_buffer_info: &AccountInfo,
+    data: &BuildLookupTableData,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let point_offset = data.point_offset as usize;
+    let point = EdwardsPoint::from_bytes(
+        &compute_buffer_data[point_offset..point_offset+128]
+    );
+
+    let table = NafLookupTable5::<ProjectiveNielsPoint>::from(&point);
+
+    let table_offset = data.table_offset as usize;
+    type NafLUT = NafLookupTable5::<ProjectiveNielsPoint>;
+    compute_slice_mut(&mut compute_buffer_data, table_offset, NafLUT::TABLE_SIZE)?.copy_from_slice(
+        bytemuck::cast_slice::<NafLUT, u8>(std::slice::from_ref(&table)));
+
+    Ok(())
+}
+
+fn process_vartime_multiscalar_mul(
+    compute_buffer_info: &AccountInfo,
+    data: &MultiscalarMulData,
+) -> ProgramResult {
+    let num_inputs = data.num_inputs as usize;
+    if num_inputs > MAX_VARTIME_MULTISCALAR_POINTS {
+        msg!("Too many points");
+        return Err(Curve25519Error::TooManyPoints.into());
+    }
+    if data.start > data.end || data.end > 64 {
+        msg!("Invalid multiscalar mul steps");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    // reject a header result before running the steps
+    let result_offset = data.result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_info.try_borrow_mut_data()?, result_offset, 128)?;
+
+    // deserialize lookup tables
+    let compute_buffer_data = compute_buffer_info.try_borrow_data()?;
+    let table_offset = data.tables_offset as usize;
+    type NafLUT = NafLookupTable5::<ProjectiveNielsPoint>;
+    let lookup_tables = bytemuck::cast_slice::<u8, NafLUT>(
+        &compute_buffer_data[table_offset..table_offset + NafLUT::TABLE_SIZE * num_inputs]);
+
+    // NAF digits from NafScalar
+    let scalar_offset = data.scalars_offset as usize;
+    let nafs = bytemuck::cast_slice::<u8, [u8; NAF_SIZE]>(
+        &compute_buffer_data[scalar_offset..scalar_offset + NAF_SIZE * num_inputs]);
+
+    // deserialize point computation
+    let mut r = EdwardsPoint::from_bytes(
+        &compute_buffer_data[result_offset..result_offset+128]
+    ).to_projective();
+
+    // run compute. each step is 4 NAF digits
+    for i in (4 * data.start as usize..4 * data.end as usize).rev() {
+        let mut t = r.double();
+
+        for (naf, lookup_table) in nafs.iter().zip(lookup_tables.iter()) {
+            let digit = naf[i] as i8 as i16;
+            if digit == 0 {
+                continue;
+            }
+            // width 5 NAF digits are odd and below 16 in absolute value
+            if digit & 1 == 0 || digit.abs() >= 16 {
+                msg!("Invalid digit");
+                return Err(Curve25519Error::InvalidDigit.into());
+            }
+            if digit > 0 {
+                t = &t.to_extended() + &lookup_table.select(digit as usize);
+            } else {
+                t = &t.to_extended() - &lookup_table.select(-digit as usize);
+            }
+        }
+
+        r = t.to_projective();
+    }
+
+    // serialize
+    drop(compute_buffer_data);
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 128)?.copy_from_slice(
+        &r.to_extended().to_bytes());
+
+    Ok(())
+}
+
+fn process_radix_scalar(
+    compute_buffer_info: &AccountInfo,
+    data: &RadixScalarData,
+) -> ProgramResult {
+    if data.w < 4 || data.w > 8 {
+        msg!("Invalid radix");
+        return Err(Curve25519Error::InvalidRadix.into());
+    }
+
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let scalar = read_scalar(&compute_buffer_data, data.scalar_offset)?;
+    let digits = scalar.to_radix_2w(data.w as usize);
+
+    let result_offset = data.result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 64)?.copy_from_slice(
+        bytemuck::cast_slice::<i8, u8>(&digits));
+
+    Ok(())
+}
+
+fn process_pack_scalar_radix_2w(
+    compute_buffer_info: &AccountInfo,
+    data: &RadixScalarData,
+) -> ProgramResult {
+    if data.w < 4 || data.w > 7 {
+        msg!("Invalid radix");
+        return Err(Curve25519Error::InvalidRadix.into());
+    }
+
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let scalar = read_scalar(&compute_buffer_data, data.scalar_offset)?;
+    let packed = scalar.to_packed_radix_2w(data.w as usize);
+
+    let result_offset = data.result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, scalar::PACKED_RADIX_2W_SIZE)?
+        .copy_from_slice(&packed);
+
+    Ok(())
+}
+
+// writes the `size` multiples 1P..sizeP directly into the buffer. The larger
+// tables don't fit in a stack frame
+fn process_build_lookup_table_radix(
+    compute_buffer_info: &AccountInfo,
+    data: &BuildLookupTableData,
+    size: usize,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let point_offset = data.point_offset as usize;
+    let point = EdwardsPoint::from_bytes(
+        &compute_buffer_data[point_offset..point_offset+128]
+    );
+
+    let table_offset = data.table_offset as usize;
+    let points = bytemuck::cast_slice_mut::<u8, ProjectiveNielsPoint>(
+        compute_slice_mut(&mut compute_buffer_data, table_offset, 40 * 4 * size)?);
+
+    points[0] = point.to_projective_niels();
+    for j in 0..size-1 {
+        points[j + 1] = (&point + &points[j]).to_extended().to_projective_niels();
+    }
+
+    Ok(())
+}
+
+// MultiscalarMul over the `to_packed_radix_2w(w)` digits with tables of type
+// `T` read through `select`
+fn process_multiscalar_mul_radix<T: bytemuck::Pod>(
+    compute_buffer_info: &AccountInfo,
+    data: &MultiscalarMulData,
+    w: usize,
+    max_points: usize,
+    select: fn(&T, i8) -> ProjectiveNielsPoint,
+) -> ProgramResult {
+    let num_inputs = data.num_inputs as usize;
+    if num_inputs > max_points {
+        msg!("Too many points");
+        return Err(Curve25519Error::TooManyPoints.into());
+    }
+    if data.start > data.end || data.end as usize > Scalar::to_radix_2w_size_hint(w) {
+        msg!("Invalid multiscalar mul steps");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    // reject a header result before running the steps
+    let result_offset = data.result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_info.try_borrow_mut_data()?, result_offset, 128)?;
+
+    // deserialize lookup tables
+    let compute_buffer_data = compute_buffer_info.try_borrow_data()?;
+    let table_offset = data.tables_offset as usize;
+    let table_size = std::mem::size_of::<T>();
+    let lookup_tables = bytemuck::cast_slice::<u8, T>(
+        &compute_buffer_data[table_offset..table_offset + table_size * num_inputs]);
+
+    // packed digits from PackScalarRadix2w
+    let packed_size = scalar::PACKED_RADIX_2W_SIZE;
+    let scalar_offset = data.scalars_offset as usize;
+    let packed_scalars = &compute_buffer_data[
+        scalar_offset..scalar_offset + packed_size * num_inputs];
+
+    // deserialize point computation
+    let mut Q = EdwardsPoint::from_bytes(
+        &compute_buffer_data[result_offset..result_offset+128]
+    );
+
+    // run compute
+    for j in (data.start as usize..data.end as usize).rev() {
+        Q = Q.mul_by_pow_2(w as u32);
+        let it = packed_scalars.chunks_exact(packed_size).zip(lookup_tables.iter());
+        for (s_i, lookup_table_i) in it {
+            // R_i = s_{i,j} * P_i
+            let R_i = select(lookup_table_i, Scalar::packed_radix_2w_digit(s_i, w, j));
+            // Q = Q + R_i
+            Q = (&Q + &R_i).to_extended();
+        }
+    }
+
+    // serialize
+    drop(compute_buffer_data);
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 128)?.copy_from_slice(
+        &Q.to_bytes());
+
+    Ok(())
+}
+
+// offsets into the BatchNormalizeData state
+const BATCH_INVERT_INVERSE: usize = 32 * 5;
+const BATCH_INVERT_PRODUCTS: usize = 32 * 6;
+
+fn process_batch_normalize_prefix(
+    compute_buffer_info: &AccountInfo,
+    data: &BatchNormalizeData,
+) -> ProgramResult {
+    if data.start > data.end {
+        msg!("Invalid batch normalize steps");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    let state_offset = data.state_offset as usize;
+
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let mut product = if data.start == 0 {
+        FieldElement::one()
+    } else {
+        read_field_element(&compute_buffer_data, state_offset)?
+    };
+
+    let points_offset = data.points_offset as usize;
+    for i in data.start as usize..data.end as usize {
+        let point_offset = points_offset + 160 * i;
+        let point = bytemuck::from_bytes::<ProjectiveNielsPoint>(
+            &compute_buffer_data[point_offset..point_offset+160]);
+        product = &product * &point.Z;
+
+        let product_offset = state_offset + BATCH_INVERT_PRODUCTS + 32 * i;
+        compute_slice_mut(&mut compute_buffer_data, product_offset, 32)?.copy_from_slice(
+            &product.to_bytes());
+    }
+
+    compute_slice_mut(&mut compute_buffer_data, state_offset, 32)?.copy_from_slice(
+        &product.to_bytes());
+
+    Ok(())
+}
+
+fn process_invert_fini(
+    compute_buffer_info: &AccountInfo,
+    offset: u32,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let offset = offset as usize;
+
+    let t3 = read_field_element(&compute_buffer_data, offset + 32 * 3)?;
+    let t19 = read_field_element(&compute_buffer_data, offset + 32 * 4)?;
+
+    let inverse = &t19.pow2k(5) * &t3;
+
+    let offset = offset + BATCH_INVERT_INVERSE;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(&inverse.to_bytes());
+
+    Ok(())
+}
+
+fn process_batch_normalize(
+    compute_buffer_info: &AccountInfo,
+    data: &BatchNormalizeData,
+) -> ProgramResult {
+    if data.start > data.end {
+        msg!("Invalid batch normalize steps");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    let state_offset = data.state_offset as usize;
+    let result_offset = data.result_offset as usize;
+
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    // inverse of the product of Zs 0..end
+    let mut inverse = read_field_element(
+        &compute_buffer_data, state_offset + BATCH_INVERT_INVERSE)?;
+
+    let points_offset = data.points_offset as usize;
+    for i in (data.start as usize..data.end as usize).rev() {
+        let point_offset = points_offset + 160 * i;
+        let point = *bytemuck::from_bytes::<ProjectiveNielsPoint>(
+            &compute_buffer_data[point_offset..point_offset+160]);
+
+        let recip = if i == 0 {
+            inverse
+        } else {
+            let product = read_field_element(
+                &compute_buffer_data, state_offset + BATCH_INVERT_PRODUCTS + 32 * (i - 1))?;
+            &inverse * &product
+        };
+        inverse = &inverse * &point.Z;
+
+        let affine = AffineNielsPoint{
+            y_plus_x:  &point.Y_plus_X * &recip,
+            y_minus_x: &point.Y_minus_X * &recip,
+            xy2d:      &point.T2d * &recip,
+        };
+        let affine_offset = result_offset + 120 * i;
+        compute_slice_mut(&mut compute_buffer_data, affine_offset, 120)?.copy_from_slice(
+            bytemuck::bytes_of(&affine));
+    }
+
+    compute_slice_mut(
+        &mut compute_buffer_data, state_offset + BATCH_INVERT_INVERSE, 32,
+    )?.copy_from_slice(&inverse.to_bytes());
+
+    Ok(())
+}
+
+fn check_batch_invert_data(
+    data: &BatchInvertData,
+) -> ProgramResult {
+    if data.start > data.end || data.stride < 32 {
+        msg!("Invalid batch invert steps");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    Ok(())
+}
+
+fn process_batch_invert_prefix(
+    compute_buffer_info: &AccountInfo,
+    data: &BatchInvertData,
+) -> ProgramResult {
+    use subtle::ConditionallySelectable;
+
+    check_batch_invert_data(data)?;
+
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let state_offset = data.state_offset as usize;
+    let mut product = if data.start == 0 {
+        FieldElement::one()
+    } else {
+        read_field_element(&compute_buffer_data, state_offset)?
+    };
+
+    let elements_offset = data.elements_offset as usize;
+    let stride = data.stride as usize;
+    for i in data.start as usize..data.end as usize {
+        let element = read_field_element(&compute_buffer_data, elements_offset + stride * i)?;
+        // skip zeros so they don't zero out the rest of the batch
+        let element = FieldElement::conditional_select(
+            &element, &FieldElement::one(), element.is_zero());
+        product = &product * &element;
+
+        let product_offset = state_offset + BATCH_INVERT_PRODUCTS + 32 * i;
+        compute_slice_mut(&mut compute_buffer_data, product_offset, 32)?.copy_from_slice(
+            &product.to_bytes());
+    }
+
+    compute_slice_mut(&mut compute_buffer_data, state_offset, 32)?.copy_from_slice(
+        &product.to_bytes());
+
+    Ok(())
+}
+
+fn process_batch_invert(
+    compute_buffer_info: &AccountInfo,
+    data: &BatchInvertData,
+) -> ProgramResult {
+    use subtle::ConditionallySelectable;
+
+    check_batch_invert_data(data)?;
+
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    // inverse of the product of elements 0..end
+    let state_offset = data.state_offset as usize;
+    let mut inverse = read_field_element(
+        &compute_buffer_data, state_offset + BATCH_INVERT_INVERSE)?;
+
+    let elements_offset = data.elements_offset as usize;
+    let stride = data.stride as usize;
+    for i in (data.start as usize..data.end as usize).rev() {
+        let element_offset = elements_offset + stride * i;
+        let element = read_field_element(&compute_buffer_data, element_offset)?;
+        let is_zero = element.is_zero();
+
+        let mut recip = if i == 0 {
+            inverse
+        } else {
+            let product = read_field_element(
+                &compute_buffer_data, state_offset + BATCH_INVERT_PRODUCTS + 32 * (i - 1))?;
+            &inverse * &product
+        };
+        inverse = &inverse * &FieldElement::conditional_select(
+            &element, &FieldElement::one(), is_zero);
+        recip.conditional_assign(&FieldElement::zero(), is_zero);
+
+        compute_slice_mut(&mut compute_buffer_data, element_offset, 32)?.copy_from_slice(
+            &recip.to_bytes());
+    }
+
+    compute_slice_mut(
+        &mut compute_buffer_data, state_offset + BATCH_INVERT_INVERSE, 32,
+    )?.copy_from_slice(&inverse.to_bytes());
+
+    Ok(())
+}
+
+fn process_multiscalar_mul_affine(
+    compute_buffer_info: &AccountInfo,
+    data: &MultiscalarMulData,
+) -> ProgramResult {
+    let num_inputs = data.num_inputs as usize;
+    if num_inputs > MAX_MULTISCALAR_AFFINE_POINTS {
+        msg!("Too many points");
+        return Err(Curve25519Error::TooManyPoints.into());
     }
-    if !input_header.finalized {
-        msg!("Input buffer not finalized");
-        return Err(ProgramError::InvalidArgument);
+    if data.start > data.end || data.end > 64 {
+        msg!("Invalid multiscalar mul steps");
+        return Err(Curve25519Error::InvalidStep.into());
     }
 
-    let input_offset = offsets.input_offset as usize;
-    if input_offset < HEADER_SIZE {
-        msg!("Cannot copy from header");
-        return Err(ProgramError::InvalidArgument);
-    }
+    // reject a header result before running the steps
+    let result_offset = data.result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_info.try_borrow_mut_data()?, result_offset, 128)?;
 
-    let compute_offset = offsets.compute_offset as usize;
-    if compute_offset < HEADER_SIZE {
-        msg!("Cannot copy to header");
-        return Err(ProgramError::InvalidArgument);
+    // deserialize lookup tables
+    let compute_buffer_data = compute_buffer_info.try_borrow_data()?;
+    let table_offset = data.tables_offset as usize;
+    type AffineLUT = LookupTable::<AffineNielsPoint>;
+    let lookup_tables = bytemuck::cast_slice::<u8, AffineLUT>(
+        &compute_buffer_data[table_offset..table_offset + AffineLUT::TABLE_SIZE * num_inputs]);
+
+    // deserialize scalars
+    let scalar_offset = data.scalars_offset as usize;
+    let packed_scalar_digits = bytemuck::cast_slice::<u8, [u8; 32]>(
+        &compute_buffer_data[scalar_offset..scalar_offset + 32 * num_inputs]);
+
+    // deserialize point computation
+    let mut Q = EdwardsPoint::from_bytes(
+        &compute_buffer_data[result_offset..result_offset+128]
+    );
+
+    // run compute
+    for j in (data.start..data.end).rev() {
+        Q = Q.mul_by_pow_2(4);
+        let it = packed_scalar_digits.iter().zip(lookup_tables.iter());
+        for (s_i, lookup_table_i) in it {
+            // R_i = s_{i,j} * P_i
+            let packed_radix = if j & 1 == 1 {
+                (s_i[(j >> 1) as usize] as i8) >> 4
+            } else {
+                (s_i[(j >> 1) as usize] as i8) << 4 >> 4
+            };
+            let R_i = lookup_table_i.select(packed_radix);
+            // Q = Q + R_i
+            Q = (&Q + &R_i).to_extended();
+        }
     }
 
+    // serialize
+    drop(compute_buffer_data);
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
-    let copy_bytes = offsets.bytes as usize;
-    compute_buffer_data[
-        compute_offset..compute_offset+copy_bytes
-    ].copy_from_slice(&input_buffer_data[
-        input_offset..input_offset+copy_bytes
-    ]);
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 128)?.copy_from_slice(
+        &Q.to_bytes());
 
     Ok(())
 }
 
-fn process_write_edwards_identity(
+fn process_pippenger_accumulate(
     compute_buffer_info: &AccountInfo,
-    offset: u32,
+    data: &PippengerData,
 ) -> ProgramResult {
-    let offset = offset as usize;
-    if offset < HEADER_SIZE {
-        msg!("Cannot copy to header");
-        return Err(ProgramError::InvalidArgument);
+    if data.start > data.end
+            || data.digit_index as usize >= Scalar::to_radix_2w_size_hint(PIPPENGER_WINDOW) {
+        msg!("Invalid pippenger step");
+        return Err(Curve25519Error::InvalidStep.into());
     }
 
+    let state_offset = data.state_offset as usize;
+
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    use crate::traits::Identity;
-    compute_buffer_data[
-        offset..offset+128
-    ].copy_from_slice(&EdwardsPoint::identity().to_bytes());
+    // new digit. clear the buckets
+    let buckets_offset = state_offset + 128 * 3;
+    if data.start == 0 {
+        use crate::traits::Identity;
+        let identity = EdwardsPoint::identity().to_bytes();
+        let buckets = compute_slice_mut(
+            &mut compute_buffer_data, buckets_offset, 128 * PIPPENGER_BUCKETS)?;
+        for bucket in buckets.chunks_exact_mut(128) {
+            bucket.copy_from_slice(&identity);
+        }
+    }
+
+    for i in data.start as usize..data.end as usize {
+        let digit_offset = data.digits_offset as usize + 64 * i + data.digit_index as usize;
+        let digit = compute_buffer_data[digit_offset] as i8 as i16;
+        if digit == 0 {
+            continue;
+        }
+        let b = (digit.abs() - 1) as usize;
+        if b >= PIPPENGER_BUCKETS {
+            msg!("Invalid digit");
+            return Err(Curve25519Error::InvalidDigit.into());
+        }
+
+        let point_offset = data.points_offset as usize + 128 * i;
+        let point = EdwardsPoint::from_bytes(
+            &compute_buffer_data[point_offset..point_offset+128]
+        ).to_projective_niels();
+
+        let bucket_offset = buckets_offset + 128 * b;
+        let bucket = EdwardsPoint::from_bytes(
+            &compute_buffer_data[bucket_offset..bucket_offset+128]
+        );
+
+        let bucket = if digit > 0 {
+            (&bucket + &point).to_extended()
+        } else {
+            (&bucket - &point).to_extended()
+        };
+        compute_slice_mut(&mut compute_buffer_data, bucket_offset, 128)?.copy_from_slice(
+            &bucket.to_bytes());
+    }
 
     Ok(())
 }
 
-fn process_invsqrt_init(
+fn process_pippenger_sum_buckets(
     compute_buffer_info: &AccountInfo,
-    offset: u32,
+    data: &PippengerData,
 ) -> ProgramResult {
+    if data.start > data.end || data.end as usize > PIPPENGER_BUCKETS {
+        msg!("Invalid pippenger step");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    let state_offset = data.state_offset as usize;
+
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    let offset = offset as usize;
+    // C + (C+B) + (C+B+A) for buckets 1*A, 2*B, 3*C
+    let intermediate_offset = state_offset + 128;
+    let sum_offset = state_offset + 128 * 2;
+    let (mut buckets_intermediate_sum, mut buckets_sum) = if data.end as usize == PIPPENGER_BUCKETS {
+        use crate::traits::Identity;
+        (EdwardsPoint::identity(), EdwardsPoint::identity())
+    } else {
+        (
+            EdwardsPoint::from_bytes(
+                &compute_buffer_data[intermediate_offset..intermediate_offset+128]),
+            EdwardsPoint::from_bytes(
+                &compute_buffer_data[sum_offset..sum_offset+128]),
+        )
+    };
 
-    let u = FieldElement::one();
-    let v = read_field_element(&compute_buffer_data, offset)?;
+    let buckets_offset = state_offset + 128 * 3;
+    for b in (data.start as usize..data.end as usize).rev() {
+        let bucket_offset = buckets_offset + 128 * b;
+        buckets_intermediate_sum += EdwardsPoint::from_bytes(
+            &compute_buffer_data[bucket_offset..bucket_offset+128]);
+        buckets_sum += buckets_intermediate_sum;
+    }
 
-    let offset = offset + 32;
-    compute_buffer_data[offset..offset+32].copy_from_slice(
-        &FieldElement::sqrt_ratio_i_pow_p58_input(&u, &v).to_bytes(),
+    if data.start == 0 {
+        // fold this digit into the result
+        let result = EdwardsPoint::from_bytes(
+            &compute_buffer_data[state_offset..state_offset+128]
+        );
+        let result = result.mul_by_pow_2(PIPPENGER_WINDOW as u32) + buckets_sum;
+        compute_slice_mut(&mut compute_buffer_data, state_offset, 128)?.copy_from_slice(
+            &result.to_bytes());
+    } else {
+        compute_slice_mut(&mut compute_buffer_data, intermediate_offset, 128)?.copy_from_slice(
+            &buckets_intermediate_sum.to_bytes());
+        compute_slice_mut(&mut compute_buffer_data, sum_offset, 128)?.copy_from_slice(
+            &buckets_sum.to_bytes());
+    }
+
+    Ok(())
+}
+
+fn process_basepoint_mul(
+    compute_buffer_info: &AccountInfo,
+    data: &BasepointMulData,
+) -> ProgramResult {
+    if data.start > data.end || data.end > EdwardsBasepointTable::MUL_STEPS {
+        msg!("Invalid basepoint mul steps");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    // unpack the radix 16 digits
+    let scalar_offset = data.scalar_offset as usize;
+    let mut digits = [0i8; 64];
+    for (i, packed) in compute_buffer_data[scalar_offset..scalar_offset+32].iter().enumerate() {
+        digits[2 * i] = (*packed as i8) << 4 >> 4;
+        digits[2 * i + 1] = (*packed as i8) >> 4;
+    }
+
+    let result_offset = data.result_offset as usize;
+    let P = EdwardsPoint::from_bytes(
+        &compute_buffer_data[result_offset..result_offset+128]
     );
 
+    // take a reference so that the table is not copied onto the stack
+    let table: &'static EdwardsBasepointTable = &constants::ED25519_BASEPOINT_TABLE;
+    let res = table.mul_steps(&digits, &P, data.start, data.end);
+
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 128)?.copy_from_slice(
+        &res.to_bytes());
+
     Ok(())
 }
 
-fn process_invsqrt_fini(
+fn process_decompress_with_witness(
     compute_buffer_info: &AccountInfo,
     offset: u32,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
     let offset = offset as usize;
+    let point = CompressedRistretto::from_slice(
+        &compute_buffer_data[offset..offset+32]
+    );
 
-    let u = FieldElement::one();
-    let v = read_field_element(&compute_buffer_data, offset)?;
-    let pow_p22501_output = read_field_element(&compute_buffer_data, offset + 32 * 5)?;
+    let witness = read_field_element(&compute_buffer_data, offset + 32)?;
 
-    let (ok, r) = FieldElement::sqrt_ratio_i_pow_p58_output(&u, &v, &pow_p22501_output);
+    let Iinv_sq = point.decompress_init().ok_or(Curve25519Error::InvalidPoint)?;
+    // if !ok the witness should multiply to sqrt(-1) (aka i)
+    if &Iinv_sq * &witness.square() != FieldElement::one() {
+        msg!("Bad witness");
+        return Err(Curve25519Error::BadWitness.into());
+    }
 
-    if ok.unwrap_u8() == 0u8 {
-        return Err(ProgramError::InvalidArgument);
+    // some duplicate work in this...
+    let point = point.decompress_fini(&witness).ok_or(Curve25519Error::InvalidPoint)?;
+
+    let offset = offset + 32 * 2;
+    compute_slice_mut(&mut compute_buffer_data, offset, 128)?.copy_from_slice(
+        &point.0.to_bytes()
+    );
+
+    Ok(())
+}
+
+// checks that `witness` is the root sqrt_ratio_i(u, v) would return.
+// Returns whether u/v was square
+fn check_sqrt_ratio_i_witness(
+    u: &FieldElement,
+    v: &FieldElement,
+    witness: &FieldElement,
+) -> Result<bool, ProgramError> {
+    if witness.is_negative().unwrap_u8() == 1u8 {
+        msg!("Bad witness");
+        return Err(Curve25519Error::BadWitness.into());
     }
 
-    let offset = offset + 32 * 6;
-    compute_buffer_data[offset..offset+32].copy_from_slice(&r.to_bytes());
+    // sqrt_ratio_i(u, 0) is zero
+    if v.is_zero().unwrap_u8() == 1u8 {
+        if witness.is_zero().unwrap_u8() != 1u8 {
+            msg!("Bad witness");
+            return Err(Curve25519Error::BadWitness.into());
+        }
+        return Ok(u.is_zero().unwrap_u8() == 1u8);
+    }
+
+    let check = v * &witness.square();
+    if &check == u {
+        Ok(true)
+    } else if check == &constants::SQRT_M1 * u {
+        Ok(false)
+    } else {
+        msg!("Bad witness");
+        Err(Curve25519Error::BadWitness.into())
+    }
+}
 
+fn check_invert_witness(
+    element: &FieldElement,
+    witness: &FieldElement,
+) -> ProgramResult {
+    if element * witness != FieldElement::one() {
+        msg!("Bad witness");
+        return Err(Curve25519Error::BadWitness.into());
+    }
     Ok(())
 }
 
-fn process_pow22501_p1(
+// [ point (128 bytes), 1/Z, compressed ]
+fn process_compress_edwards_with_witness(
     compute_buffer_info: &AccountInfo,
     offset: u32,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
     let offset = offset as usize;
-    let element = read_field_element(&compute_buffer_data, offset)?;
 
-    let (t17, t13, t3) = FieldElement::pow22001(&element);
+    let point = EdwardsPoint::from_bytes(
+        &compute_buffer_data[offset..offset+128]
+    );
 
-    let offset = offset + 32;
-    compute_buffer_data[offset..offset+32].copy_from_slice(&t17.to_bytes());
+    let recip = read_field_element(&compute_buffer_data, offset + 128)?;
+    check_invert_witness(&point.Z, &recip)?;
 
-    let offset = offset + 32;
-    compute_buffer_data[offset..offset+32].copy_from_slice(&t13.to_bytes());
+    let x = &point.X * &recip;
+    let y = &point.Y * &recip;
+    let mut s: [u8; 32];
 
-    let offset = offset + 32;
-    compute_buffer_data[offset..offset+32].copy_from_slice(&t3.to_bytes());
+    s = y.to_bytes();
+    s[31] ^= x.is_negative().unwrap_u8() << 7;
+
+    let offset = offset + 128 + 32;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(&s);
 
     Ok(())
 }
 
-fn process_pow22501_p2(
+// [ compressed, x, point (128 bytes) ]
+fn process_decompress_edwards_with_witness(
     compute_buffer_info: &AccountInfo,
     offset: u32,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
     let offset = offset as usize;
-    let t17 = read_field_element(&compute_buffer_data, offset)?;
-    let t13 = read_field_element(&compute_buffer_data, offset + 32)?;
 
-    let t19 = FieldElement::pow22501(&t17, &t13);
+    let Y = read_field_element(&compute_buffer_data, offset)?;
+    let Z = FieldElement::one();
+    let YY = Y.square();
+    let u = &YY - &Z;                            // u =  y²-1
+    let v = &(&YY * &constants::EDWARDS_D) + &Z; // v = dy²+1
 
-    let offset = offset + 32 * 3; // skip t3
-    compute_buffer_data[offset..offset+32].copy_from_slice(&t19.to_bytes());
+    let mut X = read_field_element(&compute_buffer_data, offset + 32)?;
+    if !check_sqrt_ratio_i_witness(&u, &v, &X)? {
+        msg!("Invalid y coordinate");
+        return Err(Curve25519Error::InvalidPoint.into());
+    }
+
+    use subtle::{Choice, ConditionallyNegatable};
+    let compressed_sign_bit = Choice::from(compute_buffer_data[offset + 31] >> 7);
+    X.conditional_negate(compressed_sign_bit);
+
+    let res = EdwardsPoint{ X, Y, Z, T: &X * &Y };
+
+    let offset = offset + 32 * 2;
+    compute_slice_mut(&mut compute_buffer_data, offset, 128)?.copy_from_slice(
+        &res.to_bytes());
 
     Ok(())
 }
 
-fn process_decompress_init(
+// [ r_0, s, point (128 bytes) ]
+fn process_elligator_with_witness(
     compute_buffer_info: &AccountInfo,
     offset: u32,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
     let offset = offset as usize;
-    let point = CompressedRistretto::from_slice(
-        &compute_buffer_data[offset..offset+32]
-    );
 
-    let offset = offset + 32;
-    compute_buffer_data[offset..offset+32].copy_from_slice(
-        &point.decompress_init().ok_or(ProgramError::InvalidArgument)?.to_bytes()
-    );
+    let r_0 = read_field_element(&compute_buffer_data, offset)?;
+    let (r, N_s, D) = elligator_ratio(&r_0);
+
+    let s = read_field_element(&compute_buffer_data, offset + 32)?;
+    let Ns_D_is_sq = check_sqrt_ratio_i_witness(&N_s, &D, &s)?;
+
+    let res = elligator_point(&r_0, &r, &D, (Ns_D_is_sq as u8).into(), s);
+
+    let offset = offset + 32 * 2;
+    compute_slice_mut(&mut compute_buffer_data, offset, 128)?.copy_from_slice(
+        &res.0.to_bytes());
 
     Ok(())
 }
 
-fn process_decompress_fini(
+// [ r_0, 1/(1+2r²), sqrt_ratio_i(eps, 1), output ] where output is the
+// MontgomeryPoint for step 2 and the RFC 9380 EdwardsPoint for step 3
+fn process_montgomery_elligator_with_witness(
     compute_buffer_info: &AccountInfo,
     offset: u32,
+    step: u8,
 ) -> ProgramResult {
+    if step != 2 && step != 3 {
+        msg!("Invalid step");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
     let offset = offset as usize;
-    let point = CompressedRistretto::from_slice(
-        &compute_buffer_data[offset..offset+32]
-    );
 
-    let offset = offset + 32 * 7;
-    let element = read_field_element(&compute_buffer_data, offset)?;
+    let one = FieldElement::one();
 
-    let res = point.decompress_fini(&element).ok_or(ProgramError::InvalidArgument)?;
+    let r_0 = read_field_element(&compute_buffer_data, offset)?;
+    let d_1 = &one + &r_0.square2(); /* 2r^2 */
 
-    let offset = offset + 32;
-    compute_buffer_data[offset..offset+128].copy_from_slice(
-        &res.0.to_bytes());
+    let d_1_inv = read_field_element(&compute_buffer_data, offset + 32)?;
+    check_invert_witness(&d_1, &d_1_inv)?;
+
+    let (d, eps) = montgomery_elligator_eps(&d_1_inv);
+
+    let eps_sqrt = read_field_element(&compute_buffer_data, offset + 32 * 2)?;
+    let eps_is_sq: subtle::Choice = (check_sqrt_ratio_i_witness(&eps, &one, &eps_sqrt)? as u8).into();
+
+    let u = montgomery_elligator_u(&d, eps_is_sq);
+
+    let offset = offset + 32 * 3;
+    if step == 2 {
+        compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(
+            &u.to_bytes());
+    } else {
+        let res = montgomery_elligator_edwards(&r_0, &u, eps_is_sq, eps_sqrt);
+        compute_slice_mut(&mut compute_buffer_data, offset, 128)?.copy_from_slice(
+            &res.to_bytes());
+    }
 
     Ok(())
 }
 
-fn process_decompress_edwards(
+// [ u, 1/(u+1), compressed edwards ]
+fn process_montgomery_to_edwards_with_witness(
     compute_buffer_info: &AccountInfo,
     offset: u32,
-    step: u8,
+    sign_offset: u32,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
     let offset = offset as usize;
-    let compressed_bytes = &compute_buffer_data[offset..offset+32];
-    let Y = read_field_element(&compute_buffer_data, offset)?;
-    let Z = FieldElement::one();
-    let YY = Y.square();
-    let u = &YY - &Z;                            // u =  y²-1
-    let v = &(&YY * &constants::EDWARDS_D) + &Z; // v = dy²+1
 
-    if step == 0 {
-        let offset = offset + 32;
-        compute_buffer_data[offset..offset+32].copy_from_slice(
-            &FieldElement::sqrt_ratio_i_pow_p58_input(&u, &v).to_bytes(),
-        );
-        return Ok(());
+    let u = read_field_element(&compute_buffer_data, offset)?;
+    if u == FieldElement::minus_one() {
+        return Err(Curve25519Error::InvalidPoint.into());
     }
 
-    let pow_p22501_output = read_field_element(&compute_buffer_data, offset + 32 * 5)?;
-    let (is_valid_y_coord, mut X) = FieldElement::sqrt_ratio_i_pow_p58_output(&u, &v, &pow_p22501_output);
+    let one = FieldElement::one();
 
-    if is_valid_y_coord.unwrap_u8() != 1u8 {
-        msg!("Invalid y coordinate");
-        return Err(ProgramError::InvalidArgument);
-    }
+    let up1_inv = read_field_element(&compute_buffer_data, offset + 32)?;
+    check_invert_witness(&(&u + &one), &up1_inv)?;
 
-    use subtle::{Choice, ConditionallyNegatable};
-    let compressed_sign_bit = Choice::from(compressed_bytes[31] >> 7);
-    X.conditional_negate(compressed_sign_bit);
+    let y = &(&u - &one) * &up1_inv;
 
-    let res = EdwardsPoint{ X, Y, Z, T: &X * &Y };
+    let sign = (compute_buffer_data[sign_offset as usize] & 0x80) >> 7;
 
-    let offset = offset + 32 * 6;
-    compute_buffer_data[offset..offset+128].copy_from_slice(
-        &res.to_bytes());
+    let mut y_bytes = y.to_bytes();
+    y_bytes[31] ^= sign << 7;
+
+    let offset = offset + 32 * 2;
+    compute_slice_mut(&mut compute_buffer_data, offset, 32)?.copy_from_slice(&y_bytes);
 
     Ok(())
 }
 
-fn process_compress_edwards(
+fn process_edwards_add(
     compute_buffer_info: &AccountInfo,
-    offset: u32,
-    step: u8,
+    data: &EdwardsBinaryOpData,
+    negate_rhs: bool,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    let offset = offset as usize;
-    let point = EdwardsPoint::from_bytes(
-        &compute_buffer_data[offset..offset+128]
+    let lhs_offset = data.lhs_offset as usize;
+    let lhs = EdwardsPoint::from_bytes(
+        &compute_buffer_data[lhs_offset..lhs_offset+128]
     );
 
-    if step == 0 {
-        // could be probably skip this if adding some kind of result offset to every
-        // instruction...
-        let offset = offset + 32 * 4;
-        compute_buffer_data[offset..offset+32].copy_from_slice(
-            &point.Z.to_bytes()
-        );
-        return Ok(());
-    }
+    let rhs_offset = data.rhs_offset as usize;
+    let rhs = EdwardsPoint::from_bytes(
+        &compute_buffer_data[rhs_offset..rhs_offset+128]
+    ).to_projective_niels();
 
-    // invert
-    let t3 = read_field_element(&compute_buffer_data, offset + 32 * 7)?;
-    let t19 = read_field_element(&compute_buffer_data, offset + 32 * 8)?;
-    let recip = &t19.pow2k(5) * &t3;
+    let res = if negate_rhs {
+        (&lhs + &(-&rhs)).to_extended()
+    } else {
+        (&lhs + &rhs).to_extended()
+    };
 
-    let x = &point.X * &recip;
-    let y = &point.Y * &recip;
-    let mut s: [u8; 32];
+    let result_offset = data.result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 128)?.copy_from_slice(
+        &res.to_bytes());
+
+    Ok(())
+}
+
+// compares against the identity if `rhs_offset` is None. a failed assert
+// rejects like an invalid input does, recording the first failing
+// `instruction_num` and completing soft_fail buffers. points with a zero Z
+// never compare equal
+fn process_assert_ristretto_equal(
+    compute_buffer_info: &AccountInfo,
+    instruction_num: u32,
+    lhs_offset: u32,
+    rhs_offset: Option<u32>,
+) -> ProgramResult {
+    use crate::traits::Identity;
+    use subtle::ConstantTimeEq;
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let lhs_offset = lhs_offset as usize;
+    let lhs = RistrettoPoint(EdwardsPoint::from_bytes(
+        &compute_buffer_data[lhs_offset..lhs_offset+128]
+    ));
+
+    let rhs = match rhs_offset {
+        Some(rhs_offset) => {
+            let rhs_offset = rhs_offset as usize;
+            RistrettoPoint(EdwardsPoint::from_bytes(
+                &compute_buffer_data[rhs_offset..rhs_offset+128]
+            ))
+        }
+        None => RistrettoPoint::identity(),
+    };
+
+    let mut compute_header = {
+        let mut compute_buffer_ptr: &[u8] = *compute_buffer_data;
+        ComputeHeader::deserialize(&mut compute_buffer_ptr)?
+    };
 
-    s = y.to_bytes();
-    s[31] ^= x.is_negative().unwrap_u8() << 7;
+    // ct_eq compares X1·Y2 against Y1·X2, which the all-zero point (from
+    // e.g. adding a zeroed ProjectiveNielsPoint) satisfies against anything
+    let degenerate = lhs.0.Z == FieldElement::zero() || rhs.0.Z == FieldElement::zero();
+    let equal: bool = !degenerate && bool::from(lhs.ct_eq(&rhs));
+    compute_header.verdict = match (compute_header.verdict, equal) {
+        (Verdict::Rejected, _) | (_, false) => Verdict::Rejected,
+        (_, true) => Verdict::Accepted,
+    };
+    msg!("Verdict {:?}", compute_header.verdict);
+    if !equal {
+        if compute_header.rejected_instruction.is_none() {
+            compute_header.rejected_instruction = Some(instruction_num);
+        }
+        if compute_header.soft_fail {
+            compute_header.completed = true;
+        }
+    }
 
-    let offset = offset + 32 * 9;
-    compute_buffer_data[offset..offset+32].copy_from_slice(&s);
+    let compute_header_bytes = compute_header.try_to_vec()?;
+    compute_buffer_data[..compute_header_bytes.len()].copy_from_slice(
+        compute_header_bytes.as_slice());
 
     Ok(())
 }
 
-fn process_elligator(
-compute_buffer_info: &AccountInfo,
+fn process_sha512_init(
+    compute_buffer_info: &AccountInfo,
     offset: u32,
-    step: u8,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
     let offset = offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, offset, sha512::STATE_SIZE)?.copy_from_slice(
+        &sha512::Sha512::new().to_bytes());
 
-    let i = &constants::SQRT_M1;
-    let d = &constants::EDWARDS_D;
-    let d_minus_one_sq = &constants::EDWARDS_D_MINUS_ONE_SQUARED;
-    let one_minus_d_sq = &constants::ONE_MINUS_EDWARDS_D_SQUARED;
-    let mut c = constants::MINUS_ONE;
-
-    let one = FieldElement::one();
+    Ok(())
+}
 
-    let r_0 = read_field_element(&compute_buffer_data, offset)?;
-    let r = i * &r_0.square();
-    let N_s = &(&r + &one) * &one_minus_d_sq;
-    let D = &(&c - &(d * &r)) * &(&r + d);
+fn process_sha512_update(
+    compute_buffer_info: &AccountInfo,
+    data: &Sha512UpdateData,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    if step == 0 {
-        let offset = offset + 32;
-        compute_buffer_data[offset..offset+32].copy_from_slice(
-            &FieldElement::sqrt_ratio_i_pow_p58_input(&N_s, &D).to_bytes(),
-        );
-        return Ok(());
-    }
+    let state_offset = data.state_offset as usize;
+    let mut hasher = sha512::Sha512::from_bytes(
+        &compute_buffer_data[state_offset..state_offset+sha512::STATE_SIZE])?;
 
-    let pow_p22501_output = read_field_element(&compute_buffer_data, offset + 32 * 5)?;
-    let (Ns_D_is_sq, mut s) = FieldElement::sqrt_ratio_i_pow_p58_output(&N_s, &D, &pow_p22501_output);
+    // hashing the header is fine, e.g. to bind the buffer keys
+    let data_offset = data.data_offset as usize;
+    hasher.update(&compute_buffer_data[data_offset..data_offset+data.bytes as usize]);
 
-    use subtle::{ConditionallySelectable, ConditionallyNegatable};
-    let mut s_prime = &s * &r_0;
-    let s_prime_is_pos = !s_prime.is_negative();
-    s_prime.conditional_negate(s_prime_is_pos);
+    compute_slice_mut(&mut compute_buffer_data, state_offset, sha512::STATE_SIZE)?.copy_from_slice(
+        &hasher.to_bytes());
 
-    s.conditional_assign(&s_prime, !Ns_D_is_sq);
-    c.conditional_assign(&r, !Ns_D_is_sq);
+    Ok(())
+}
 
-    let N_t = &(&(&c * &(&r - &one)) * &d_minus_one_sq) - &D;
-    let s_sq = s.square();
+fn process_sha512_fini(
+    compute_buffer_info: &AccountInfo,
+    data: &Sha512FiniData,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    // The conversion from W_i is exactly the conversion from P1xP1.
-    let res = RistrettoPoint(CompletedPoint{
-        X: &(&s + &s) * &D,
-        Z: &N_t * &constants::SQRT_AD_MINUS_ONE,
-        Y: &FieldElement::one() - &s_sq,
-        T: &FieldElement::one() + &s_sq,
-    }.to_extended());
+    let state_offset = data.state_offset as usize;
+    let hasher = sha512::Sha512::from_bytes(
+        &compute_buffer_data[state_offset..state_offset+sha512::STATE_SIZE])?;
 
-    let offset = offset + 32 * 6;
-    compute_buffer_data[offset..offset+128].copy_from_slice(
-        &res.0.to_bytes());
+    let digest_offset = data.digest_offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, digest_offset, sha512::DIGEST_SIZE)?.copy_from_slice(
+        &hasher.finalize());
 
     Ok(())
 }
 
-fn process_montgomery_elligator(
+fn process_expand_message_xmd_init(
     compute_buffer_info: &AccountInfo,
     offset: u32,
-    step: u8,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
     let offset = offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, offset, sha512::STATE_SIZE)?.copy_from_slice(
+        &sha512::expand_message_xmd_init().to_bytes());
 
-    let one = FieldElement::one();
+    Ok(())
+}
 
-    if step == 0 {
-        let r_0 = read_field_element(&compute_buffer_data, offset)?;
-        let d_1 = &one + &r_0.square2(); /* 2r^2 */
+fn process_expand_message_xmd_fini(
+    compute_buffer_info: &AccountInfo,
+    data: &ExpandMessageXmdData,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-        let offset = offset + 32;
-        compute_buffer_data[offset..offset+32].copy_from_slice(
-            &d_1.to_bytes()
-        );
-        return Ok(());
+    let len_in_bytes = data.len_in_bytes as usize;
+    if len_in_bytes > 255 * sha512::DIGEST_SIZE {
+        msg!("expand_message_xmd output too long");
+        return Err(Curve25519Error::InvalidLength.into());
     }
 
-    // invert
-    let t3 = read_field_element(&compute_buffer_data, offset + 32 * 4)?;
-    let t19 = read_field_element(&compute_buffer_data, offset + 32 * 5)?;
-    let d_1_inv = &t19.pow2k(5) * &t3;
+    let state_offset = data.state_offset as usize;
+    let hasher = sha512::Sha512::from_bytes(
+        &compute_buffer_data[state_offset..state_offset+sha512::STATE_SIZE])?;
 
-    let d = &constants::MONTGOMERY_A_NEG * &d_1_inv; /* A/(1+2r^2) */
+    let dst_offset = data.dst_offset as usize;
+    let dst_len = data.dst_len as usize;
+    let mut dst = [0u8; 255];
+    dst[..dst_len].copy_from_slice(&compute_buffer_data[dst_offset..dst_offset+dst_len]);
 
-    let d_sq = &d.square();
-    let au = &constants::MONTGOMERY_A * &d;
+    let output_offset = data.output_offset as usize;
+    sha512::expand_message_xmd_fini(
+        hasher,
+        &dst[..dst_len],
+        compute_slice_mut(&mut compute_buffer_data, output_offset, len_in_bytes)?,
+    );
 
-    let inner = &(d_sq + &au) + &one;
-    let eps = &d * &inner; /* eps = d^3 + Ad^2 + d */
+    Ok(())
+}
 
-    if step == 1 {
-        let offset = offset + 32 * 6;
-        compute_buffer_data[offset..offset+32].copy_from_slice(
-            &FieldElement::sqrt_ratio_i_pow_p58_input(&eps, &one).to_bytes(),
-        );
-        return Ok(());
-    }
+fn process_hash_to_field(
+    compute_buffer_info: &AccountInfo,
+    data: &HashToFieldData,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    let pow_p22501_output = read_field_element(&compute_buffer_data, offset + 32 * 10)?;
-    let (eps_is_sq, _eps) = FieldElement::sqrt_ratio_i_pow_p58_output(&eps, &one, &pow_p22501_output);
+    let input_offset = data.input_offset as usize;
+    let element = FieldElement::from_uniform_bytes_be(
+        compute_buffer_data[input_offset..input_offset+48]
+            .try_into().map_err(|_| ProgramError::InvalidArgument)?,
+    );
 
-    use subtle::{ConditionallySelectable, ConditionallyNegatable};
+    let result_offset = data.result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 32)?.copy_from_slice(
+        &element.to_bytes());
 
-    let zero = FieldElement::zero();
-    let Atemp = FieldElement::conditional_select(&constants::MONTGOMERY_A, &zero, eps_is_sq); /* 0, or A if nonsquare*/
+    Ok(())
+}
 
-    let mut u = &d + &Atemp; /* d, or d+A if nonsquare */
-    u.conditional_negate(!eps_is_sq); /* d, or -d-A if nonsquare */
+fn process_write_constant(
+    compute_buffer_info: &AccountInfo,
+    data: &WriteConstantData,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    // write the compressed MontgomeryPoint
-    let offset = offset + 32 * 11;
-    compute_buffer_data[offset..offset+32].copy_from_slice(
-        &u.to_bytes());
+    let offset = data.offset as usize;
+    let len = data.len as usize;
+    if len > MAX_CONSTANT_BYTES {
+        msg!("Constant too long");
+        return Err(Curve25519Error::InvalidLength.into());
+    }
+    compute_slice_mut(&mut compute_buffer_data, offset, len)?.copy_from_slice(&data.bytes[..len]);
 
     Ok(())
 }
 
-fn process_montgomery_to_edwards(
+fn process_transcript_init(
     compute_buffer_info: &AccountInfo,
-    offset: u32,
-    sign_offset: u32,
-    step: u8,
+    data: &TranscriptData,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    let offset = offset as usize;
+    let label_offset = data.label_offset as usize;
+    let transcript = transcript::Transcript::new(
+        &compute_buffer_data[label_offset..label_offset+data.label_len as usize])?;
 
-    let u = read_field_element(&compute_buffer_data, offset)?;
-    if u == FieldElement::minus_one() {
-        return Err(ProgramError::InvalidArgument);
-    }
+    write_transcript(&mut compute_buffer_data, data.transcript_offset, &transcript)
+}
 
-    let one = FieldElement::one();
+fn process_transcript_append_message(
+    compute_buffer_info: &AccountInfo,
+    data: &TranscriptData,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    if step == 0 {
-        let offset = offset + 32;
-        compute_buffer_data[offset..offset+32].copy_from_slice(
-            &(&u + &one).to_bytes()
-        );
-        return Ok(());
-    }
+    let transcript_offset = data.transcript_offset as usize;
+    let mut transcript = transcript::Transcript::from_bytes(
+        &compute_buffer_data[transcript_offset..transcript_offset+transcript::TRANSCRIPT_SIZE])?;
 
-    // invert
-    let t3 = read_field_element(&compute_buffer_data, offset + 32 * 4)?;
-    let t19 = read_field_element(&compute_buffer_data, offset + 32 * 5)?;
-    let up1_inv = &t19.pow2k(5) * &t3;
+    let label_offset = data.label_offset as usize;
+    let data_offset = data.data_offset as usize;
+    transcript.append_message(
+        &compute_buffer_data[label_offset..label_offset+data.label_len as usize],
+        &compute_buffer_data[data_offset..data_offset+data.data_len as usize],
+    )?;
 
-    let y = &(&u - &one) * &up1_inv;
+    write_transcript(&mut compute_buffer_data, data.transcript_offset, &transcript)
+}
 
-    let sign = (compute_buffer_data[sign_offset as usize] & 0x80) >> 7;
+fn process_transcript_challenge_bytes(
+    compute_buffer_info: &AccountInfo,
+    data: &TranscriptData,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    let mut y_bytes = y.to_bytes();
-    y_bytes[31] ^= sign << 7;
+    let transcript_offset = data.transcript_offset as usize;
+    let mut transcript = transcript::Transcript::from_bytes(
+        &compute_buffer_data[transcript_offset..transcript_offset+transcript::TRANSCRIPT_SIZE])?;
 
-    let offset = offset + 32 * 6;
-    compute_buffer_data[offset..offset+32].copy_from_slice(&y_bytes);
+    let label_offset = data.label_offset as usize;
+    let mut challenge = vec![0; data.data_len as usize];
+    transcript.challenge_bytes(
+        &compute_buffer_data[label_offset..label_offset+data.label_len as usize],
+        &mut challenge,
+    )?;
+
+    let data_offset = data.data_offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, data_offset, challenge.len())?.copy_from_slice(
+        &challenge);
+
+    write_transcript(&mut compute_buffer_data, data.transcript_offset, &transcript)
+}
+
+fn write_transcript(
+    compute_buffer_data: &mut [u8],
+    transcript_offset: u32,
+    transcript: &transcript::Transcript,
+) -> ProgramResult {
+    let transcript_offset = transcript_offset as usize;
+    compute_slice_mut(compute_buffer_data, transcript_offset, transcript::TRANSCRIPT_SIZE)?.copy_from_slice(
+        &transcript.to_bytes());
 
     Ok(())
 }
 
-fn process_mul_by_cofactor(
+fn process_scalar_binary_op(
     compute_buffer_info: &AccountInfo,
-    offset: u32,
-    result_offset: u32,
+    data: &ScalarBinaryOpData,
+    op: fn(&Scalar, &Scalar) -> Scalar,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    let offset = offset as usize;
-    let point = EdwardsPoint::from_bytes(
-        &compute_buffer_data[offset..offset+128]
-    );
+    let lhs = read_scalar(&compute_buffer_data, data.lhs_offset)?;
+    let rhs = read_scalar(&compute_buffer_data, data.rhs_offset)?;
 
-    let result_offset = result_offset as usize;
-    compute_buffer_data[result_offset..result_offset+128].copy_from_slice(
-        &point.mul_by_cofactor().to_bytes()
-    );
+    let result_offset = data.result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 32)?.copy_from_slice(
+        op(&lhs, &rhs).as_bytes());
 
     Ok(())
 }
 
-fn process_build_lookup_table(
+fn process_scalar_unary_op(
     compute_buffer_info: &AccountInfo,
-    data: &BuildLookupTableData,
+    data: &ScalarUnaryOpData,
+    op: fn(&Scalar) -> [u8; 32],
 ) -> ProgramResult {
-    let compute_buffer_data = compute_buffer_info.try_borrow_data()?;
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    let point_offset = data.point_offset as usize;
-    let point = EdwardsPoint::from_bytes(
-        &compute_buffer_data[point_offset..point_offset+128]
-    );
+    let scalar = read_scalar(&compute_buffer_data, data.scalar_offset)?;
 
-    let table = LookupTable::<ProjectiveNielsPoint>::from(&point);
+    let result_offset = data.result_offset as usize;
+    compute_slice_mut(&mut compute_buffer_data, result_offset, 32)?.copy_from_slice(
+        &op(&scalar));
 
+    Ok(())
+}
 
-    drop(compute_buffer_data);
-    let mut table_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
-    let table_offset = data.table_offset as usize;
-    type LUT = LookupTable::<ProjectiveNielsPoint>;
-    table_buffer_data[table_offset..table_offset + LUT::TABLE_SIZE].copy_from_slice(
-        bytemuck::cast_slice::<LUT, u8>(std::slice::from_ref(&table)));
+fn process_scalar_from_wide(
+    compute_buffer_info: &AccountInfo,
+    data: &ScalarFromWideData,
+    packed: bool,
+) -> ProgramResult {
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
+
+    let wide_offset = data.wide_offset as usize;
+    let scalar = Scalar::from_bytes_mod_order_wide(
+        compute_buffer_data[wide_offset..wide_offset+64].try_into().unwrap());
+
+    let scalar_offset = data.scalar_offset as usize;
+    if packed {
+        compute_slice_mut(&mut compute_buffer_data, scalar_offset, 32)?.copy_from_slice(
+            &scalar.to_packed_radix_16());
+    } else {
+        compute_slice_mut(&mut compute_buffer_data, scalar_offset, 32)?.copy_from_slice(
+            scalar.as_bytes());
+    }
 
     Ok(())
 }
 
-fn process_multiscalar_mul(
+fn process_scalar_invert(
     compute_buffer_info: &AccountInfo,
-    data: &MultiscalarMulData,
+    offset: u32,
+    step: u8,
 ) -> ProgramResult {
-    let num_inputs = data.num_inputs as usize;
-    if num_inputs > MAX_MULTISCALAR_POINTS {
-        msg!("Too many points");
-        return Err(ProgramError::InvalidArgument);
-    }
+    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    // deserialize lookup tables
-    let compute_buffer_data = compute_buffer_info.try_borrow_data()?;
-    let table_offset = u32::from(data.tables_offset) as usize;
-    type LUT = LookupTable::<ProjectiveNielsPoint>;
-    let lookup_tables = bytemuck::cast_slice::<u8, LUT>(
-        &compute_buffer_data[table_offset..table_offset + LUT::TABLE_SIZE * num_inputs]);
+    let offset = offset as usize;
 
-    // deserialize scalars
-    // TODO: just encode the radix_16 values directly?
-    let scalar_offset = u32::from(data.scalars_offset) as usize;
-    let packed_scalar_digits = bytemuck::cast_slice::<u8, [u8; 32]>(
-        &compute_buffer_data[scalar_offset..scalar_offset + 32 * num_inputs]);
+    let table_offset = offset + 32;
+    let y_offset = offset + 32 * 10;
+    let result_offset = offset + 32 * 11;
 
-    // deserialize point computation
-    let result_offset = u32::from(data.result_offset) as usize;
-    let mut Q = EdwardsPoint::from_bytes(
-        &compute_buffer_data[result_offset..result_offset+128]
-    );
+    // montgomery form values are reduced but not canonical
+    let read_montgomery = |data: &[u8], offset: usize| {
+        Scalar::from_bits(data[offset..offset+32].try_into().unwrap())
+    };
 
-    // run compute
-    for j in (data.start..data.end).rev() {
-        Q = Q.mul_by_pow_2(4);
-        let it = packed_scalar_digits.iter().zip(lookup_tables.iter());
-        for (s_i, lookup_table_i) in it {
-            // R_i = s_{i,j} * P_i
-            let packed_radix = if j & 1 == 1 {
-                (s_i[(j >> 1) as usize] as i8) >> 4
-            } else {
-                (s_i[(j >> 1) as usize] as i8) << 4 >> 4
-            };
-            let R_i = lookup_table_i.select(packed_radix);
-            // Q = Q + R_i
-            Q = (&Q + &R_i).to_extended();
+    if step == 0 {
+        let x = read_scalar(&compute_buffer_data, offset as u32)?;
+        let (table, y) = x.invert_init();
+        for (i, t) in table.iter().enumerate() {
+            compute_slice_mut(&mut compute_buffer_data, table_offset+32*i, 32)?.copy_from_slice(
+                t.as_bytes());
         }
-    }
+        compute_slice_mut(&mut compute_buffer_data, y_offset, 32)?.copy_from_slice(y.as_bytes());
+    } else if step < SCALAR_INVERT_STEPS {
+        let mut table = [Scalar::zero(); 9];
+        for (i, t) in table.iter_mut().enumerate() {
+            *t = read_montgomery(&compute_buffer_data, table_offset + 32 * i);
+        }
+        let y = read_montgomery(&compute_buffer_data, y_offset);
 
-    // serialize
-    drop(compute_buffer_data);
-    let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
-    compute_buffer_data[result_offset..result_offset+128].copy_from_slice(
-        &Q.to_bytes());
+        let start = (step as usize - 1) * SCALAR_INVERT_OPS_PER_STEP;
+        let end = (start + SCALAR_INVERT_OPS_PER_STEP).min(scalar::MONTGOMERY_INVERT_OPS);
+        let y = Scalar::invert_chain(&table, &y, start, end);
+        compute_slice_mut(&mut compute_buffer_data, y_offset, 32)?.copy_from_slice(y.as_bytes());
+
+        if end == scalar::MONTGOMERY_INVERT_OPS {
+            compute_slice_mut(&mut compute_buffer_data, result_offset, 32)?.copy_from_slice(
+                Scalar::invert_fini(&y).as_bytes());
+        }
+    } else {
+        msg!("Invalid step");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
 
     Ok(())
 }
 
-fn process_decompress_with_witness(
+fn process_batch_scalar_invert(
     compute_buffer_info: &AccountInfo,
-    offset: u32,
+    data: &BatchScalarInvertData,
 ) -> ProgramResult {
     let mut compute_buffer_data = compute_buffer_info.try_borrow_mut_data()?;
 
-    let offset = offset as usize;
-    let point = CompressedRistretto::from_slice(
-        &compute_buffer_data[offset..offset+32]
-    );
+    let offset = data.offset as usize;
 
-    let witness = read_field_element(&compute_buffer_data, offset + 32)?;
+    let num_scalars = data.num_scalars as usize;
+    let scratch_offset = offset + 32 * num_scalars;
+    let invert_offset = offset + 32 * 2 * num_scalars;
 
-    let Iinv_sq = point.decompress_init().ok_or(ProgramError::InvalidArgument)?;
-    // if !ok the witness should multiply to sqrt(-1) (aka i)
-    if &Iinv_sq * &witness.square() != FieldElement::one() {
-        msg!("Bad witness");
-        return Err(ProgramError::InvalidArgument);
-    }
+    // montgomery form values are reduced but not canonical
+    let read_montgomery = |data: &[u8], offset: usize| {
+        Scalar::from_bits(data[offset..offset+32].try_into().unwrap())
+    };
 
-    // some duplicate work in this...
-    let point = point.decompress_fini(&witness).ok_or(ProgramError::InvalidArgument)?;
+    let mut inputs = vec![Scalar::zero(); num_scalars];
+    let mut scratch = vec![Scalar::zero(); num_scalars];
 
-    let offset = offset + 32 * 2;
-    compute_buffer_data[offset..offset+128].copy_from_slice(
-        &point.0.to_bytes()
-    );
+    if data.step == 0 {
+        for (i, input) in inputs.iter_mut().enumerate() {
+            *input = read_scalar(&compute_buffer_data, (offset + 32 * i) as u32)?;
+            // one zero would zero the product and with it every inverse
+            if *input == Scalar::zero() {
+                msg!("Cannot invert zero");
+                return Err(Curve25519Error::ZeroScalar.into());
+            }
+        }
+
+        let product = Scalar::batch_invert_prepare(&mut inputs, &mut scratch);
+        for (i, scratch) in scratch.iter().enumerate() {
+            compute_slice_mut(&mut compute_buffer_data, scratch_offset+32*i, 32)?.copy_from_slice(
+                scratch.as_bytes());
+        }
+        compute_slice_mut(&mut compute_buffer_data, invert_offset, 32)?.copy_from_slice(
+            product.as_bytes());
+    } else if data.step == 1 {
+        for (i, (input, scratch)) in inputs.iter_mut().zip(scratch.iter_mut()).enumerate() {
+            *input = read_montgomery(&compute_buffer_data, offset + 32 * i);
+            *scratch = read_montgomery(&compute_buffer_data, scratch_offset + 32 * i);
+        }
+
+        // ScalarInvert output
+        let inverse_offset = invert_offset + 32 * 11;
+        let inverse = read_scalar(&compute_buffer_data, inverse_offset as u32)?;
+
+        Scalar::batch_invert_finish(&mut inputs, &scratch, &inverse);
+    } else {
+        msg!("Invalid step");
+        return Err(Curve25519Error::InvalidStep.into());
+    }
+
+    for (i, input) in inputs.iter().enumerate() {
+        compute_slice_mut(&mut compute_buffer_data, offset+32*i, 32)?.copy_from_slice(
+            input.as_bytes());
+    }
 
     Ok(())
 }
 
+// scalars in the compute buffer are expected to be canonical. anything else
+// was copied in unchecked
+fn read_scalar(
+    compute_buffer_data: &[u8],
+    offset: u32,
+) -> Result<Scalar, ProgramError> {
+    let offset = offset as usize;
+    Scalar::from_canonical_bytes(
+        compute_buffer_data[offset..offset+32].try_into().unwrap(),
+    ).ok_or_else(|| {
+        msg!("Non-canonical scalar");
+        ProgramError::from(Curve25519Error::NonCanonicalScalar)
+    })
+}
 
 fn read_field_element(
     compute_buffer_data: &[u8],
@@ -1067,3 +3462,17 @@ fn read_field_element(
             .try_into().map_err(|_| ProgramError::InvalidArgument)?,
     ))
 }
+
+// every DSL write goes through here. The header holds the verdict that
+// cpi::verify_completed_compute_buffer trusts so it is never writable
+fn compute_slice_mut(
+    compute_buffer_data: &mut [u8],
+    offset: usize,
+    len: usize,
+) -> Result<&mut [u8], ProgramError> {
+    if offset < HEADER_SIZE {
+        msg!("Cannot write to header");
+        return Err(Curve25519Error::HeaderOffset.into());
+    }
+    Ok(&mut compute_buffer_data[offset..offset+len])
+}
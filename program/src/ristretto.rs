@@ -1,17 +1,21 @@
 #![allow(non_snake_case)]
 
 use core::borrow::Borrow;
+use core::ops::Mul;
 
 use subtle::Choice;
 use subtle::ConditionallyNegatable;
 use subtle::ConstantTimeEq;
 
 use crate::backend::serial::u64::constants;
+use crate::edwards::EdwardsBasepointTable;
 use crate::edwards::EdwardsPoint;
 use crate::field::FieldElement;
 use crate::scalar::Scalar;
+use crate::traits::BasepointTable;
 use crate::traits::Identity;
 use crate::traits::MultiscalarMul;
+use crate::traits::VartimeMultiscalarMul;
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash)]
 pub struct CompressedRistretto(pub [u8; 32]);
@@ -165,6 +169,70 @@ impl Default for CompressedRistretto {
 #[derive(Copy, Clone)]
 pub struct RistrettoPoint(pub EdwardsPoint);
 
+impl RistrettoPoint {
+    /// Input to `FieldElement::pow22501` for compressing this point. The
+    /// exponentiation is split out so that it can run in separate
+    /// instructions; finish with `compress_fini`.
+    pub fn compress_init(&self) -> FieldElement {
+        let Y = &self.0.Y;
+        let Z = &self.0.Z;
+
+        let u1 = &(Z + Y) * &(Z - Y);
+        let u2 = &self.0.X * Y;
+
+        FieldElement::sqrt_ratio_i_pow_p58_input(
+            &FieldElement::one(),
+            &(&u1 * &u2.square()),
+        )
+    }
+
+    /// Compress this point to `CompressedRistretto` format given the
+    /// `pow22501` output of `compress_init`.
+    pub fn compress_fini(
+        &self,
+        pow_p22501_output: &FieldElement,
+    ) -> CompressedRistretto {
+        use subtle::ConditionallySelectable;
+
+        let mut X = self.0.X;
+        let mut Y = self.0.Y;
+        let Z = &self.0.Z;
+        let T = &self.0.T;
+
+        let u1 = &(Z + &Y) * &(Z - &Y);
+        let u2 = &X * &Y;
+        // Ignore return value since this is always square
+        let (_, invsqrt) = FieldElement::sqrt_ratio_i_pow_p58_output(
+            &FieldElement::one(),
+            &(&u1 * &u2.square()),
+            pow_p22501_output,
+        );
+        let i1 = &invsqrt * &u1;
+        let i2 = &invsqrt * &u2;
+        let z_inv = &i1 * &(&i2 * T);
+        let mut den_inv = i2;
+
+        let iX = &X * &constants::SQRT_M1;
+        let iY = &Y * &constants::SQRT_M1;
+        let ristretto_magic = &constants::INVSQRT_A_MINUS_D;
+        let enchanted_denominator = &i1 * ristretto_magic;
+
+        let rotate = (T * &z_inv).is_negative();
+
+        X.conditional_assign(&iY, rotate);
+        Y.conditional_assign(&iX, rotate);
+        den_inv.conditional_assign(&enchanted_denominator, rotate);
+
+        Y.conditional_negate((&X * &z_inv).is_negative());
+
+        let mut s = &den_inv * &(Z - &Y);
+        let s_is_negative = s.is_negative();
+        s.conditional_negate(s_is_negative);
+
+        CompressedRistretto(s.to_bytes())
+    }
+}
+
 // ------------------------------------------------------------------------
 // Multiscalar Multiplication impls
 // ------------------------------------------------------------------------
@@ -189,6 +257,21 @@ impl MultiscalarMul for RistrettoPoint {
     }
 }
 
+impl VartimeMultiscalarMul for RistrettoPoint {
+    type Point = RistrettoPoint;
+
+    fn optional_multiscalar_mul<I, J>(scalars: I, points: J) -> Option<RistrettoPoint>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Scalar>,
+        J: IntoIterator<Item = Option<RistrettoPoint>>,
+    {
+        let extended_points = points.into_iter().map(|opt_P| opt_P.map(|P| P.0));
+
+        EdwardsPoint::optional_multiscalar_mul(scalars, extended_points).map(RistrettoPoint)
+    }
+}
+
 impl ConstantTimeEq for RistrettoPoint {
     /// Test equality between two `RistrettoPoint`s.
     ///
@@ -212,3 +295,46 @@ impl Identity for RistrettoPoint {
     }
 }
 
+// ------------------------------------------------------------------------
+// Fixed-base scalar multiplication
+// ------------------------------------------------------------------------
+
+/// A precomputed table of multiples of a basepoint, used to accelerate
+/// scalar multiplication.
+///
+/// A precomputed table of multiples of the Ristretto basepoint is
+/// available in the `constants` module.
+#[derive(Clone)]
+pub struct RistrettoBasepointTable(pub(crate) EdwardsBasepointTable);
+
+impl BasepointTable for RistrettoBasepointTable {
+    type Point = RistrettoPoint;
+
+    fn create(basepoint: &RistrettoPoint) -> RistrettoBasepointTable {
+        RistrettoBasepointTable(EdwardsBasepointTable::create(&basepoint.0))
+    }
+
+    fn basepoint(&self) -> RistrettoPoint {
+        RistrettoPoint(self.0.basepoint())
+    }
+
+    fn basepoint_mul(&self, scalar: &Scalar) -> RistrettoPoint {
+        RistrettoPoint(self.0.basepoint_mul(scalar))
+    }
+}
+
+impl<'a, 'b> Mul<&'b Scalar> for &'a RistrettoBasepointTable {
+    type Output = RistrettoPoint;
+
+    fn mul(self, scalar: &'b Scalar) -> RistrettoPoint {
+        self.basepoint_mul(scalar)
+    }
+}
+
+impl<'a, 'b> Mul<&'a RistrettoBasepointTable> for &'b Scalar {
+    type Output = RistrettoPoint;
+
+    fn mul(self, basepoint_table: &'a RistrettoBasepointTable) -> RistrettoPoint {
+        basepoint_table * self
+    }
+}
@@ -173,6 +173,10 @@ use crate::backend::serial::u64::constants;
 /// module.
 type UnpackedScalar = backend::serial::u64::scalar::Scalar52;
 
+/// Bytes in the output of `Scalar::to_packed_radix_2w`. The digits take at
+/// most 260 bits for `w <= 7`.
+pub const PACKED_RADIX_2W_SIZE: usize = 33;
+
 
 /// The `Scalar` struct holds an integer \\(s < 2\^{255} \\) which
 /// represents an element of \\(\mathbb Z / \ell\\).
@@ -752,7 +756,6 @@ impl Scalar {
     /// assert_eq!(scalars[3], Scalar::from(11u64).invert());
     /// # }
     /// ```
-    #[cfg(feature = "alloc")]
     pub fn batch_invert(inputs: &mut [Scalar]) -> Scalar {
         // This code is essentially identical to the FieldElement
         // implementation, and is documented there.  Unfortunately,
@@ -764,20 +767,35 @@ impl Scalar {
         use zeroize::Zeroizing;
 
         let n = inputs.len();
-        let one: UnpackedScalar = Scalar::one().unpack().to_montgomery();
 
         // Place scratch storage in a Zeroizing wrapper to wipe it when
         // we pass out of scope.
-        let scratch_vec = vec![one; n];
+        let scratch_vec = vec![Scalar::one(); n];
         let mut scratch = Zeroizing::new(scratch_vec);
 
+        let acc = Scalar::batch_invert_prepare(inputs, &mut scratch);
+
+        // Compute the inverse of all products
+        let acc = acc.invert();
+
+        Scalar::batch_invert_finish(inputs, &scratch, &acc);
+
+        // We need to return the product of all inverses
+        acc
+    }
+
+    /// The first pass of `batch_invert`, split out so that the inversion in
+    /// the middle can be spread across instructions. Replaces `inputs` with
+    /// their Montgomery forms, records the running products in `scratch` and
+    /// returns the product of all inputs. Callers reject zero inputs first.
+    pub(crate) fn batch_invert_prepare(inputs: &mut [Scalar], scratch: &mut [Scalar]) -> Scalar {
         // Keep an accumulator of all of the previous products
         let mut acc = Scalar::one().unpack().to_montgomery();
 
         // Pass through the input vector, recording the previous
         // products in the scratch space
         for (input, scratch) in inputs.iter_mut().zip(scratch.iter_mut()) {
-            *scratch = acc;
+            *scratch = acc.pack();
 
             // Avoid unnecessary Montgomery multiplication in second pass by
             // keeping inputs in Montgomery form
@@ -789,21 +807,184 @@ impl Scalar {
         // acc is nonzero iff all inputs are nonzero
         debug_assert!(acc.pack() != Scalar::zero());
 
-        // Compute the inverse of all products
-        acc = acc.montgomery_invert().from_montgomery();
+        acc.from_montgomery().pack()
+    }
 
-        // We need to return the product of all inverses later
-        let ret = acc.pack();
+    /// The second pass of `batch_invert`. Given the state left by
+    /// `batch_invert_prepare` and the inverse of the product it returned,
+    /// replaces `inputs` with their inverses.
+    pub(crate) fn batch_invert_finish(inputs: &mut [Scalar], scratch: &[Scalar], inverse: &Scalar) {
+        let mut acc = inverse.unpack();
 
         // Pass through the vector backwards to compute the inverses
         // in place
         for (input, scratch) in inputs.iter_mut().rev().zip(scratch.iter().rev()) {
             let tmp = UnpackedScalar::montgomery_mul(&acc, &input.unpack());
-            *input = UnpackedScalar::montgomery_mul(&acc, &scratch).pack();
+            *input = UnpackedScalar::montgomery_mul(&acc, &scratch.unpack()).pack();
             acc = tmp;
         }
+    }
+
+    /// The small multiples and starting value of the addition chain used to
+    /// invert this `Scalar`, in Montgomery form. See `invert_chain`.
+    pub(crate) fn invert_init(&self) -> ([Scalar; 9], Scalar) {
+        let table = self.unpack().to_montgomery().montgomery_invert_table();
+        let y = UnpackedScalar::montgomery_mul(&table[8], &table[0]);
+
+        let mut packed = [Scalar::zero(); 9];
+        for (p, t) in packed.iter_mut().zip(table.iter()) {
+            *p = t.pack();
+        }
+
+        (packed, y.pack())
+    }
+
+    /// Runs the squarings and multiplications numbered `start..end` of the
+    /// inversion addition chain on `y`. After all `MONTGOMERY_INVERT_OPS` have
+    /// been run, `invert_fini` gives the inverse.
+    pub(crate) fn invert_chain(table: &[Scalar; 9], y: &Scalar, start: usize, end: usize) -> Scalar {
+        let mut unpacked_table = [UnpackedScalar::zero(); 9];
+        for (u, t) in unpacked_table.iter_mut().zip(table.iter()) {
+            *u = t.unpack();
+        }
+
+        let mut y = y.unpack();
+        UnpackedScalar::montgomery_invert_chain(&mut y, &unpacked_table, start, end);
+
+        y.pack()
+    }
+
+    pub(crate) fn invert_fini(y: &Scalar) -> Scalar {
+        y.unpack().from_montgomery().pack()
+    }
+
+    /// Compute a width-\\(w\\) "Non-Adjacent Form" of this scalar.
+    ///
+    /// A width-\\(w\\) NAF of a positive integer \\(k\\) is an expression
+    /// $$
+    /// k = \sum_{i=0}\^m n\_i 2\^i,
+    /// $$
+    /// where each nonzero
+    /// coefficient \\(n\_i\\) is odd and bounded by \\(|n\_i| < 2\^{w-1}\\),
+    /// \\(n\_{m-1}\\) is nonzero, and at most one of any \\(w\\) consecutive
+    /// coefficients is nonzero.  (Hankerson, Menezes, Vanstone; def 3.32).
+    ///
+    /// The length of the NAF is at most one more than the length of
+    /// the binary representation of \\(k\\).  This is why the
+    /// `Scalar` type maintains an invariant that the top bit is
+    /// \\(0\\), so that the NAF of a scalar has at most 256 digits.
+    ///
+    /// Intuitively, this is like a binary expansion, except that we
+    /// allow some coefficients to grow in magnitude up to
+    /// \\(2\^{w-1}\\) so that the nonzero coefficients are as sparse
+    /// as possible.
+    ///
+    /// When doing scalar multiplication, we can then use a lookup
+    /// table of precomputed multiples of a point to add the nonzero
+    /// terms \\( k_i P \\).  Using signed digits cuts the table size
+    /// in half, and using odd digits cuts the table size in half
+    /// again.
+    ///
+    /// To compute a \\(w\\)-NAF, we use a modification of Algorithm 3.35 of HMV:
+    ///
+    /// 1. \\( i \gets 0 \\)
+    /// 2. While \\( k \ge 1 \\):
+    ///     1. If \\(k\\) is odd, \\( n_i \gets k \operatorname{mods} 2^w \\), \\( k \gets k - n_i \\).
+    ///     2. If \\(k\\) is even, \\( n_i \gets 0 \\).
+    ///     3. \\( k \gets k / 2 \\), \\( i \gets i + 1 \\).
+    /// 3. Return \\( n_0, n_1, ... , \\)
+    ///
+    /// Here \\( \bar x = x \operatorname{mods} 2^w \\) means the
+    /// \\( \bar x \\) with \\( \bar x \equiv x \pmod{2^w} \\) and
+    /// \\( -2^{w-1} \leq \bar x < 2^w \\).
+    ///
+    /// We implement this by scanning across the bits of \\(k\\) from
+    /// least-significant bit to most-significant-bit.
+    /// Write the bits of \\(k\\) as
+    /// $$
+    /// k = \sum\_{i=0}\^m k\_i 2^i,
+    /// $$
+    /// and split the sum as
+    /// $$
+    /// k = \sum\_{i=0}^{w-1} k\_i 2^i + 2^w \sum\_{i=0} k\_{i+w} 2^i
+    /// $$
+    /// where the first part is \\( k \mod 2^w \\).
+    ///
+    /// If \\( k \mod 2^w\\) is odd, and \\( k \mod 2^w < 2^{w-1} \\), then we emit
+    /// \\( n_0 = k \mod 2^w \\).  Instead of computing
+    /// \\( k - n_0 \\), we just advance \\(w\\) bits and reindex.
+    ///
+    /// If \\( k \mod 2^w\\) is odd, and \\( k \mod 2^w \ge 2^{w-1} \\), then
+    /// \\( n_0 = k \operatorname{mods} 2^w = k \mod 2^w - 2^w \\).
+    /// The quantity \\( k - n_0 \\) is
+    /// $$
+    /// \begin{aligned}
+    /// k - n_0 &= \sum\_{i=0}^{w-1} k\_i 2^i + 2^w \sum\_{i=0} k\_{i+w} 2^i
+    ///          - \sum\_{i=0}^{w-1} k\_i 2^i + 2^w \\\\
+    /// &= 2^w + 2^w \sum\_{i=0} k\_{i+w} 2^i
+    /// \end{aligned}
+    /// $$
+    /// so instead of computing the subtraction, we can set a carry
+    /// bit, advance \\(w\\) bits, and reindex.
+    ///
+    /// If \\( k \mod 2^w\\) is even, we emit \\(0\\), advance 1 bit
+    /// and reindex.  In fact, by setting all digits to \\(0\\)
+    /// initially, we don't need to emit anything.
+    pub(crate) fn non_adjacent_form(&self, w: usize) -> [i8; 256] {
+        // required by the NAF definition
+        debug_assert!( w >= 2 );
+        // required so that the NAF digits fit in i8
+        debug_assert!( w <= 8 );
+
+        use byteorder::{ByteOrder, LittleEndian};
+
+        let mut naf = [0i8; 256];
+
+        let mut x_u64 = [0u64; 5];
+        LittleEndian::read_u64_into(&self.bytes, &mut x_u64[0..4]);
+
+        let width = 1 << w;
+        let window_mask = width - 1;
+
+        let mut pos = 0;
+        let mut carry = 0;
+        while pos < 256 {
+            // Construct a buffer of bits of the scalar, starting at bit `pos`
+            let u64_idx = pos / 64;
+            let bit_idx = pos % 64;
+            let bit_buf: u64;
+            if bit_idx < 64 - w {
+                // This window's bits are contained in a single u64
+                bit_buf = x_u64[u64_idx] >> bit_idx;
+            } else {
+                // Combine the current u64's bits with the bits from the next u64
+                bit_buf = (x_u64[u64_idx] >> bit_idx) | (x_u64[1+u64_idx] << (64 - bit_idx));
+            }
+
+            // Add the carry into the current window
+            let window = carry + (bit_buf & window_mask);
+
+            if window & 1 == 0 {
+                // If the window value is even, preserve the carry and continue.
+                // Why is the carry preserved?
+                // If carry == 0 and window & 1 == 0, then the next carry should be 0
+                // If carry == 1 and window & 1 == 0, then bit_buf & 1 == 1 so the next carry should be 1
+                pos += 1;
+                continue;
+            }
+
+            if window < width/2 {
+                carry = 0;
+                naf[pos] = window as i8;
+            } else {
+                carry = 1;
+                naf[pos] = (window as i8).wrapping_sub(width as i8);
+            }
+
+            pos += w;
+        }
 
-        ret
+        naf
     }
 
     /// Write this scalar in radix 16, with coefficients in \\([-8,8)\\),
@@ -856,6 +1037,134 @@ impl Scalar {
         output
     }
 
+    /// Returns a size hint indicating how many entries of the return
+    /// value of `to_radix_2w` are nonzero.
+    pub(crate) fn to_radix_2w_size_hint(w: usize) -> usize {
+        debug_assert!(w >= 4);
+        debug_assert!(w <= 8);
+
+        let digits_count = match w {
+            4 => (256 + w - 1)/w as usize,
+            5 => (256 + w - 1)/w as usize,
+            6 => (256 + w - 1)/w as usize,
+            7 => (256 + w - 1)/w as usize,
+            // See comment in to_radix_2w on handling the terminal carry.
+            8 => (256 + w - 1)/w + 1 as usize,
+            _ => panic!("invalid radix parameter"),
+        };
+
+        debug_assert!(digits_count <= 64);
+        digits_count
+    }
+
+    /// Creates a representation of a Scalar in radix 32, 64, 128 or 256 for use with the Pippenger algorithm.
+    /// For lower radix, use `to_radix_16`, which is used by the Straus multi-scalar multiplication.
+    /// Higher radixes are not supported to save cache space. Radix 256 is near-optimal even for very
+    /// large inputs.
+    ///
+    /// Radix below 32 or above 256 is prohibited.
+    /// This method returns digits in a fixed-sized array, excess digits are zeroes.
+    ///
+    /// ## Scalar representation
+    ///
+    /// Radix \\(2\^w\\), with \\(n = ceil(256/w)\\) coefficients in \\([-(2\^w)/2,(2\^w)/2)\\),
+    /// i.e., scalar is represented using digits \\(a\_i\\) such that
+    /// $$
+    ///    a = a\_0 + a\_1 2\^1w + \cdots + a_{n-1} 2\^{w*(n-1)},
+    /// $$
+    /// with \\(-2\^w/2 \leq a_i < 2\^w/2\\) for \\(0 \leq i < (n-1)\\) and \\(-2\^w/2 \leq a_{n-1} \leq 2\^w/2\\).
+    ///
+    pub(crate) fn to_radix_2w(&self, w: usize) -> [i8; 64] {
+        debug_assert!(w >= 4);
+        debug_assert!(w <= 8);
+
+        if w == 4 {
+            return self.to_radix_16();
+        }
+
+        use byteorder::{ByteOrder, LittleEndian};
+
+        // Scalar formatted as four `u64`s with carry bit packed into the highest bit.
+        let mut scalar64x4 = [0u64; 4];
+        LittleEndian::read_u64_into(&self.bytes, &mut scalar64x4[0..4]);
+
+        let radix: u64 = 1 << w;
+        let window_mask: u64 = radix - 1;
+
+        let mut carry = 0u64;
+        let mut digits = [0i8; 64];
+        let digits_count = (256 + w - 1)/w as usize;
+        for i in 0..digits_count {
+            // Construct a buffer of bits of the scalar, starting at `bit_offset`.
+            let bit_offset = i*w;
+            let u64_idx = bit_offset / 64;
+            let bit_idx = bit_offset % 64;
+
+            // Read the bits from the scalar
+            let bit_buf: u64;
+            if bit_idx < 64 - w  || u64_idx == 3 {
+                // This window's bits are contained in a single u64,
+                // or it's the last u64 anyway.
+                bit_buf = scalar64x4[u64_idx] >> bit_idx;
+            } else {
+                // Combine the current u64's bits with the bits from the next u64
+                bit_buf = (scalar64x4[u64_idx] >> bit_idx) | (scalar64x4[1+u64_idx] << (64 - bit_idx));
+            }
+
+            // Read the actual coefficient value from the window
+            let coef = carry + (bit_buf & window_mask); // coef = [0, 2^r)
+
+             // Recenter coefficients from [0,2^w) to [-2^w/2, 2^w/2)
+            carry = (coef + (radix/2) as u64) >> w;
+            digits[i] = ((coef as i64) - (carry << w) as i64) as i8;
+        }
+
+        // When w < 8, we can fold the final carry onto the last digit d,
+        // because d < 2^w/2 so d + carry*2^w = d + 1*2^w < 2^(w+1) < 2^8.
+        //
+        // When w = 8, we can't fit carry*2^w into an i8.  This should
+        // not happen anyways, because the final carry will be 0 for
+        // reduced scalars, but the Scalar invariant allows 255-bit scalars.
+        // To handle this, we expand the size_hint by 1 when w=8,
+        // and accumulate the final carry onto another digit.
+        match w {
+            8 => digits[digits_count] += carry as i8,
+            _ => digits[digits_count-1] += (carry << w) as i8,
+        }
+
+        digits
+    }
+
+    /// Pack the `to_radix_2w` digits as `w`-bit two's complement with digit
+    /// \\(i\\) at bit \\(iw\\). Same as `to_packed_radix_16` for `w = 4`.
+    pub fn to_packed_radix_2w(&self, w: usize) -> [u8; PACKED_RADIX_2W_SIZE] {
+        debug_assert!(w >= 4);
+        debug_assert!(w <= 7);
+
+        let digits = self.to_radix_2w(w);
+        let mut output = [0u8; PACKED_RADIX_2W_SIZE];
+
+        let mask = (1u16 << w) - 1;
+        for i in 0..Scalar::to_radix_2w_size_hint(w) {
+            let bit_offset = i * w;
+            let bits = ((digits[i] as u16) & mask) << (bit_offset % 8);
+            output[bit_offset / 8] |= bits as u8;
+            output[bit_offset / 8 + 1] |= (bits >> 8) as u8;
+        }
+
+        output
+    }
+
+    /// Digit `i` of `to_packed_radix_2w` output.
+    pub fn packed_radix_2w_digit(packed: &[u8], w: usize, i: usize) -> i8 {
+        let bit_offset = i * w;
+        let bits = (packed[bit_offset / 8] as u16 | (packed[bit_offset / 8 + 1] as u16) << 8)
+            >> (bit_offset % 8);
+
+        // sign extend from w bits
+        ((bits << (16 - w)) as i16 >> (16 - w)) as i8
+    }
+
     /// Unpack this `Scalar` to an `UnpackedScalar` for faster arithmetic.
     pub(crate) fn unpack(&self) -> UnpackedScalar {
         UnpackedScalar::from_bytes(&self.bytes)
@@ -893,6 +1202,49 @@ impl Scalar {
     }
 }
 
+/// The tail of the `montgomery_invert` addition chain as (squarings, index
+/// into `montgomery_invert_table`) so that it can be run a piece at a time.
+const MONTGOMERY_INVERT_CHAIN: [(usize, usize); 27] = [
+    (123 + 3, 4),
+    (  2 + 2, 3),
+    (  1 + 4, 8),
+    (  1 + 4, 8),
+    (      4, 6),
+    (      2, 3),
+    (  1 + 4, 8),
+    (  1 + 3, 4),
+    (  3 + 3, 4),
+    (      3, 5),
+    (  1 + 4, 8),
+    (  2 + 3, 5),
+    (  2 + 2, 3),
+    (  1 + 4, 7),
+    (  2 + 4, 7),
+    (  6 + 4, 6),
+    (  2 + 2, 3),
+    (  3 + 2, 3),
+    (  3 + 2, 3),
+    (  1 + 4, 6),
+    (  1 + 3, 5),
+    (  2 + 4, 8),
+    (  1 + 4, 7),
+    (      3, 4),
+    (  2 + 4, 8),
+    (      3, 4),
+    (  1 + 2, 3),
+];
+
+/// Number of squarings and multiplications in `MONTGOMERY_INVERT_CHAIN`.
+pub(crate) const MONTGOMERY_INVERT_OPS: usize = {
+    let mut ops = 0;
+    let mut i = 0;
+    while i < MONTGOMERY_INVERT_CHAIN.len() {
+        ops += MONTGOMERY_INVERT_CHAIN[i].0 + 1;
+        i += 1;
+    }
+    ops
+};
+
 impl UnpackedScalar {
     /// Pack the limbs of this `UnpackedScalar` into a `Scalar`.
     fn pack(&self) -> Scalar {
@@ -901,9 +1253,22 @@ impl UnpackedScalar {
 
     /// Inverts an UnpackedScalar in Montgomery form.
     pub fn montgomery_invert(&self) -> UnpackedScalar {
+        let table = self.montgomery_invert_table();
+
+        // _10000
+        let mut y = UnpackedScalar::montgomery_mul(&table[8], &table[0]);
+
+        UnpackedScalar::montgomery_invert_chain(&mut y, &table, 0, MONTGOMERY_INVERT_OPS);
+
+        y
+    }
+
+    /// The small multiples `[_1, _10, _100, _11, _101, _111, _1001, _1011, _1111]`
+    /// of an UnpackedScalar in Montgomery form used by `montgomery_invert`.
+    fn montgomery_invert_table(&self) -> [UnpackedScalar; 9] {
         // Uses the addition chain from
         // https://briansmith.org/ecc-inversion-addition-chains-01#curve25519_scalar_inversion
-        let    _1 = self;
+        let    _1 = *self;
         let   _10 = _1.montgomery_square();
         let  _100 = _10.montgomery_square();
         let   _11 = UnpackedScalar::montgomery_mul(&_10,     &_1);
@@ -913,46 +1278,37 @@ impl UnpackedScalar {
         let _1011 = UnpackedScalar::montgomery_mul(&_10,  &_1001);
         let _1111 = UnpackedScalar::montgomery_mul(&_100, &_1011);
 
-        // _10000
-        let mut y = UnpackedScalar::montgomery_mul(&_1111, &_1);
+        [_1, _10, _100, _11, _101, _111, _1001, _1011, _1111]
+    }
 
-        #[inline]
-        fn square_multiply(y: &mut UnpackedScalar, squarings: usize, x: &UnpackedScalar) {
+    /// Runs the squarings and multiplications numbered `start..end` of the
+    /// `montgomery_invert` addition chain on `y`.
+    fn montgomery_invert_chain(
+        y: &mut UnpackedScalar,
+        table: &[UnpackedScalar; 9],
+        start: usize,
+        end: usize,
+    ) {
+        let mut op = 0;
+        for &(squarings, x) in MONTGOMERY_INVERT_CHAIN.iter() {
+            if op >= end {
+                break;
+            }
+            if op + squarings + 1 <= start {
+                op += squarings + 1;
+                continue;
+            }
             for _ in 0..squarings {
-                *y = y.montgomery_square();
+                if op >= start && op < end {
+                    *y = y.montgomery_square();
+                }
+                op += 1;
             }
-            *y = UnpackedScalar::montgomery_mul(y, x);
+            if op >= start && op < end {
+                *y = UnpackedScalar::montgomery_mul(y, &table[x]);
+            }
+            op += 1;
         }
-
-        square_multiply(&mut y, 123 + 3, &_101);
-        square_multiply(&mut y,   2 + 2, &_11);
-        square_multiply(&mut y,   1 + 4, &_1111);
-        square_multiply(&mut y,   1 + 4, &_1111);
-        square_multiply(&mut y,       4, &_1001);
-        square_multiply(&mut y,       2, &_11);
-        square_multiply(&mut y,   1 + 4, &_1111);
-        square_multiply(&mut y,   1 + 3, &_101);
-        square_multiply(&mut y,   3 + 3, &_101);
-        square_multiply(&mut y,       3, &_111);
-        square_multiply(&mut y,   1 + 4, &_1111);
-        square_multiply(&mut y,   2 + 3, &_111);
-        square_multiply(&mut y,   2 + 2, &_11);
-        square_multiply(&mut y,   1 + 4, &_1011);
-        square_multiply(&mut y,   2 + 4, &_1011);
-        square_multiply(&mut y,   6 + 4, &_1001);
-        square_multiply(&mut y,   2 + 2, &_11);
-        square_multiply(&mut y,   3 + 2, &_11);
-        square_multiply(&mut y,   3 + 2, &_11);
-        square_multiply(&mut y,   1 + 4, &_1001);
-        square_multiply(&mut y,   1 + 3, &_111);
-        square_multiply(&mut y,   2 + 4, &_1111);
-        square_multiply(&mut y,   1 + 4, &_1011);
-        square_multiply(&mut y,       3, &_101);
-        square_multiply(&mut y,   2 + 4, &_1111);
-        square_multiply(&mut y,       3, &_101);
-        square_multiply(&mut y,   1 + 2, &_11);
-
-        y
     }
 
     /// Inverts an UnpackedScalar not in Montgomery form.
@@ -960,3 +1316,39 @@ impl UnpackedScalar {
         self.to_montgomery().montgomery_invert().from_montgomery()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn non_adjacent_form_round_trip() {
+        let mut scalars: Vec<_> = (0..32u64)
+            .map(|i| Scalar::hash_from_bytes::<sha2::Sha512>(&i.to_le_bytes()))
+            .collect();
+        scalars.extend_from_slice(&[Scalar::zero(), Scalar::one(), -Scalar::one()]);
+
+        for scalar in scalars.iter() {
+            let naf = scalar.non_adjacent_form(5);
+
+            // sum of naf[i] * 2^i, most significant digit first
+            let mut sum = Scalar::zero();
+            for digit in naf.iter().rev() {
+                sum = &sum + &sum;
+                let abs = Scalar::from(digit.abs() as u64);
+                sum = if *digit < 0 { &sum - &abs } else { &sum + &abs };
+            }
+            assert!(sum == *scalar);
+
+            // odd digits below 2^4 with at least 4 zeros between them
+            let mut last = None;
+            for (i, digit) in naf.iter().enumerate().filter(|(_, d)| **d != 0) {
+                assert!(digit % 2 != 0 && digit.abs() < 16);
+                if let Some(last) = last {
+                    assert!(i >= last + 5);
+                }
+                last = Some(i);
+            }
+        }
+    }
+}
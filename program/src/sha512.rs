@@ -0,0 +1,284 @@
+//! SHA-512 (FIPS 180-4) with a state that can be written to and read back from
+//! the compute buffer so that a digest can be accumulated over several cranks.
+
+use crate::error::Curve25519Error;
+
+use solana_program::{msg, program_error::ProgramError};
+
+use std::convert::TryInto;
+
+/// Bytes needed to store a `Sha512` state in a buffer.
+pub const STATE_SIZE: usize = 8 * 8 + 8 + 8 + BLOCK_SIZE;
+
+/// Bytes in a SHA-512 digest.
+pub const DIGEST_SIZE: usize = 64;
+
+const BLOCK_SIZE: usize = 128;
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+const K: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+    0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+    0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+    0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+    0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+    0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+    0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+    0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+    0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+    0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+    0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+    0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+#[derive(Clone, Copy)]
+pub struct Sha512 {
+    h: [u64; 8],
+    // total bytes absorbed so far
+    length: u64,
+    buffer_len: u64,
+    buffer: [u8; BLOCK_SIZE],
+}
+
+impl Default for Sha512 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha512 {
+    pub fn new() -> Self {
+        Self {
+            h: IV,
+            length: 0,
+            buffer_len: 0,
+            buffer: [0; BLOCK_SIZE],
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u64;
+
+        let buffer_len = self.buffer_len as usize;
+        if buffer_len > 0 {
+            let n = (BLOCK_SIZE - buffer_len).min(data.len());
+            self.buffer[buffer_len..buffer_len+n].copy_from_slice(&data[..n]);
+            data = &data[n..];
+            if buffer_len + n < BLOCK_SIZE {
+                self.buffer_len += n as u64;
+                return;
+            }
+            let block = self.buffer;
+            compress(&mut self.h, &block);
+        }
+
+        let mut blocks = data.chunks_exact(BLOCK_SIZE);
+        for block in &mut blocks {
+            compress(&mut self.h, block.try_into().unwrap());
+        }
+
+        let rem = blocks.remainder();
+        self.buffer[..rem.len()].copy_from_slice(rem);
+        self.buffer_len = rem.len() as u64;
+    }
+
+    pub fn finalize(mut self) -> [u8; DIGEST_SIZE] {
+        let bit_length = (self.length as u128) << 3;
+
+        let mut padding = [0u8; BLOCK_SIZE * 2];
+        padding[0] = 0x80;
+        let buffer_len = self.buffer_len as usize;
+        let padding_len = if buffer_len < BLOCK_SIZE - 16 {
+            BLOCK_SIZE - buffer_len
+        } else {
+            BLOCK_SIZE * 2 - buffer_len
+        };
+        padding[padding_len-16..padding_len].copy_from_slice(&bit_length.to_be_bytes());
+
+        // don't count the padding towards the message length
+        let length = self.length;
+        self.update(&padding[..padding_len]);
+        self.length = length;
+
+        let mut digest = [0u8; DIGEST_SIZE];
+        for (chunk, h) in digest.chunks_exact_mut(8).zip(self.h.iter()) {
+            chunk.copy_from_slice(&h.to_be_bytes());
+        }
+        digest
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProgramError> {
+        if bytes.len() < STATE_SIZE {
+            msg!("Sha512 state too short");
+            return Err(Curve25519Error::InvalidLength.into());
+        }
+        let read_u64 = |i: usize| u64::from_le_bytes(bytes[i*8..(i+1)*8].try_into().unwrap());
+        let mut h = [0u64; 8];
+        for (i, h) in h.iter_mut().enumerate() {
+            *h = read_u64(i);
+        }
+        let (length, buffer_len) = (read_u64(8), read_u64(9));
+        // the buffer always holds the partial block at the end of the message
+        if buffer_len >= BLOCK_SIZE as u64 || length % BLOCK_SIZE as u64 != buffer_len {
+            msg!("Invalid sha512 state length {} buffered {}", length, buffer_len);
+            return Err(Curve25519Error::InvalidSha512State.into());
+        }
+        Ok(Self {
+            h,
+            length,
+            buffer_len,
+            buffer: bytes[80..80+BLOCK_SIZE].try_into().unwrap(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; STATE_SIZE] {
+        let mut bytes = [0u8; STATE_SIZE];
+        for (chunk, h) in bytes.chunks_exact_mut(8).zip(self.h.iter()) {
+            chunk.copy_from_slice(&h.to_le_bytes());
+        }
+        bytes[64..72].copy_from_slice(&self.length.to_le_bytes());
+        bytes[72..80].copy_from_slice(&self.buffer_len.to_le_bytes());
+        bytes[80..].copy_from_slice(&self.buffer);
+        bytes
+    }
+}
+
+/// RFC 9380 `expand_message_xmd` state with `Z_pad` absorbed. Feed it the
+/// message with `update` and finish with `expand_message_xmd_fini`.
+pub fn expand_message_xmd_init() -> Sha512 {
+    let mut hasher = Sha512::new();
+    hasher.update(&[0; BLOCK_SIZE]);
+    hasher
+}
+
+/// Finishes `expand_message_xmd` into `output`. The caller checks that
+/// `dst.len() <= 255` and that `output.len() <= 255 * DIGEST_SIZE`.
+pub fn expand_message_xmd_fini(mut hasher: Sha512, dst: &[u8], output: &mut [u8]) {
+    let dst_len = [dst.len() as u8];
+
+    hasher.update(&(output.len() as u16).to_be_bytes());
+    hasher.update(&[0]);
+    hasher.update(dst);
+    hasher.update(&dst_len);
+    let b_0 = hasher.finalize();
+
+    // b_i = H(strxor(b_0, b_(i-1)) || I2OSP(i, 1) || DST_prime) with b_1 hashing b_0
+    let mut b_i = [0u8; DIGEST_SIZE];
+    for (i, chunk) in output.chunks_mut(DIGEST_SIZE).enumerate() {
+        for (b, b0) in b_i.iter_mut().zip(b_0.iter()) {
+            *b ^= b0;
+        }
+        let mut hasher = Sha512::new();
+        hasher.update(&b_i);
+        hasher.update(&[(i + 1) as u8]);
+        hasher.update(dst);
+        hasher.update(&dst_len);
+        b_i = hasher.finalize();
+        chunk.copy_from_slice(&b_i[..chunk.len()]);
+    }
+}
+
+fn compress(h: &mut [u64; 8], block: &[u8; BLOCK_SIZE]) {
+    let mut w = [0u64; 80];
+    for (w, chunk) in w.iter_mut().zip(block.chunks_exact(8)) {
+        *w = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    for t in 16..80 {
+        let s0 = w[t-15].rotate_right(1) ^ w[t-15].rotate_right(8) ^ (w[t-15] >> 7);
+        let s1 = w[t-2].rotate_right(19) ^ w[t-2].rotate_right(61) ^ (w[t-2] >> 6);
+        w[t] = w[t-16]
+            .wrapping_add(s0)
+            .wrapping_add(w[t-7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = *h;
+    for t in 0..80 {
+        let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = hh
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(K[t])
+            .wrapping_add(w[t]);
+        let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+
+        hh = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (h, v) in h.iter_mut().zip([a, b, c, d, e, f, g, hh].iter()) {
+        *h = h.wrapping_add(*v);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sha2::Digest;
+
+    #[test]
+    fn matches_sha2() {
+        let message: Vec<u8> = (0..600u32).map(|i| (i * 7 + 3) as u8).collect();
+        for len in [0, 1, 111, 112, 127, 128, 129, 239, 240, 256, 600].iter() {
+            let message = &message[..*len];
+            let expected = sha2::Sha512::digest(message);
+
+            let mut hasher = Sha512::new();
+            hasher.update(message);
+            assert_eq!(&hasher.finalize()[..], &expected[..]);
+
+            // absorb in uneven pieces through the serialized state
+            let mut state = Sha512::new().to_bytes();
+            for piece in message.chunks(37) {
+                let mut hasher = Sha512::from_bytes(&state).unwrap();
+                hasher.update(piece);
+                state = hasher.to_bytes();
+            }
+            assert_eq!(&Sha512::from_bytes(&state).unwrap().finalize()[..], &expected[..]);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_state() {
+        let mut hasher = Sha512::new();
+        hasher.update(&[7; 200]);
+        let state = hasher.to_bytes();
+        assert!(Sha512::from_bytes(&state).is_ok());
+        assert_eq!(
+            Sha512::from_bytes(&state[..STATE_SIZE-1]).err(),
+            Some(Curve25519Error::InvalidLength.into()),
+        );
+
+        // a buffered count past the block would overrun `buffer`
+        for (length, buffer_len) in [(200u64, 200u64), (128, 128), (200, 71)].iter() {
+            let mut state = state;
+            state[64..72].copy_from_slice(&length.to_le_bytes());
+            state[72..80].copy_from_slice(&buffer_len.to_le_bytes());
+            assert_eq!(
+                Sha512::from_bytes(&state).err(),
+                Some(Curve25519Error::InvalidSha512State.into()),
+            );
+        }
+    }
+}
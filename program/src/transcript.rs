@@ -0,0 +1,276 @@
+//! Merlin transcripts (STROBE-128 over Keccak-f[1600]) with a state that can be
+//! written to and read back from the compute buffer. Matches
+//! `merlin::Transcript` for the operations implemented here.
+
+use crate::{error::Curve25519Error, scalar::Scalar};
+
+use solana_program::{msg, program_error::ProgramError};
+
+use std::convert::TryInto;
+
+/// Bytes needed to store a `Transcript` in a buffer.
+pub const TRANSCRIPT_SIZE: usize = 200 + 3;
+
+const MERLIN_PROTOCOL_LABEL: &[u8] = b"Merlin v1.0";
+
+const STROBE_R: u8 = 166;
+
+const FLAG_I: u8 = 1;
+const FLAG_A: u8 = 1 << 1;
+const FLAG_C: u8 = 1 << 2;
+const FLAG_T: u8 = 1 << 3;
+const FLAG_M: u8 = 1 << 4;
+const FLAG_K: u8 = 1 << 5;
+
+/// The subset of STROBE-128 used by merlin.
+#[derive(Clone, Copy)]
+pub struct Strobe128 {
+    state: [u8; 200],
+    pos: u8,
+    pos_begin: u8,
+    cur_flags: u8,
+}
+
+impl Strobe128 {
+    pub fn new(protocol_label: &[u8]) -> Result<Strobe128, ProgramError> {
+        let mut state = [0u8; 200];
+        state[0..6].copy_from_slice(&[1, STROBE_R + 2, 1, 0, 1, 96]);
+        state[6..18].copy_from_slice(b"STROBEv1.0.2");
+        keccak_f1600(&mut state);
+
+        let mut strobe = Strobe128 {
+            state,
+            pos: 0,
+            pos_begin: 0,
+            cur_flags: 0,
+        };
+
+        strobe.meta_ad(protocol_label, false)?;
+
+        Ok(strobe)
+    }
+
+    pub fn meta_ad(&mut self, data: &[u8], more: bool) -> Result<(), ProgramError> {
+        self.begin_op(FLAG_M | FLAG_A, more)?;
+        self.absorb(data);
+        Ok(())
+    }
+
+    pub fn ad(&mut self, data: &[u8], more: bool) -> Result<(), ProgramError> {
+        self.begin_op(FLAG_A, more)?;
+        self.absorb(data);
+        Ok(())
+    }
+
+    pub fn prf(&mut self, data: &mut [u8], more: bool) -> Result<(), ProgramError> {
+        self.begin_op(FLAG_I | FLAG_A | FLAG_C, more)?;
+        self.squeeze(data);
+        Ok(())
+    }
+
+    fn run_f(&mut self) {
+        self.state[self.pos as usize] ^= self.pos_begin;
+        self.state[(self.pos + 1) as usize] ^= 0x04;
+        self.state[(STROBE_R + 1) as usize] ^= 0x80;
+        keccak_f1600(&mut self.state);
+        self.pos = 0;
+        self.pos_begin = 0;
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state[self.pos as usize] ^= byte;
+            self.pos += 1;
+            if self.pos == STROBE_R {
+                self.run_f();
+            }
+        }
+    }
+
+    fn squeeze(&mut self, data: &mut [u8]) {
+        for byte in data {
+            *byte = self.state[self.pos as usize];
+            self.state[self.pos as usize] = 0;
+            self.pos += 1;
+            if self.pos == STROBE_R {
+                self.run_f();
+            }
+        }
+    }
+
+    fn begin_op(&mut self, flags: u8, more: bool) -> Result<(), ProgramError> {
+        // Check if we're continuing an operation
+        if more {
+            if self.cur_flags != flags {
+                msg!("Tried to continue op {:#b} but changed flags to {:#b}", self.cur_flags, flags);
+                return Err(Curve25519Error::InvalidTranscript.into());
+            }
+            return Ok(());
+        }
+
+        // Skip adjusting direction information (we just use AD, PRF)
+        if flags & FLAG_T != 0 {
+            msg!("Transcript does not support the T flag");
+            return Err(Curve25519Error::InvalidTranscript.into());
+        }
+
+        let old_begin = self.pos_begin;
+        self.pos_begin = self.pos + 1;
+        self.cur_flags = flags;
+
+        self.absorb(&[old_begin, flags]);
+
+        // Force running F if C or K is set
+        let force_f = 0 != (flags & (FLAG_C | FLAG_K));
+
+        if force_f && self.pos != 0 {
+            self.run_f();
+        }
+
+        Ok(())
+    }
+}
+
+fn keccak_f1600(state: &mut [u8; 200]) {
+    let mut lanes = [0u64; 25];
+    for (lane, chunk) in lanes.iter_mut().zip(state.chunks_exact(8)) {
+        *lane = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    keccak::f1600(&mut lanes);
+    for (chunk, lane) in state.chunks_exact_mut(8).zip(lanes.iter()) {
+        chunk.copy_from_slice(&lane.to_le_bytes());
+    }
+}
+
+/// A merlin transcript.
+#[derive(Clone, Copy)]
+pub struct Transcript {
+    strobe: Strobe128,
+}
+
+impl Transcript {
+    pub fn new(label: &[u8]) -> Result<Transcript, ProgramError> {
+        let mut transcript = Transcript {
+            strobe: Strobe128::new(MERLIN_PROTOCOL_LABEL)?,
+        };
+        transcript.append_message(b"dom-sep", label)?;
+
+        Ok(transcript)
+    }
+
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) -> Result<(), ProgramError> {
+        let data_len = (message.len() as u32).to_le_bytes();
+        self.strobe.meta_ad(label, false)?;
+        self.strobe.meta_ad(&data_len, true)?;
+        self.strobe.ad(message, false)
+    }
+
+    pub fn append_u64(&mut self, label: &[u8], x: u64) -> Result<(), ProgramError> {
+        self.append_message(label, &x.to_le_bytes())
+    }
+
+    pub fn challenge_bytes(&mut self, label: &[u8], dest: &mut [u8]) -> Result<(), ProgramError> {
+        let data_len = (dest.len() as u32).to_le_bytes();
+        self.strobe.meta_ad(label, false)?;
+        self.strobe.meta_ad(&data_len, true)?;
+        self.strobe.prf(dest, false)
+    }
+
+    // conventions used by the zk-token-sdk proofs
+
+    pub fn append_domain_separator(&mut self, label: &[u8]) -> Result<(), ProgramError> {
+        self.append_message(b"dom-sep", label)
+    }
+
+    pub fn append_point(&mut self, label: &[u8], point: &[u8; 32]) -> Result<(), ProgramError> {
+        self.append_message(label, point)
+    }
+
+    pub fn append_scalar(&mut self, label: &[u8], scalar: &Scalar) -> Result<(), ProgramError> {
+        self.append_message(label, scalar.as_bytes())
+    }
+
+    pub fn challenge_scalar(&mut self, label: &[u8]) -> Result<Scalar, ProgramError> {
+        let mut buf = [0u8; 64];
+        self.challenge_bytes(label, &mut buf)?;
+
+        Ok(Scalar::from_bytes_mod_order_wide(&buf))
+    }
+
+    // the state comes from the compute buffer so anything a DSL instruction
+    // wrote there. pos indexes the sponge and the op being continued starts
+    // at or before it
+    pub fn from_bytes(bytes: &[u8]) -> Result<Transcript, ProgramError> {
+        if bytes.len() < TRANSCRIPT_SIZE {
+            msg!("Transcript too short");
+            return Err(Curve25519Error::InvalidLength.into());
+        }
+        let (pos, pos_begin) = (bytes[200], bytes[201]);
+        if pos >= STROBE_R || pos_begin > pos {
+            msg!("Invalid transcript position {} {}", pos, pos_begin);
+            return Err(Curve25519Error::InvalidTranscript.into());
+        }
+        Ok(Transcript {
+            strobe: Strobe128 {
+                state: bytes[..200].try_into().unwrap(),
+                pos,
+                pos_begin,
+                cur_flags: bytes[202],
+            },
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; TRANSCRIPT_SIZE] {
+        let mut bytes = [0u8; TRANSCRIPT_SIZE];
+        bytes[..200].copy_from_slice(&self.strobe.state);
+        bytes[200] = self.strobe.pos;
+        bytes[201] = self.strobe.pos_begin;
+        bytes[202] = self.strobe.cur_flags;
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matches_merlin() {
+        let message = [7u8; 1000];
+        let mut transcript = Transcript::new(b"test protocol").unwrap();
+        let mut expected = merlin::Transcript::new(b"test protocol");
+        for i in 0..5 {
+            // messages and challenges that span several sponge blocks
+            transcript.append_message(b"some label", &message[..i * 200 + 3]).unwrap();
+            expected.append_message(b"some label", &message[..i * 200 + 3]);
+            transcript.append_u64(b"n", i as u64).unwrap();
+            expected.append_u64(b"n", i as u64);
+
+            let (mut challenge, mut expected_challenge) = ([0u8; 300], [0u8; 300]);
+            transcript.challenge_bytes(b"challenge", &mut challenge).unwrap();
+            expected.challenge_bytes(b"challenge", &mut expected_challenge);
+            assert_eq!(&challenge[..], &expected_challenge[..]);
+
+            // round trip through the serialized state between operations
+            transcript = Transcript::from_bytes(&transcript.to_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn challenge_scalar_matches_merlin() {
+        // merlin::Transcript with the same operations, reduced as in the
+        // zk-token-sdk
+        let mut transcript = Transcript::new(b"test-proof-instruction").unwrap();
+        transcript.append_domain_separator(b"test-proof").unwrap();
+        transcript.append_scalar(b"z", &Scalar::from(12345u64)).unwrap();
+        let mut expected = merlin::Transcript::new(b"test-proof-instruction");
+        expected.append_message(b"dom-sep", b"test-proof");
+        expected.append_message(b"z", Scalar::from(12345u64).as_bytes());
+        let mut buf = [0u8; 64];
+        expected.challenge_bytes(b"c", &mut buf);
+        assert_eq!(
+            transcript.challenge_scalar(b"c").unwrap(),
+            Scalar::from_bytes_mod_order_wide(&buf),
+        );
+    }
+}
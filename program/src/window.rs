@@ -9,6 +9,7 @@ use subtle::Choice;
 
 use crate::traits::Identity;
 
+use crate::edwards::AffineNielsPoint;
 use crate::edwards::EdwardsPoint;
 use crate::edwards::ProjectiveNielsPoint;
 
@@ -86,10 +87,25 @@ impl<'a> From<&'a EdwardsPoint> for $name<ProjectiveNielsPoint> {
     }
 }
 
+impl<'a> From<&'a EdwardsPoint> for $name<AffineNielsPoint> {
+    fn from(P: &'a EdwardsPoint) -> Self {
+        let mut points = [P.to_affine_niels(); $size];
+        for j in $conv_range {
+            points[j + 1] = (P + &points[j]).to_extended().to_affine_niels();
+        }
+        $name(points)
+    }
+}
+
 }}  // End macro_rules! impl_lookup_table
 
 // The first one has to be named "LookupTable" because it's used as a constructor for consts.
 impl_lookup_table! {Name = LookupTable,         Size =   8, SizeNeg =   -8, SizeRange = 1 ..   9, ConversionRange = 0 ..   7} // radix-16
+impl_lookup_table! {Name = LookupTableRadix32,  Size =  16, SizeNeg =  -16, SizeRange = 1 ..  17, ConversionRange = 0 ..  15} // radix-32
+impl_lookup_table! {Name = LookupTableRadix64,  Size =  32, SizeNeg =  -32, SizeRange = 1 ..  33, ConversionRange = 0 ..  31} // radix-64
+
+// For homogeneity we then alias it to "LookupTableRadix16".
+pub type LookupTableRadix16<T> = LookupTable<T>;
 
 unsafe impl bytemuck::Zeroable for LookupTable<ProjectiveNielsPoint> {
 }
@@ -99,3 +115,66 @@ impl LookupTable<ProjectiveNielsPoint> {
     pub const TABLE_SIZE: usize = 40 * 4 * 8;
 }
 
+unsafe impl bytemuck::Zeroable for LookupTable<AffineNielsPoint> {
+}
+unsafe impl bytemuck::Pod for LookupTable<AffineNielsPoint> {
+}
+impl LookupTable<AffineNielsPoint> {
+    pub const TABLE_SIZE: usize = 40 * 3 * 8;
+}
+
+unsafe impl bytemuck::Zeroable for LookupTableRadix32<ProjectiveNielsPoint> {
+}
+unsafe impl bytemuck::Pod for LookupTableRadix32<ProjectiveNielsPoint> {
+}
+impl LookupTableRadix32<ProjectiveNielsPoint> {
+    pub const TABLE_SIZE: usize = 40 * 4 * 16;
+}
+
+unsafe impl bytemuck::Zeroable for LookupTableRadix64<ProjectiveNielsPoint> {
+}
+unsafe impl bytemuck::Pod for LookupTableRadix64<ProjectiveNielsPoint> {
+}
+impl LookupTableRadix64<ProjectiveNielsPoint> {
+    pub const TABLE_SIZE: usize = 40 * 4 * 32;
+}
+
+/// Holds odd multiples 1A, 3A, ..., 15A of a point A.
+#[derive(Copy, Clone)]
+pub struct NafLookupTable5<T>(pub(crate) [T; 8]);
+
+impl<T: Copy> NafLookupTable5<T> {
+    /// Given public, odd \\( x \\) with \\( 0 < x < 2^4 \\), return \\(xA\\).
+    pub fn select(&self, x: usize) -> T {
+        debug_assert_eq!(x & 1, 1);
+        debug_assert!(x < 16);
+
+        self.0[x / 2]
+    }
+}
+
+impl<T: Debug> Debug for NafLookupTable5<T> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "NafLookupTable5({:?})", self.0)
+    }
+}
+
+impl<'a> From<&'a EdwardsPoint> for NafLookupTable5<ProjectiveNielsPoint> {
+    fn from(A: &'a EdwardsPoint) -> Self {
+        let mut Ai = [A.to_projective_niels(); 8];
+        let A2 = A.double();
+        for i in 0..7 {
+            Ai[i + 1] = (&A2 + &Ai[i]).to_extended().to_projective_niels();
+        }
+        // Now Ai = [A, 3A, 5A, 7A, 9A, 11A, 13A, 15A]
+        NafLookupTable5(Ai)
+    }
+}
+
+unsafe impl bytemuck::Zeroable for NafLookupTable5<ProjectiveNielsPoint> {
+}
+unsafe impl bytemuck::Pod for NafLookupTable5<ProjectiveNielsPoint> {
+}
+impl NafLookupTable5<ProjectiveNielsPoint> {
+    pub const TABLE_SIZE: usize = 40 * 4 * 8;
+}
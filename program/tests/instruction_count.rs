@@ -1,18 +1,21 @@
 #![cfg(feature = "test-bpf")]
 
 use {
+    borsh::BorshDeserialize,
     solana_program_test::*,
     solana_sdk::{
+        account_info::IntoAccountInfo,
         compute_budget::ComputeBudgetInstruction,
         hash::Hash,
-        instruction::Instruction,
+        instruction::{Instruction, InstructionError},
         signer::keypair::Keypair,
         signature::Signer,
         system_instruction,
         sysvar::rent::Rent,
-        transaction::Transaction,
+        transaction::{Transaction, TransactionError},
     },
     curve25519_dalek_onchain::{
+        error::Curve25519Error,
         id,
         instruction,
         processor::process_instruction,
@@ -31,6 +34,7 @@ fn create_buffer_instructions(
     input_buffer_len: usize,
     compute_buffer: &Keypair,
     compute_buffer_len: usize,
+    soft_fail: bool,
 ) -> [Instruction; 6] {
     [
         system_instruction::create_account(
@@ -66,12 +70,21 @@ fn create_buffer_instructions(
             instruction::Key::InputBufferV1,
             vec![],
         ),
-        instruction::initialize_buffer(
-            compute_buffer.pubkey(),
-            payer.pubkey(),
-            instruction::Key::ComputeBufferV1,
-            vec![instruction_buffer.pubkey(), input_buffer.pubkey()],
-        ),
+        if soft_fail {
+            instruction::initialize_soft_fail_compute_buffer(
+                compute_buffer.pubkey(),
+                payer.pubkey(),
+                instruction_buffer.pubkey(),
+                input_buffer.pubkey(),
+            )
+        } else {
+            instruction::initialize_buffer(
+                compute_buffer.pubkey(),
+                payer.pubkey(),
+                instruction::Key::ComputeBufferV1,
+                vec![instruction_buffer.pubkey(), input_buffer.pubkey()],
+            )
+        },
     ]
 }
 
@@ -105,7 +118,7 @@ fn write_dsl_instructions(
 
 async fn crank_dsl(
     dsl: &[u8],
-    instructions_per_tx: u8,
+    steps_per_tx: u8,
     payer: &dyn Signer,
     banks_client: &mut BanksClient,
     recent_blockhash: Hash,
@@ -115,26 +128,19 @@ async fn crank_dsl(
 ) {
     let num_cranks = dsl.len() / instruction::INSTRUCTION_SIZE;
 
-    let mut current = 0;
-    while current < num_cranks {
-        println!("cranking... {}", current);
-        let mut instructions = vec![
+    let cranks = instruction::crank_compute_instructions(
+        instruction_buffer.pubkey(),
+        input_buffer.pubkey(),
+        compute_buffer.pubkey(),
+        num_cranks,
+        steps_per_tx,
+    );
+    for (i, crank) in cranks.into_iter().enumerate() {
+        println!("cranking... {}", i * steps_per_tx as usize);
+        let instructions = vec![
             ComputeBudgetInstruction::request_units(1_000_000),
-            instruction::noop(current.try_into().unwrap()),
+            crank,
         ];
-        for _j in 0..instructions_per_tx {
-            if current >= num_cranks {
-                break;
-            }
-            current += 1;
-            instructions.push(
-                instruction::crank_compute(
-                    instruction_buffer.pubkey(),
-                    input_buffer.pubkey(),
-                    compute_buffer.pubkey(),
-                ),
-            );
-        }
 
         let mut transaction = Transaction::new_with_payer(
             instructions.as_slice(),
@@ -226,7 +232,7 @@ async fn run_multiscalar_mul(
 
     assert_eq!(scalars.len(), points.len());
 
-    let dsl = instruction::transfer_proof_instructions(proof_groups.clone(), with_witness);
+    let dsl = instruction::transfer_proof_instructions_with_asserts(proof_groups.clone(), with_witness);
 
     let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
     let input_buffer_len = instruction::HEADER_SIZE + scalars.len() * 32 * 3 + 128;
@@ -249,6 +255,7 @@ async fn run_multiscalar_mul(
             input_buffer_len,
             &compute_buffer,
             compute_buffer_len,
+            false,
         ),
     );
 
@@ -310,19 +317,61 @@ async fn run_multiscalar_mul(
         buffer_idx += 128;
     }
 
+    let compute_header = instruction::ComputeHeader::deserialize(
+        &mut account.data.as_slice()).unwrap();
+    assert_eq!(compute_header.verdict, instruction::Verdict::Accepted);
+
+    let mut account = account;
+    let compute_buffer_key = compute_buffer.pubkey();
+    let compute_buffer_info = (&compute_buffer_key, &mut account).into_account_info();
+    assert!(
+        curve25519_dalek_onchain::cpi::verify_completed_compute_buffer(
+            &compute_buffer_info,
+            &instruction_buffer.pubkey(),
+            &input_buffer.pubkey(),
+        ).is_ok()
+    );
+    assert_eq!(
+        curve25519_dalek_onchain::cpi::verify_completed_compute_buffer(
+            &compute_buffer_info,
+            &instruction_buffer.pubkey(),
+            &instruction_buffer.pubkey(),
+        ).unwrap_err(),
+        solana_sdk::program_error::ProgramError::Custom(Curve25519Error::MismatchedBuffer as u32),
+    );
+    drop(compute_buffer_info);
+
+    // the instruction and input buffers can't be swapped out from under the
+    // compute buffer
+    for buffer in [instruction_buffer.pubkey(), input_buffer.pubkey()] {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction::close_buffer(buffer, payer.pubkey())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(Curve25519Error::BufferInUse as u32),
+            ),
+        );
+    }
 
     let mut transaction = Transaction::new_with_payer(
         &[
-            instruction::close_buffer(
-                instruction_buffer.pubkey(),
+            instruction::close_compute_buffer(
+                compute_buffer.pubkey(),
                 payer.pubkey(),
+                instruction_buffer.pubkey(),
+                input_buffer.pubkey(),
             ),
             instruction::close_buffer(
-                input_buffer.pubkey(),
+                instruction_buffer.pubkey(),
                 payer.pubkey(),
             ),
             instruction::close_buffer(
-                compute_buffer.pubkey(),
+                input_buffer.pubkey(),
                 payer.pubkey(),
             ),
         ],
@@ -376,6 +425,7 @@ async fn test_elligator() {
             input_buffer_len,
             &compute_buffer,
             compute_buffer_len,
+            false,
         ),
     );
 
@@ -423,6 +473,89 @@ async fn test_elligator() {
     );
 }
 
+#[tokio::test]
+async fn test_ristretto_from_uniform_bytes() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    let mut hash_bytes = [0u8; 64];
+    let mut hasher = Sha512::new();
+    hasher.update(b"test_ristretto_from_uniform_bytes");
+    hash_bytes.copy_from_slice(&hasher.finalize());
+
+    let offset = instruction::HEADER_SIZE.try_into().unwrap();
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &instruction::ristretto_from_uniform_bytes_instructions(offset, offset + 64));
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 64;
+
+    // scratch + result space
+    let compute_buffer_len = instruction::HEADER_SIZE + 64 + 32 * 24;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &hash_bytes,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 10, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let buffer_idx = instruction::HEADER_SIZE + 64 + 32 * 20;
+    let result_bytes = &account.data[buffer_idx..128+buffer_idx];
+
+    let expected = curve25519_dalek::ristretto::RistrettoPoint::from_uniform_bytes(&hash_bytes);
+    let mut expected_bytes = [0; 128];
+    expected_bytes[  ..32].copy_from_slice(&expected.0.X.to_bytes());
+    expected_bytes[32..64].copy_from_slice(&expected.0.Y.to_bytes());
+    expected_bytes[64..96].copy_from_slice(&expected.0.Z.to_bytes());
+    expected_bytes[96..  ].copy_from_slice(&expected.0.T.to_bytes());
+
+    assert_eq!(result_bytes, &expected_bytes[..]);
+}
+
 #[tokio::test]
 async fn test_edwards_elligator() {
     let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
@@ -467,6 +600,7 @@ async fn test_edwards_elligator() {
             input_buffer_len,
             &compute_buffer,
             compute_buffer_len,
+            false,
         ),
     );
 
@@ -606,6 +740,7 @@ async fn test_edwards_decompress() {
             input_buffer_len,
             &compute_buffer,
             compute_buffer_len,
+            false,
         ),
     );
 
@@ -703,6 +838,7 @@ async fn test_edwards_compress() {
             input_buffer_len,
             &compute_buffer,
             compute_buffer_len,
+            false,
         ),
     );
 
@@ -744,6 +880,91 @@ async fn test_edwards_compress() {
     );
 }
 
+#[tokio::test]
+async fn test_ristretto_compress() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    // not normalized so that Z != 1
+    let point = curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT
+        * curve25519_dalek::scalar::Scalar::from(0x0123456789abcdefu64)
+        + curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+    let mut point_bytes = [0; 128];
+    point_bytes[  ..32].copy_from_slice(&point.0.X.to_bytes());
+    point_bytes[32..64].copy_from_slice(&point.0.Y.to_bytes());
+    point_bytes[64..96].copy_from_slice(&point.0.Z.to_bytes());
+    point_bytes[96..  ].copy_from_slice(&point.0.T.to_bytes());
+
+    let offset = instruction::HEADER_SIZE.try_into().unwrap();
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &instruction::compress_ristretto_instructions(offset, offset + 32 * 4));
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 128;
+
+    // scratch + result space
+    let compute_buffer_len = instruction::HEADER_SIZE + 1000;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &point_bytes,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 10, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let buffer_idx = instruction::HEADER_SIZE + 32 * 4 + 32 * 9;
+    let compress_result_bytes = &account.data[buffer_idx..32+buffer_idx];
+
+    assert_eq!(
+        compress_result_bytes,
+        point.compress().as_bytes(),
+    );
+}
+
 #[tokio::test]
 async fn test_decompress_witness() {
     let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
@@ -797,6 +1018,7 @@ async fn test_decompress_witness() {
             input_buffer_len,
             &compute_buffer,
             compute_buffer_len,
+            false,
         ),
     );
 
@@ -859,3 +1081,2864 @@ async fn test_decompress_witness() {
         curve25519_dalek::ristretto::CompressedRistretto(compressed_bytes).decompress().unwrap(),
     );
 }
+
+#[tokio::test]
+async fn test_edwards_add_sub() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    let p = ED25519_BASEPOINT_POINT * curve25519_dalek::scalar::Scalar::from(3u64);
+    let q = ED25519_BASEPOINT_POINT * curve25519_dalek::scalar::Scalar::from(5u64);
+
+    let mut point_bytes = [0; 256];
+    point_bytes[..128].copy_from_slice(&p.to_bytes());
+    point_bytes[128..].copy_from_slice(&q.to_bytes());
+
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &[
+            instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+                input_offset: offset,
+                compute_offset: offset,
+                bytes: 256,
+            }),
+            instruction::DSLInstruction::EdwardsAdd(instruction::EdwardsBinaryOpData{
+                lhs_offset: offset,
+                rhs_offset: offset + 128,
+                result_offset: offset + 128 * 2,
+            }),
+            instruction::DSLInstruction::EdwardsSub(instruction::EdwardsBinaryOpData{
+                lhs_offset: offset,
+                rhs_offset: offset + 128,
+                result_offset: offset + 128 * 3,
+            }),
+        ]);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 256;
+
+    // inputs + result space
+    let compute_buffer_len = instruction::HEADER_SIZE + 128 * 4;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &point_bytes,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 10, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let buffer_idx = instruction::HEADER_SIZE + 128 * 2;
+    let sum = curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[buffer_idx..128+buffer_idx]
+    );
+    assert_eq!(sum, p + q);
+
+    let buffer_idx = instruction::HEADER_SIZE + 128 * 3;
+    let difference = curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[buffer_idx..128+buffer_idx]
+    );
+    assert_eq!(difference, p - q);
+}
+
+#[tokio::test]
+async fn test_assert_ristretto_equal() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    use curve25519_dalek::constants::{ED25519_BASEPOINT_POINT, EIGHT_TORSION};
+    let p = ED25519_BASEPOINT_POINT * curve25519_dalek::scalar::Scalar::from(3u64);
+    // same ristretto point, different representative
+    let p_torsion = p + EIGHT_TORSION[2];
+    let q = ED25519_BASEPOINT_POINT * curve25519_dalek::scalar::Scalar::from(5u64);
+
+    let mut point_bytes = [0; 128 * 3];
+    point_bytes[..128].copy_from_slice(&p.to_bytes());
+    point_bytes[128..256].copy_from_slice(&p_torsion.to_bytes());
+    point_bytes[256..].copy_from_slice(&q.to_bytes());
+
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &[
+            instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+                input_offset: offset,
+                compute_offset: offset,
+                bytes: 128 * 3,
+            }),
+            instruction::DSLInstruction::AssertRistrettoEqual(instruction::AssertEqualData{
+                lhs_offset: offset,
+                rhs_offset: offset + 128,
+            }),
+            instruction::DSLInstruction::AssertRistrettoEqual(instruction::AssertEqualData{
+                lhs_offset: offset,
+                rhs_offset: offset + 128 * 2,
+            }),
+            // rejection is sticky
+            instruction::DSLInstruction::AssertRistrettoEqual(instruction::AssertEqualData{
+                lhs_offset: offset + 128,
+                rhs_offset: offset,
+            }),
+        ]);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 128 * 3;
+    let compute_buffer_len = instruction::HEADER_SIZE + 128 * 3;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &point_bytes,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 10, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let compute_header = instruction::ComputeHeader::deserialize(
+        &mut account.data.as_slice()).unwrap();
+    assert_eq!(compute_header.verdict, instruction::Verdict::Rejected);
+}
+
+#[tokio::test]
+async fn test_sha512_challenge() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    // long enough to be split into multiple updates
+    let transcript_bytes: Vec<u8> = (0..600u32).map(|i| (i * 31 + 7) as u8).collect();
+    let transcript_len = transcript_bytes.len();
+
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let transcript_len_u32: u32 = transcript_len.try_into().unwrap();
+    let scalar_offset = offset + transcript_len_u32;
+    let scratch_space = scalar_offset + 32;
+
+    let mut dsl_instructions = vec![
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: offset,
+            compute_offset: offset,
+            bytes: transcript_len_u32,
+        }),
+    ];
+    // hash as two ranges, the second split into multiple updates
+    dsl_instructions.extend_from_slice(
+        &instruction::sha512_challenge_instructions(
+            &[(offset, 40), (offset + 40, transcript_len_u32 - 40)],
+            scratch_space,
+            scalar_offset,
+        ),
+    );
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + transcript_len;
+    let compute_buffer_len = instruction::HEADER_SIZE + transcript_len + 32
+        + curve25519_dalek_onchain::sha512::STATE_SIZE
+        + curve25519_dalek_onchain::sha512::DIGEST_SIZE;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // separately to stay under the transaction size limit
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            instruction::write_bytes(
+                input_buffer.pubkey(),
+                payer.pubkey(),
+                instruction::HEADER_SIZE as u32,
+                true,
+                &transcript_bytes,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 10, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let mut hash = Sha512::new();
+    hash.update(&transcript_bytes);
+    let expected = Scalar::from_hash(hash);
+
+    let buffer_idx = scalar_offset as usize;
+    assert_eq!(
+        &account.data[buffer_idx..buffer_idx+32],
+        &expected.to_packed_radix_16(),
+    );
+}
+
+#[tokio::test]
+async fn test_transcript_challenge() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    use curve25519_dalek_onchain::transcript::TRANSCRIPT_SIZE;
+
+    let point = curve25519_dalek::constants::RISTRETTO_BASEPOINT_COMPRESSED.to_bytes();
+    let scalar = Scalar::from(12345u64);
+
+    let mut input_bytes = [0; 64];
+    input_bytes[..32].copy_from_slice(&point);
+    input_bytes[32..].copy_from_slice(scalar.as_bytes());
+
+    // [ ..header.., point, scalar, transcript, challenges, scratch, labels ]
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let transcript_offset = offset + 64;
+    let challenges_offset = transcript_offset + TRANSCRIPT_SIZE as u32;
+    let scratch_space = challenges_offset + 64;
+    let label_space = scratch_space + 64;
+
+    let mut transcript = instruction::DSLTranscript::new(
+        b"test-proof-instruction", transcript_offset, label_space);
+    transcript.append_domain_separator(b"test-proof");
+    transcript.append_point(b"Y", offset);
+    transcript.append_scalar(b"z", offset + 32);
+    transcript.challenge_scalar(b"c", scratch_space, challenges_offset);
+    transcript.challenge_scalar(b"w", scratch_space, challenges_offset + 32);
+
+    let mut dsl_instructions = vec![
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: offset,
+            compute_offset: offset,
+            bytes: 64,
+        }),
+    ];
+    dsl_instructions.extend_from_slice(&transcript.instructions);
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 64;
+    let compute_buffer_len = transcript.label_space_end() as usize;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &input_bytes,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    // what the prover would have done, with the zk-token-sdk conventions on
+    // top of merlin
+    let mut expected = merlin::Transcript::new(b"test-proof-instruction");
+    expected.append_message(b"dom-sep", b"test-proof");
+    expected.append_message(b"Y", &point);
+    expected.append_message(b"z", scalar.as_bytes());
+    let mut challenge_scalar = |label: &'static [u8]| {
+        let mut buf = [0u8; 64];
+        expected.challenge_bytes(label, &mut buf);
+        Scalar::from_bytes_mod_order_wide(&buf)
+    };
+    let c = challenge_scalar(b"c");
+    let w = challenge_scalar(b"w");
+
+    let buffer_idx = challenges_offset as usize;
+    assert_eq!(&account.data[buffer_idx..buffer_idx+32], &c.to_packed_radix_16());
+    assert_eq!(&account.data[buffer_idx+32..buffer_idx+64], &w.to_packed_radix_16());
+}
+
+#[tokio::test]
+async fn test_scalar_arithmetic() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    let s = Scalar::from(1234567u64);
+    let c = -Scalar::from(89u64);
+    let x = Scalar::from(1u64 << 40) * Scalar::from(1u64 << 40);
+    let wide = [0xffu8; 64];
+
+    let mut input_bytes = [0; 32 * 5];
+    input_bytes[..32].copy_from_slice(s.as_bytes());
+    input_bytes[32..64].copy_from_slice(c.as_bytes());
+    input_bytes[64..96].copy_from_slice(x.as_bytes());
+    input_bytes[96..].copy_from_slice(&wide);
+
+    // [ ..header.., s, c, x, wide (64), c*x, z, -z, z + -z, from_wide, packed z ]
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let scalar_offset = |i: u32| offset + 32 * i;
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &[
+            instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+                input_offset: offset,
+                compute_offset: offset,
+                bytes: 32 * 5,
+            }),
+            instruction::DSLInstruction::ScalarMul(instruction::ScalarBinaryOpData{
+                lhs_offset: scalar_offset(1),
+                rhs_offset: scalar_offset(2),
+                result_offset: scalar_offset(5),
+            }),
+            instruction::DSLInstruction::ScalarSub(instruction::ScalarBinaryOpData{
+                lhs_offset: scalar_offset(0),
+                rhs_offset: scalar_offset(5),
+                result_offset: scalar_offset(6),
+            }),
+            instruction::DSLInstruction::ScalarNeg(instruction::ScalarUnaryOpData{
+                scalar_offset: scalar_offset(6),
+                result_offset: scalar_offset(7),
+            }),
+            instruction::DSLInstruction::ScalarAdd(instruction::ScalarBinaryOpData{
+                lhs_offset: scalar_offset(6),
+                rhs_offset: scalar_offset(7),
+                result_offset: scalar_offset(8),
+            }),
+            instruction::DSLInstruction::ScalarFromWide(instruction::ScalarFromWideData{
+                wide_offset: scalar_offset(3),
+                scalar_offset: scalar_offset(9),
+            }),
+            instruction::DSLInstruction::PackScalar(instruction::ScalarUnaryOpData{
+                scalar_offset: scalar_offset(6),
+                result_offset: scalar_offset(10),
+            }),
+        ]);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 32 * 5;
+    let compute_buffer_len = instruction::HEADER_SIZE + 32 * 11;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &input_bytes,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 10, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let read_scalar = |i: u32| {
+        let buffer_idx = scalar_offset(i) as usize;
+        &account.data[buffer_idx..buffer_idx+32]
+    };
+
+    let z = s - c * x;
+    assert_eq!(read_scalar(5), (c * x).as_bytes());
+    assert_eq!(read_scalar(6), z.as_bytes());
+    assert_eq!(read_scalar(7), (-z).as_bytes());
+    assert_eq!(read_scalar(8), Scalar::zero().as_bytes());
+    assert_eq!(read_scalar(9), Scalar::from_bytes_mod_order_wide(&wide).as_bytes());
+    assert_eq!(read_scalar(10), &z.to_packed_radix_16());
+}
+
+#[tokio::test]
+async fn test_load_scalar() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    let s = Scalar::from(1234567u64);
+
+    // s and the group order, which is not canonical
+    let mut input_bytes = [0; 32 * 2];
+    input_bytes[..32].copy_from_slice(s.as_bytes());
+    input_bytes[32..].copy_from_slice(&curve25519_dalek::constants::BASEPOINT_ORDER.to_bytes());
+
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &[
+            instruction::DSLInstruction::LoadScalar(instruction::LoadScalarData{
+                input_offset: offset,
+                compute_offset: offset,
+            }),
+            instruction::DSLInstruction::LoadScalar(instruction::LoadScalarData{
+                input_offset: offset + 32,
+                compute_offset: offset + 32,
+            }),
+        ]);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 32 * 2;
+    let compute_buffer_len = instruction::HEADER_SIZE + 32 * 2;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &input_bytes,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut cranks = instruction::crank_compute_instructions(
+        instruction_buffer.pubkey(),
+        input_buffer.pubkey(),
+        compute_buffer.pubkey(),
+        2,
+        1,
+    ).into_iter();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[cranks.next().unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+    let buffer_idx = offset as usize;
+    assert_eq!(&account.data[buffer_idx..buffer_idx+32], &s.to_packed_radix_16());
+
+    let mut transaction = Transaction::new_with_payer(
+        &[cranks.next().unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(Curve25519Error::NonCanonicalScalar as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn test_scalar_invert() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    let x = Scalar::from(0xdeadbeef12345u64) * Scalar::from(0x1234567u64);
+    let batch = [Scalar::from(3u64), -Scalar::from(5u64), x * x];
+
+    let mut input_bytes = [0; 32 * 4];
+    input_bytes[..32].copy_from_slice(x.as_bytes());
+    for (i, s) in batch.iter().enumerate() {
+        input_bytes[32*(i+1)..32*(i+2)].copy_from_slice(s.as_bytes());
+    }
+
+    // [ ..header.., ..single inversion.., ..batch inversion.. ]
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let batch_offset = offset + 32 * 12;
+
+    let mut dsl_instructions = vec![
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: offset,
+            compute_offset: offset,
+            bytes: 32,
+        }),
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: offset + 32,
+            compute_offset: batch_offset,
+            bytes: 32 * 3,
+        }),
+    ];
+    dsl_instructions.extend_from_slice(
+        &instruction::scalar_invert_instructions(offset));
+    dsl_instructions.extend_from_slice(
+        &instruction::batch_scalar_invert_instructions(batch_offset, 3));
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 32 * 4;
+    let compute_buffer_len = instruction::HEADER_SIZE + 32 * 12 + 32 * (2 * 3 + 12);
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &input_bytes,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 3, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let buffer_idx = (offset + 32 * 11) as usize;
+    assert_eq!(&account.data[buffer_idx..buffer_idx+32], x.invert().as_bytes());
+
+    for (i, s) in batch.iter().enumerate() {
+        let buffer_idx = batch_offset as usize + 32 * i;
+        assert_eq!(&account.data[buffer_idx..buffer_idx+32], s.invert().as_bytes());
+    }
+
+    // a single zero has no inverse and fails the whole batch
+    let mut zero_batch = [0; 32 * 3];
+    zero_batch[..32].copy_from_slice(batch[0].as_bytes());
+    zero_batch[64..].copy_from_slice(batch[2].as_bytes());
+
+    let mut dsl_instructions = vec![
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: offset,
+            compute_offset: offset,
+            bytes: 32 * 3,
+        }),
+    ];
+    dsl_instructions.extend_from_slice(
+        &instruction::batch_scalar_invert_instructions(offset, 3));
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let (_, result) = run_dsl(
+        &dsl, &zero_batch, instruction::HEADER_SIZE + 32 * (2 * 3 + 12), false,
+        &payer, &mut banks_client, recent_blockhash,
+    ).await;
+    assert_eq!(
+        result,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(Curve25519Error::ZeroScalar as u32),
+        )),
+    );
+}
+
+#[tokio::test]
+async fn test_ed25519_verify() {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+    type DalekScalar = curve25519_dalek::scalar::Scalar;
+
+    let secret = DalekScalar::from(0x1234567890abcdefu64) * DalekScalar::from(0xfedcba987u64);
+    let message: Vec<u8> = (0..300u32).map(|i| i as u8).collect();
+
+    // ed25519 signature with a deterministic nonce
+    let pubkey = (&secret * &ED25519_BASEPOINT_TABLE).compress().to_bytes();
+    let mut hash = Sha512::new();
+    hash.update(b"nonce");
+    hash.update(&message);
+    let r = DalekScalar::from_hash(hash);
+    let nonce_point = (&r * &ED25519_BASEPOINT_TABLE).compress().to_bytes();
+    let mut hash = Sha512::new();
+    hash.update(&nonce_point);
+    hash.update(&pubkey);
+    hash.update(&message);
+    let k = DalekScalar::from_hash(hash);
+    let s = r + k * secret;
+
+    let mut signature = [0; 64];
+    signature[..32].copy_from_slice(&nonce_point);
+    signature[32..].copy_from_slice(s.as_bytes());
+
+    let mut bad_signature = signature;
+    bad_signature[40] ^= 1;
+
+    assert_eq!(
+        run_ed25519_verify(&pubkey, &signature, &message).await,
+        instruction::Verdict::Accepted,
+    );
+    assert_eq!(
+        run_ed25519_verify(&pubkey, &bad_signature, &message).await,
+        instruction::Verdict::Rejected,
+    );
+}
+
+async fn run_ed25519_verify(
+    pubkey: &[u8; 32],
+    signature: &[u8; 64],
+    message: &[u8],
+) -> instruction::Verdict {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    // [ ..header.., pubkey, signature, message ]
+    let mut input_bytes = vec![];
+    input_bytes.extend_from_slice(pubkey);
+    input_bytes.extend_from_slice(signature);
+    input_bytes.extend_from_slice(message);
+
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let message_len: u32 = message.len().try_into().unwrap();
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &instruction::ed25519_verify_instructions(
+            offset,
+            offset + 32,
+            offset + 96..offset + 96 + message_len,
+        ),
+    );
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + input_bytes.len();
+    let compute_buffer_len = instruction::ed25519_verify_compute_buffer_len(message_len);
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &input_bytes,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let compute_header = instruction::ComputeHeader::deserialize(
+        &mut account.data.as_slice()).unwrap();
+    compute_header.verdict
+}
+
+#[tokio::test]
+async fn test_hash_to_curve() {
+    // RFC 9380 appendix J.5
+    let hex_to_le = |hex: &str| -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for i in 0..32 {
+            bytes[31 - i] = u8::from_str_radix(&hex[2*i..2*i+2], 16).unwrap();
+        }
+        bytes
+    };
+    let compressed = |x: &str, y: &str| -> [u8; 32] {
+        let mut bytes = hex_to_le(y);
+        bytes[31] |= (hex_to_le(x)[0] & 1) << 7;
+        bytes
+    };
+
+    let dst = b"QUUX-V01-CS02-with-edwards25519_XMD:SHA-512_ELL2_RO_";
+    assert_eq!(
+        run_hash_to_curve(b"", dst, true).await,
+        compressed(
+            "3c3da6925a3c3c268448dcabb47ccde5439559d9599646a8260e47b1e4822fc6",
+            "09a6c8561a0b22bef63124c588ce4c62ea83a3c899763af26d795302e115dc21",
+        ),
+    );
+    assert_eq!(
+        run_hash_to_curve(b"abc", dst, true).await,
+        compressed(
+            "608040b42285cc0d72cbb3985c6b04c935370c7361f4b7fbdb1ae7f8c1a8ecad",
+            "1a8395b88338f22e435bbd301183e7f20a5f9de643f11882fb237f88268a5531",
+        ),
+    );
+    assert_eq!(
+        run_hash_to_curve(b"abcdef0123456789", dst, true).await,
+        compressed(
+            "6d7fabf47a2dc03fe7d47f7dddd21082c5fb8f86743cd020f3fb147d57161472",
+            "53060a3d140e7fbcda641ed3cf42c88a75411e648a1add71217f70ea8ec561a6",
+        ),
+    );
+
+    let dst = b"QUUX-V01-CS02-with-edwards25519_XMD:SHA-512_ELL2_NU_";
+    assert_eq!(
+        run_hash_to_curve(b"", dst, false).await,
+        compressed(
+            "1ff2b70ecf862799e11b7ae744e3489aa058ce805dd323a936375a84695e76da",
+            "222e314d04a4d5725e9f2aff9fb2a6b69ef375a1214eb19021ceab2d687f0f9b",
+        ),
+    );
+    assert_eq!(
+        run_hash_to_curve(b"abc", dst, false).await,
+        compressed(
+            "5f13cc69c891d86927eb37bd4afc6672360007c63f68a33ab423a3aa040fd2a8",
+            "67732d50f9a26f73111dd1ed5dba225614e538599db58ba30aaea1f5c827fa42",
+        ),
+    );
+}
+
+async fn run_hash_to_curve(
+    message: &[u8],
+    dst: &[u8],
+    random_oracle: bool,
+) -> [u8; 32] {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let message_len: u32 = message.len().try_into().unwrap();
+    let scratch_space = offset + message_len;
+
+    let mut dsl_instructions = vec![
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: offset,
+            compute_offset: offset,
+            bytes: message_len,
+        }),
+    ];
+    let ranges = [(offset, message_len)];
+    if random_oracle {
+        dsl_instructions.extend(
+            instruction::hash_to_curve_instructions(&ranges, dst, scratch_space));
+    } else {
+        dsl_instructions.extend(
+            instruction::encode_to_curve_instructions(&ranges, dst, scratch_space));
+    }
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + message.len();
+    let compute_buffer_len = scratch_space as usize
+        + instruction::hash_to_curve_scratch_len(dst, random_oracle);
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            message,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let buffer_idx = scratch_space as usize;
+    curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[buffer_idx..128+buffer_idx]
+    ).compress().to_bytes()
+}
+
+#[tokio::test]
+async fn test_basepoint_mul() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    let scalar = curve25519_dalek::scalar::Scalar::from(0x1234567890abcdefu64)
+        * curve25519_dalek::scalar::Scalar::from(0xfedcba987u64);
+
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let mut dsl_instructions = vec![
+        instruction::DSLInstruction::LoadScalar(instruction::LoadScalarData{
+            input_offset: offset,
+            compute_offset: offset,
+        }),
+    ];
+    dsl_instructions.extend(
+        instruction::basepoint_mul_instructions(offset, offset + 32));
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 32;
+    let compute_buffer_len = instruction::HEADER_SIZE + 32 + 128;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            scalar.as_bytes(),
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let buffer_idx = instruction::HEADER_SIZE + 32;
+    let result = curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[buffer_idx..128+buffer_idx]
+    );
+
+    assert_eq!(
+        result.compress(),
+        (&scalar * &curve25519_dalek::constants::ED25519_BASEPOINT_TABLE).compress(),
+    );
+}
+
+#[tokio::test]
+async fn test_multiscalar_mul_table_buffer() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+    let table_buffer = Keypair::new();
+
+    use curve25519_dalek_onchain::backend::serial::u64::constants;
+    let num_points = 8;
+    let points: Vec<_> = (0..num_points)
+        .map(|i| &Scalar::from(7919 * i as u64 + 3) * &constants::ED25519_BASEPOINT_TABLE)
+        .collect();
+    let scalars: Vec<_> = (0..num_points)
+        .map(|i| curve25519_dalek::scalar::Scalar::from(0x9e3779b97f4a7c15u64.wrapping_mul(i as u64 + 1)))
+        .collect();
+
+    // generators are built once and finalized before any compute buffer uses them
+    let table_buffer_len = instruction::table_buffer_len(num_points);
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &table_buffer.pubkey(),
+                rent.minimum_balance(table_buffer_len),
+                table_buffer_len as u64,
+                &id(),
+            ),
+            instruction::initialize_buffer(
+                table_buffer.pubkey(),
+                payer.pubkey(),
+                instruction::Key::TableBufferV1,
+                vec![],
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &table_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // tables are built in order
+    let mut transaction = Transaction::new_with_payer(
+        &instruction::build_tables(table_buffer.pubkey(), payer.pubkey(), 1, &points[..1]),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(Curve25519Error::InvalidTableOffset as u32),
+        ),
+    );
+
+    let mut instructions = instruction::build_tables(
+        table_buffer.pubkey(), payer.pubkey(), 0, &points);
+    instructions.extend(
+        instruction::finalize_buffer(table_buffer.pubkey(), payer.pubkey()));
+    for instruction in instructions {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    let offset = instruction::HEADER_SIZE;
+    let mut dsl_instructions = vec![];
+    for i in 0..num_points {
+        dsl_instructions.push(
+            instruction::DSLInstruction::LoadScalar(instruction::LoadScalarData{
+                input_offset: (offset + 32 * i).try_into().unwrap(),
+                compute_offset: (offset + 32 * i).try_into().unwrap(),
+            }),
+        );
+    }
+    dsl_instructions.extend(
+        instruction::multiscalar_mul_table_buffer_instructions(
+            num_points, offset, 0, offset + 32 * num_points));
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 32 * num_points;
+    let compute_buffer_len = instruction::HEADER_SIZE + 32 * num_points + 128;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+    // bind the instruction buffer to the table buffer
+    instructions[3] = instruction::initialize_buffer(
+        instruction_buffer.pubkey(),
+        payer.pubkey(),
+        instruction::Key::InstructionBufferV1,
+        vec![table_buffer.pubkey()],
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut instructions = vec![];
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    let scalar_bytes: Vec<u8> = scalars.iter().flat_map(|s| s.to_bytes().to_vec()).collect();
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &scalar_bytes,
+        ),
+    );
+    for instruction in instructions {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    let num_cranks = dsl.len() / instruction::INSTRUCTION_SIZE;
+    for current in (0..num_cranks).step_by(5) {
+        let instructions = vec![
+            ComputeBudgetInstruction::request_units(1_000_000),
+            instruction::crank_compute_with_table_buffer(
+                instruction_buffer.pubkey(),
+                input_buffer.pubkey(),
+                compute_buffer.pubkey(),
+                table_buffer.pubkey(),
+                current.try_into().unwrap(),
+                5,
+            ),
+        ];
+
+        let mut transaction = Transaction::new_with_payer(
+            instructions.as_slice(),
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let buffer_idx = instruction::HEADER_SIZE + 32 * num_points;
+    let result = curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[buffer_idx..128+buffer_idx]
+    );
+
+    use curve25519_dalek::traits::MultiscalarMul;
+    let expected = curve25519_dalek::edwards::EdwardsPoint::multiscalar_mul(
+        scalars.iter(),
+        points.iter().map(|p| curve25519_dalek::edwards::EdwardsPoint::from_bytes(&p.to_bytes())),
+    );
+    assert_eq!(result.compress(), expected.compress());
+
+    // reading past the built tables or between them fails instead of
+    // selecting zeroed or misaligned points
+    let result_offset = (offset + 32 * num_points) as u32;
+    for tables_offset in [
+        instruction::table_buffer_len(1),
+        instruction::HEADER_SIZE + 128,
+    ] {
+        let dsl = instruction::dsl_instructions_to_bytes(
+            &[
+                instruction::DSLInstruction::MultiscalarMulTableBuffer(instruction::MultiscalarMulData{
+                    start: 63,
+                    end: 64,
+                    num_inputs: num_points as u8,
+                    scalars_offset: offset as u32,
+                    tables_offset: tables_offset as u32,
+                    result_offset,
+                }),
+            ]);
+        let (_, result) = run_dsl_with_table_buffer(
+            &dsl, &[], compute_buffer_len, false, Some(table_buffer.pubkey()),
+            &payer, &mut banks_client, recent_blockhash,
+        ).await;
+        assert_eq!(
+            result,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(Curve25519Error::InvalidTableOffset as u32),
+            )),
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_vartime_multiscalar_mul() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    use curve25519_dalek_onchain::backend::serial::u64::constants;
+    let num_points = instruction::MAX_VARTIME_MULTISCALAR_POINTS;
+    let points: Vec<_> = (0..num_points)
+        .map(|i| &Scalar::from(7919 * i as u64 + 3) * &constants::ED25519_BASEPOINT_TABLE)
+        .collect();
+    let scalars: Vec<_> = (0..num_points)
+        .map(|i| curve25519_dalek::scalar::Scalar::from(0x9e3779b97f4a7c15u64.wrapping_mul(i as u64 + 1)))
+        .collect();
+
+    // input buffer is laid out as [ ..header.., ..scalars.., ..points.. ]
+    let mut input = vec![];
+    for scalar in scalars.iter() {
+        input.extend_from_slice(scalar.as_bytes());
+    }
+    for point in points.iter() {
+        input.extend_from_slice(&point.to_bytes());
+    }
+
+    let offset = instruction::HEADER_SIZE;
+    let points_offset = offset + 32 * num_points;
+    let scratch_space = points_offset + 128 * num_points;
+    let result_offset = scratch_space + instruction::vartime_multiscalar_mul_scratch_len(num_points);
+
+    let mut dsl_instructions = vec![
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: offset.try_into().unwrap(),
+            compute_offset: offset.try_into().unwrap(),
+            bytes: input.len().try_into().unwrap(),
+        }),
+    ];
+    dsl_instructions.extend(
+        instruction::vartime_multiscalar_mul_instructions(
+            num_points, offset, points_offset, scratch_space, result_offset));
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + input.len();
+    let compute_buffer_len = result_offset + 128;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut instructions = vec![];
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+    for (i, chunk) in input.chunks(800).enumerate() {
+        instructions.push(
+            instruction::write_bytes(
+                input_buffer.pubkey(),
+                payer.pubkey(),
+                (instruction::HEADER_SIZE + 800 * i) as u32,
+                800 * i + chunk.len() == input.len(),
+                chunk,
+            ),
+        );
+    }
+    for instruction in instructions {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let result = curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[result_offset..128+result_offset]
+    );
+
+    use curve25519_dalek::traits::VartimeMultiscalarMul;
+    let expected = curve25519_dalek::edwards::EdwardsPoint::vartime_multiscalar_mul(
+        scalars.iter(),
+        points.iter().map(|p| curve25519_dalek::edwards::EdwardsPoint::from_bytes(&p.to_bytes())),
+    );
+    assert_eq!(result.compress(), expected.compress());
+}
+
+#[tokio::test]
+async fn test_pippenger() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    use curve25519_dalek_onchain::backend::serial::u64::constants;
+    let num_points = 48;
+    let points: Vec<_> = (0..num_points)
+        .map(|i| &Scalar::from(7919 * i as u64 + 3) * &constants::ED25519_BASEPOINT_TABLE)
+        .collect();
+    let x = curve25519_dalek::scalar::Scalar::from(2128506u64).invert();
+    let y = curve25519_dalek::scalar::Scalar::from(4443282u64).invert();
+    let scalars: Vec<_> = (0..num_points)
+        .map(|i| x + curve25519_dalek::scalar::Scalar::from(i as u64) * y)
+        .collect();
+
+    // input buffer is laid out as [ ..header.., ..scalars.., ..points.. ]
+    let mut input = vec![];
+    for scalar in scalars.iter() {
+        input.extend_from_slice(scalar.as_bytes());
+    }
+    for point in points.iter() {
+        input.extend_from_slice(&point.to_bytes());
+    }
+
+    let offset = instruction::HEADER_SIZE;
+    let points_offset = offset + 32 * num_points;
+    let scratch_space = points_offset + 128 * num_points;
+
+    let mut dsl_instructions = vec![
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: offset.try_into().unwrap(),
+            compute_offset: offset.try_into().unwrap(),
+            bytes: input.len().try_into().unwrap(),
+        }),
+    ];
+    dsl_instructions.extend(
+        instruction::pippenger_instructions(
+            num_points, offset, points_offset, scratch_space));
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + input.len();
+    let compute_buffer_len = scratch_space + instruction::pippenger_scratch_len(num_points);
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut instructions = vec![];
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+    for (i, chunk) in input.chunks(800).enumerate() {
+        instructions.push(
+            instruction::write_bytes(
+                input_buffer.pubkey(),
+                payer.pubkey(),
+                (instruction::HEADER_SIZE + 800 * i) as u32,
+                800 * i + chunk.len() == input.len(),
+                chunk,
+            ),
+        );
+    }
+    for instruction in instructions {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let result = curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[scratch_space..128+scratch_space]
+    );
+
+    use curve25519_dalek::traits::VartimeMultiscalarMul;
+    let expected = curve25519_dalek::edwards::EdwardsPoint::vartime_multiscalar_mul(
+        scalars.iter(),
+        points.iter().map(|p| curve25519_dalek::edwards::EdwardsPoint::from_bytes(&p.to_bytes())),
+    );
+    assert_eq!(result.compress(), expected.compress());
+}
+
+#[tokio::test]
+async fn test_multiscalar_mul_radix() {
+    run_multiscalar_mul_radix(5).await;
+    run_multiscalar_mul_radix(6).await;
+}
+
+async fn run_multiscalar_mul_radix(w: usize) {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    use curve25519_dalek_onchain::backend::serial::u64::constants;
+    let num_points = if w == 5 {
+        instruction::MAX_MULTISCALAR_RADIX_32_POINTS
+    } else {
+        instruction::MAX_MULTISCALAR_RADIX_64_POINTS
+    };
+    let points: Vec<_> = (0..num_points)
+        .map(|i| &Scalar::from(7919 * i as u64 + 3) * &constants::ED25519_BASEPOINT_TABLE)
+        .collect();
+    let scalars: Vec<_> = (0..num_points)
+        .map(|i| curve25519_dalek::scalar::Scalar::from(0x9e3779b97f4a7c15u64.wrapping_mul(i as u64 + 1)))
+        .collect();
+
+    // input buffer is laid out as [ ..header.., ..scalars.., ..points.. ]
+    let mut input = vec![];
+    for scalar in scalars.iter() {
+        input.extend_from_slice(scalar.as_bytes());
+    }
+    for point in points.iter() {
+        input.extend_from_slice(&point.to_bytes());
+    }
+
+    let offset = instruction::HEADER_SIZE;
+    let points_offset = offset + 32 * num_points;
+    let scratch_space = points_offset + 128 * num_points;
+    let result_offset = scratch_space + instruction::multiscalar_mul_radix_scratch_len(w, num_points);
+
+    let mut dsl_instructions = vec![
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: offset.try_into().unwrap(),
+            compute_offset: offset.try_into().unwrap(),
+            bytes: input.len().try_into().unwrap(),
+        }),
+    ];
+    dsl_instructions.extend(
+        instruction::multiscalar_mul_radix_instructions(
+            w, num_points, offset, points_offset, scratch_space, result_offset));
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    // a full group takes fewer cranks than radix-16 would for the same points
+    let steps = dsl_instructions.iter().filter(|ix| matches!(ix,
+        instruction::DSLInstruction::MultiscalarMulRadix32(_)
+        | instruction::DSLInstruction::MultiscalarMulRadix64(_)
+    )).count();
+    assert_eq!(steps, if w == 5 { 52 } else { 43 });
+    assert_eq!(steps, instruction::multiscalar_mul_steps(w, num_points));
+    assert!(steps < instruction::multiscalar_mul_steps(4, num_points));
+    // and more once a second group is needed
+    assert!(
+        instruction::multiscalar_mul_steps(w, num_points + 1)
+        > instruction::multiscalar_mul_steps(4, num_points + 1)
+    );
+    // copy, pack + table per point, identity, steps
+    assert_eq!(dsl.len() / instruction::INSTRUCTION_SIZE, 1 + 2 * num_points + 1 + steps);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + input.len();
+    let compute_buffer_len = result_offset + 128;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut instructions = vec![];
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+    for (i, chunk) in input.chunks(800).enumerate() {
+        instructions.push(
+            instruction::write_bytes(
+                input_buffer.pubkey(),
+                payer.pubkey(),
+                (instruction::HEADER_SIZE + 800 * i) as u32,
+                800 * i + chunk.len() == input.len(),
+                chunk,
+            ),
+        );
+    }
+    for instruction in instructions {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let result = curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[result_offset..128+result_offset]
+    );
+
+    use curve25519_dalek::traits::VartimeMultiscalarMul;
+    let expected = curve25519_dalek::edwards::EdwardsPoint::vartime_multiscalar_mul(
+        scalars.iter(),
+        points.iter().map(|p| curve25519_dalek::edwards::EdwardsPoint::from_bytes(&p.to_bytes())),
+    );
+    assert_eq!(result.compress(), expected.compress());
+}
+
+#[tokio::test]
+async fn test_multiscalar_mul_affine() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    use curve25519_dalek_onchain::backend::serial::u64::constants;
+    let num_points = instruction::MAX_MULTISCALAR_AFFINE_POINTS;
+    let points: Vec<_> = (0..num_points)
+        .map(|i| &Scalar::from(7919 * i as u64 + 3) * &constants::ED25519_BASEPOINT_TABLE)
+        .collect();
+    let scalars: Vec<_> = (0..num_points)
+        .map(|i| curve25519_dalek::scalar::Scalar::from(0x9e3779b97f4a7c15u64.wrapping_mul(i as u64 + 1)))
+        .collect();
+
+    // input buffer is laid out as [ ..header.., ..scalars.., ..points.. ]
+    let mut input = vec![];
+    for scalar in scalars.iter() {
+        input.extend_from_slice(scalar.as_bytes());
+    }
+    for point in points.iter() {
+        input.extend_from_slice(&point.to_bytes());
+    }
+
+    let offset = instruction::HEADER_SIZE;
+    let points_offset = offset + 32 * num_points;
+    let scratch_space = points_offset + 128 * num_points;
+    let tables_offset = instruction::affine_lookup_tables_offset(num_points, scratch_space);
+    let result_offset = scratch_space + instruction::affine_lookup_tables_scratch_len(num_points);
+
+    let mut dsl_instructions = vec![];
+    for i in 0..num_points {
+        dsl_instructions.push(
+            instruction::DSLInstruction::LoadScalar(instruction::LoadScalarData{
+                input_offset: (offset + 32 * i).try_into().unwrap(),
+                compute_offset: (offset + 32 * i).try_into().unwrap(),
+            }),
+        );
+    }
+    dsl_instructions.push(
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: points_offset.try_into().unwrap(),
+            compute_offset: points_offset.try_into().unwrap(),
+            bytes: (128 * num_points).try_into().unwrap(),
+        }),
+    );
+    dsl_instructions.extend(
+        instruction::build_affine_lookup_tables_instructions(
+            num_points, points_offset, scratch_space));
+    dsl_instructions.extend(
+        instruction::multiscalar_mul_affine_instructions(
+            num_points, offset, tables_offset, result_offset));
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + input.len();
+    let compute_buffer_len = result_offset + 128;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut instructions = vec![];
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+    for (i, chunk) in input.chunks(800).enumerate() {
+        instructions.push(
+            instruction::write_bytes(
+                input_buffer.pubkey(),
+                payer.pubkey(),
+                (instruction::HEADER_SIZE + 800 * i) as u32,
+                800 * i + chunk.len() == input.len(),
+                chunk,
+            ),
+        );
+    }
+    for instruction in instructions {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let result = curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[result_offset..128+result_offset]
+    );
+
+    use curve25519_dalek::traits::VartimeMultiscalarMul;
+    let expected = curve25519_dalek::edwards::EdwardsPoint::vartime_multiscalar_mul(
+        scalars.iter(),
+        points.iter().map(|p| curve25519_dalek::edwards::EdwardsPoint::from_bytes(&p.to_bytes())),
+    );
+    assert_eq!(result.compress(), expected.compress());
+}
+
+// creates buffers for `dsl` and `input` and cranks one step per transaction,
+// stopping at the first error
+async fn run_dsl(
+    dsl: &[u8],
+    input: &[u8],
+    compute_buffer_len: usize,
+    soft_fail: bool,
+    payer: &dyn Signer,
+    banks_client: &mut BanksClient,
+    recent_blockhash: Hash,
+) -> (Keypair, Result<(), TransactionError>) {
+    run_dsl_with_table_buffer(
+        dsl, input, compute_buffer_len, soft_fail, None,
+        payer, banks_client, recent_blockhash,
+    ).await
+}
+
+// `run_dsl` with the instruction buffer bound to and cranked with
+// `table_buffer`
+async fn run_dsl_with_table_buffer(
+    dsl: &[u8],
+    input: &[u8],
+    compute_buffer_len: usize,
+    soft_fail: bool,
+    table_buffer: Option<solana_sdk::pubkey::Pubkey>,
+    payer: &dyn Signer,
+    banks_client: &mut BanksClient,
+    recent_blockhash: Hash,
+) -> (Keypair, Result<(), TransactionError>) {
+    let rent = banks_client.get_rent().await.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    let mut instructions = create_buffer_instructions(
+        payer,
+        &rent,
+        &instruction_buffer,
+        instruction::HEADER_SIZE + dsl.len(),
+        &input_buffer,
+        instruction::HEADER_SIZE + input.len(),
+        &compute_buffer,
+        compute_buffer_len,
+        soft_fail,
+    );
+    if let Some(table_buffer) = table_buffer {
+        instructions[3] = instruction::initialize_buffer(
+            instruction_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::Key::InstructionBufferV1,
+            vec![table_buffer],
+        );
+    }
+    let mut transaction = Transaction::new_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut instructions = vec![];
+    write_dsl_instructions(&mut instructions, dsl, payer, &instruction_buffer);
+    for (i, chunk) in input.chunks(800).enumerate() {
+        instructions.push(
+            instruction::write_bytes(
+                input_buffer.pubkey(),
+                payer.pubkey(),
+                (instruction::HEADER_SIZE + 800 * i) as u32,
+                800 * i + chunk.len() == input.len(),
+                chunk,
+            ),
+        );
+    }
+    for instruction in instructions {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    let cranks = instruction::crank_compute_instructions(
+        instruction_buffer.pubkey(),
+        input_buffer.pubkey(),
+        compute_buffer.pubkey(),
+        dsl.len() / instruction::INSTRUCTION_SIZE,
+        1,
+    );
+    for mut crank in cranks {
+        if let Some(table_buffer) = table_buffer {
+            crank.accounts.push(
+                solana_sdk::instruction::AccountMeta::new_readonly(table_buffer, false));
+        }
+        let mut transaction = Transaction::new_with_payer(
+            &[crank],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[payer], recent_blockhash);
+        if let Err(err) = banks_client.process_transaction(transaction).await {
+            return (compute_buffer, Err(err.unwrap()));
+        }
+    }
+
+    (compute_buffer, Ok(()))
+}
+
+#[tokio::test]
+async fn test_decompress_points_packed() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    use curve25519_dalek_onchain::{edwards::ProjectiveNielsPoint, window::LookupTable};
+    let table_size = LookupTable::<ProjectiveNielsPoint>::TABLE_SIZE;
+
+    // more than one batch with a partial last one
+    let num_points = 2 * instruction::BATCH_DECOMPRESS_PER_CRANK as usize + 1;
+    let mut input = vec![];
+    for i in 0..num_points {
+        let point = curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT
+            * curve25519_dalek::scalar::Scalar::from(7919 * i as u64 + 3);
+        input.extend_from_slice(point.compress().as_bytes());
+    }
+
+    // compute buffer is laid out as
+    // [ ..header.., ..batch scratch.., ..batch tables.., ..slot.., ..tables.. ]
+    let offset = instruction::HEADER_SIZE;
+    let batch_tables_offset = offset + instruction::decompress_points_packed_scratch_len(num_points);
+    let slot_offset = batch_tables_offset + table_size * num_points;
+    let tables_offset = slot_offset + instruction::DECOMPRESS_SIZE;
+    let compute_buffer_len = tables_offset + table_size * num_points;
+
+    let mut dsl_instructions = instruction::decompress_points_packed(
+        num_points, offset, offset, batch_tables_offset);
+    // one Pow22501 chain per point with the init and fini steps of the 3
+    // batches packed
+    assert_eq!(dsl_instructions.len(), 1 + 3 * num_points + 2 * 3);
+    for i in 0..num_points {
+        dsl_instructions.extend_from_slice(
+            &instruction::decompress_point(
+                offset + 32 * i,
+                slot_offset.try_into().unwrap(),
+                tables_offset + table_size * i,
+            ),
+        );
+    }
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let (compute_buffer, result) = run_dsl(
+        &dsl, &input, compute_buffer_len, false, &payer, &mut banks_client, recent_blockhash).await;
+    assert_eq!(result, Ok(()));
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+    assert_eq!(
+        &account.data[batch_tables_offset..batch_tables_offset + table_size * num_points],
+        &account.data[tables_offset..tables_offset + table_size * num_points],
+    );
+
+    // an invalid encoding in the second batch fails the same way
+    let invalid = instruction::BATCH_DECOMPRESS_PER_CRANK as usize + 2;
+    input[32 * invalid] ^= 1;
+
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &instruction::decompress_points_packed(num_points, offset, offset, batch_tables_offset));
+    let (_, batch_result) = run_dsl(
+        &dsl, &input, batch_tables_offset + table_size * num_points, false,
+        &payer, &mut banks_client, recent_blockhash).await;
+
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &instruction::decompress_point(
+            offset + 32 * invalid,
+            offset.try_into().unwrap(),
+            offset + instruction::DECOMPRESS_SIZE,
+        ));
+    let (_, result) = run_dsl(
+        &dsl, &input, offset + instruction::DECOMPRESS_SIZE + table_size, false,
+        &payer, &mut banks_client, recent_blockhash).await;
+
+    assert_eq!(
+        batch_result,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(Curve25519Error::InvalidPoint as u32),
+        )),
+    );
+    assert_eq!(batch_result, result);
+}
+
+#[tokio::test]
+async fn test_batch_compress_edwards() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    use curve25519_dalek_onchain::backend::serial::u64::constants;
+    let num_points = 16;
+    let points: Vec<_> = (0..num_points)
+        .map(|i| &Scalar::from(7919 * i as u64 + 3) * &constants::ED25519_BASEPOINT_TABLE)
+        .collect();
+
+    let mut input = vec![];
+    for point in points.iter() {
+        input.extend_from_slice(&point.to_bytes());
+    }
+
+    let offset = instruction::HEADER_SIZE;
+    let scratch_space = offset;
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &instruction::batch_compress_edwards_instructions(num_points, offset, scratch_space));
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + input.len();
+    let compute_buffer_len = scratch_space + instruction::batch_compress_edwards_scratch_len(num_points);
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut instructions = vec![];
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+    for (i, chunk) in input.chunks(800).enumerate() {
+        instructions.push(
+            instruction::write_bytes(
+                input_buffer.pubkey(),
+                payer.pubkey(),
+                (instruction::HEADER_SIZE + 800 * i) as u32,
+                800 * i + chunk.len() == input.len(),
+                chunk,
+            ),
+        );
+    }
+    for instruction in instructions {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    for (i, point) in points.iter().enumerate() {
+        let compressed_offset = scratch_space + instruction::COMPRESS_EDWARDS_SIZE * i + 32 * 9;
+        let expected = curve25519_dalek::edwards::EdwardsPoint::from_bytes(&point.to_bytes()).compress();
+        assert_eq!(&account.data[compressed_offset..compressed_offset+32], expected.as_bytes());
+    }
+
+    // CompressEdwards only has steps 0 through 2
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &[
+            instruction::DSLInstruction::CompressEdwards(instruction::RunSplitComputeData{
+                offset: instruction::HEADER_SIZE as u32,
+                step: 3,
+            }),
+        ]);
+    let (_, result) = run_dsl(
+        &dsl, &[], instruction::HEADER_SIZE + instruction::COMPRESS_EDWARDS_SIZE, false,
+        &payer, &mut banks_client, recent_blockhash,
+    ).await;
+    assert_eq!(
+        result,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(Curve25519Error::InvalidStep as u32),
+        )),
+    );
+}
+
+#[tokio::test]
+async fn test_with_witness() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    let hash_bytes = [
+        0, 1, 2, 3, 4, 5, 6, 7,
+        0, 1, 2, 3, 4, 5, 6, 7,
+        0, 1, 2, 3, 4, 5, 6, 7,
+        0, 1, 2, 3, 4, 5, 6, 7,
+    ];
+
+    use curve25519_dalek_onchain::backend::serial::u64::constants;
+    let point = &Scalar::from(7919u64) * &constants::ED25519_BASEPOINT_TABLE;
+    let expected_point = curve25519_dalek::edwards::EdwardsPoint::from_bytes(&point.to_bytes());
+    let compressed = expected_point.compress();
+
+    // [ elligator input, decompress input, compress input, edwards elligator input ]
+    let elligator_input = instruction::elligator_witness_input(&hash_bytes);
+    let decompress_input = instruction::decompress_edwards_witness_input(compressed.as_bytes()).unwrap();
+    let compress_input = instruction::compress_edwards_witness_input(&point.to_bytes()).unwrap();
+    let mut input = vec![];
+    input.extend_from_slice(&elligator_input);
+    input.extend_from_slice(&decompress_input);
+    input.extend_from_slice(&compress_input);
+    input.extend_from_slice(&instruction::edwards_elligator_witness_input(&hash_bytes).unwrap());
+
+    let offset = instruction::HEADER_SIZE as u32;
+    let edwards_elligator_offset = offset + 288;
+    // with and without witnesses
+    let edwards_elligator_scratch = [offset + 32 * 18, offset + 32 * 29];
+    // MontgomeryElligatorWithWitness and MontgomeryElligator step 3
+    let rfc_elligator_scratch = [offset + 32 * 56, offset + 32 * 63];
+    let mut dsl_instructions = vec![];
+    dsl_instructions.extend_from_slice(
+        &instruction::elligator_to_curve_with_witness_instructions(offset, offset));
+    dsl_instructions.extend_from_slice(
+        &instruction::decompress_edwards_with_witness_instructions(offset + 64, offset + 32 * 6));
+    dsl_instructions.extend_from_slice(
+        &instruction::compress_edwards_with_witness_instructions(offset + 128, offset + 32 * 12));
+    dsl_instructions.extend_from_slice(
+        &instruction::edwards_elligator_to_curve_with_witness_instructions(
+            edwards_elligator_offset, edwards_elligator_scratch[0]));
+    dsl_instructions.extend_from_slice(
+        &instruction::edwards_elligator_to_curve_instructions(
+            edwards_elligator_offset, edwards_elligator_scratch[1]));
+    dsl_instructions.extend_from_slice(&[
+        // [ r_0, 1/(1+2r²), sqrt_ratio_i(eps, 1) ]
+        instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+            input_offset: edwards_elligator_offset,
+            compute_offset: rfc_elligator_scratch[0],
+            bytes: 32 * 3,
+        }),
+        instruction::DSLInstruction::MontgomeryElligatorWithWitness(instruction::RunSplitComputeData{
+            offset: rfc_elligator_scratch[0],
+            step: 3,
+        }),
+    ]);
+    // the steps before MontgomeryElligator step 2 are shared with the RFC 9380 map
+    dsl_instructions.extend_from_slice(
+        &instruction::edwards_elligator_to_curve_instructions(
+            edwards_elligator_offset, rfc_elligator_scratch[1])[..7]);
+    dsl_instructions.push(
+        instruction::DSLInstruction::MontgomeryElligator(instruction::RunSplitComputeData{
+            offset: rfc_elligator_scratch[1],
+            step: 3,
+        }),
+    );
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + input.len();
+    let compute_buffer_len = instruction::HEADER_SIZE + 32 * 79;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut instructions = vec![];
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &input,
+        ),
+    );
+    for instruction in instructions {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let elligator_idx = instruction::HEADER_SIZE + 32 * 2;
+    let elligator_result = curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[elligator_idx..elligator_idx+128]
+    );
+    assert_eq!(
+        elligator_result,
+        curve25519_dalek::ristretto::RistrettoPoint::elligator_ristretto_flavor(
+            &curve25519_dalek::field::FieldElement::from_bytes(&hash_bytes),
+        ).0,
+    );
+
+    let decompress_idx = instruction::HEADER_SIZE + 32 * 8;
+    let decompress_result = curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[decompress_idx..decompress_idx+128]
+    );
+    assert_eq!(decompress_result, expected_point);
+
+    let compress_idx = instruction::HEADER_SIZE + 32 * 17;
+    assert_eq!(&account.data[compress_idx..compress_idx+32], compressed.as_bytes());
+
+    let read_point = |idx: u32| curve25519_dalek::edwards::EdwardsPoint::from_bytes(
+        &account.data[idx as usize..idx as usize + 128]
+    );
+    assert_eq!(
+        read_point(edwards_elligator_scratch[0] + 32 * 7),
+        read_point(edwards_elligator_scratch[1] + 32 * 23),
+    );
+    assert_eq!(
+        read_point(rfc_elligator_scratch[0] + 32 * 3),
+        read_point(rfc_elligator_scratch[1] + 32 * 12),
+    );
+
+    // the program only checks the witnesses, so wrong ones must be rejected
+    use curve25519_dalek_onchain::field::FieldElement;
+    let read_element = |bytes: &[u8]| FieldElement::from_bytes(bytes.try_into().unwrap());
+
+    // wrong inverse
+    let mut bad_compress_input = compress_input;
+    let z_inv = read_element(&compress_input[128..]);
+    bad_compress_input[128..].copy_from_slice(&(&z_inv + &FieldElement::one()).to_bytes());
+
+    // the negative root
+    let mut bad_decompress_input = decompress_input;
+    let x = read_element(&decompress_input[32..]);
+    bad_decompress_input[32..].copy_from_slice(&(-&x).to_bytes());
+
+    // neither sqrt(u/v) nor sqrt(i*u/v)
+    let mut bad_elligator_input = elligator_input;
+    let mut s = &read_element(&elligator_input[32..]) + &FieldElement::one();
+    if s.is_negative().unwrap_u8() == 1 {
+        s = -&s;
+    }
+    bad_elligator_input[32..].copy_from_slice(&s.to_bytes());
+
+    let bad_witnesses: [(&[u8], [instruction::DSLInstruction; 2]); 3] = [
+        (
+            &bad_compress_input,
+            instruction::compress_edwards_with_witness_instructions(offset, offset),
+        ),
+        (
+            &bad_decompress_input,
+            instruction::decompress_edwards_with_witness_instructions(offset, offset),
+        ),
+        (
+            &bad_elligator_input,
+            instruction::elligator_to_curve_with_witness_instructions(offset, offset),
+        ),
+    ];
+    for (bad_input, bad_dsl) in bad_witnesses.iter() {
+        let (_, result) = run_dsl(
+            &instruction::dsl_instructions_to_bytes(bad_dsl),
+            bad_input,
+            instruction::HEADER_SIZE + 32 * 6,
+            false,
+            &payer, &mut banks_client, recent_blockhash,
+        ).await;
+        assert_eq!(
+            result,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(Curve25519Error::BadWitness as u32),
+            )),
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_soft_fail() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    // a y coordinate that isn't on the curve
+    let compressed = (2u8..).map(|y| {
+        let mut compressed = [0u8; 32];
+        compressed[0] = y;
+        compressed
+    }).find(|compressed| instruction::decompress_edwards_witness_input(compressed).is_none()).unwrap();
+
+    let offset = instruction::HEADER_SIZE as u32;
+    let dsl_instructions = instruction::decompress_edwards_instructions(offset, offset);
+    let dsl = instruction::dsl_instructions_to_bytes(&dsl_instructions);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 32;
+    let compute_buffer_len = instruction::HEADER_SIZE + 32 * 10;
+
+    let mut instructions = create_buffer_instructions(
+        &payer,
+        &rent,
+        &instruction_buffer,
+        instruction_buffer_len,
+        &input_buffer,
+        input_buffer_len,
+        &compute_buffer,
+        compute_buffer_len,
+        true,
+    ).to_vec();
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &compressed,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // the rejecting crank still commits
+    crank_dsl(
+        &dsl, 5, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+
+    let compute_header = instruction::ComputeHeader::deserialize(
+        &mut account.data.as_slice()).unwrap();
+    assert_eq!(compute_header.verdict, instruction::Verdict::Rejected);
+    assert!(compute_header.completed);
+    assert_eq!(compute_header.rejected_instruction, Some(dsl_instructions.len() as u32 - 1));
+
+    // further cranks fail with a decodable error
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            ComputeBudgetInstruction::request_units(1_000_000),
+            instruction::crank_compute_steps(
+                instruction_buffer.pubkey(),
+                input_buffer.pubkey(),
+                compute_buffer.pubkey(),
+                compute_header.instruction_num,
+                1,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(Curve25519Error::ComputeBufferCompleted as u32),
+        ),
+    );
+
+    // non-canonical scalars reject too
+    let load_scalar = |input_offset: u32| instruction::dsl_instructions_to_bytes(
+        &[
+            instruction::DSLInstruction::LoadScalar(instruction::LoadScalarData{
+                input_offset,
+                compute_offset: offset,
+            }),
+        ]);
+    let scalar_bytes = curve25519_dalek::constants::BASEPOINT_ORDER.to_bytes();
+    let (compute_buffer, result) = run_dsl(
+        &load_scalar(offset), &scalar_bytes, instruction::HEADER_SIZE + 32, true,
+        &payer, &mut banks_client, recent_blockhash,
+    ).await;
+    assert_eq!(result, Ok(()));
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+    let compute_header = instruction::ComputeHeader::deserialize(
+        &mut account.data.as_slice()).unwrap();
+    assert_eq!(compute_header.verdict, instruction::Verdict::Rejected);
+    assert!(compute_header.completed);
+    assert_eq!(compute_header.rejected_instruction, Some(0));
+
+    // as do failed asserts, which complete the buffer so later instructions
+    // never run
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &[
+            instruction::DSLInstruction::WriteEdwardsBasepoint(instruction::RunDecompressData{
+                offset,
+            }),
+            instruction::DSLInstruction::AssertIdentity(instruction::RunDecompressData{
+                offset,
+            }),
+            instruction::DSLInstruction::WriteEdwardsBasepoint(instruction::RunDecompressData{
+                offset,
+            }),
+        ]);
+    let (compute_buffer, result) = run_dsl(
+        &dsl, &[], instruction::HEADER_SIZE + 128, true,
+        &payer, &mut banks_client, recent_blockhash,
+    ).await;
+    assert_eq!(
+        result,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(Curve25519Error::ComputeBufferCompleted as u32),
+        )),
+    );
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+    let compute_header = instruction::ComputeHeader::deserialize(
+        &mut account.data.as_slice()).unwrap();
+    assert_eq!(compute_header.verdict, instruction::Verdict::Rejected);
+    assert!(compute_header.completed);
+    assert_eq!(compute_header.instruction_num, 2);
+    assert_eq!(compute_header.rejected_instruction, Some(1));
+
+    // but a bad instruction still fails the crank
+    let (compute_buffer, result) = run_dsl(
+        &load_scalar(0), &scalar_bytes, instruction::HEADER_SIZE + 32, true,
+        &payer, &mut banks_client, recent_blockhash,
+    ).await;
+    assert_eq!(
+        result,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(Curve25519Error::HeaderOffset as u32),
+        )),
+    );
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+    let compute_header = instruction::ComputeHeader::deserialize(
+        &mut account.data.as_slice()).unwrap();
+    assert_eq!(compute_header.verdict, instruction::Verdict::Pending);
+    assert!(!compute_header.completed);
+}
+
+// CPIs into VerifyComputeBuffer and fails unless the return data matches the
+// expected bytes in data[64..]
+fn process_verify_caller(
+    _program_id: &solana_sdk::pubkey::Pubkey,
+    accounts: &[solana_sdk::account_info::AccountInfo],
+    data: &[u8],
+) -> solana_sdk::entrypoint::ProgramResult {
+    let verify = instruction::verify_compute_buffer(
+        solana_sdk::pubkey::Pubkey::new(&data[..32]),
+        solana_sdk::pubkey::Pubkey::new(&data[32..64]),
+        *accounts[0].key,
+    );
+    solana_sdk::program::invoke(&verify, accounts)?;
+
+    match solana_sdk::program::get_return_data() {
+        Some((program_id, result)) if program_id == id() && result == data[64..] => Ok(()),
+        _ => Err(solana_sdk::program_error::ProgramError::InvalidArgument),
+    }
+}
+
+#[tokio::test]
+async fn test_verify_compute_buffer() {
+    let caller_id = solana_sdk::pubkey::Pubkey::new_unique();
+    let mut pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+    pc.add_program("verify_caller", caller_id, processor!(process_verify_caller));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    use curve25519_dalek::constants::{ED25519_BASEPOINT_POINT, EIGHT_TORSION};
+    let p = ED25519_BASEPOINT_POINT;
+    let p_torsion = p + EIGHT_TORSION[2];
+
+    let mut point_bytes = [0; 128 * 2];
+    point_bytes[..128].copy_from_slice(&p.to_bytes());
+    point_bytes[128..].copy_from_slice(&p_torsion.to_bytes());
+
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &[
+            instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+                input_offset: offset,
+                compute_offset: offset,
+                bytes: 128 * 2,
+            }),
+            instruction::DSLInstruction::AssertRistrettoEqual(instruction::AssertEqualData{
+                lhs_offset: offset,
+                rhs_offset: offset + 128,
+            }),
+        ]);
+
+    // over-allocated. The zero padding past the DSL must never run
+    let instruction_buffer_len = instruction::HEADER_SIZE + dsl.len() + 4 * instruction::INSTRUCTION_SIZE;
+    let input_buffer_len = instruction::HEADER_SIZE + 128 * 2;
+    let compute_buffer_len = instruction::HEADER_SIZE + 128 * 2;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &point_bytes,
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let verify_caller = |expected: &[u8]| {
+        let mut data = vec![];
+        data.extend_from_slice(&instruction_buffer.pubkey().to_bytes());
+        data.extend_from_slice(&input_buffer.pubkey().to_bytes());
+        data.extend_from_slice(expected);
+        Instruction {
+            program_id: caller_id,
+            accounts: vec![
+                solana_sdk::instruction::AccountMeta::new_readonly(compute_buffer.pubkey(), false),
+                solana_sdk::instruction::AccountMeta::new_readonly(id(), false),
+            ],
+            data,
+        }
+    };
+
+    // not cranked yet
+    let mut not_completed = vec![1];
+    not_completed.extend_from_slice(
+        &(Curve25519Error::ComputeBufferNotCompleted as u32).to_le_bytes());
+    let mut transaction = Transaction::new_with_payer(
+        &[verify_caller(&not_completed)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    crank_dsl(
+        &dsl, 10, &payer, &mut banks_client, recent_blockhash,
+        &instruction_buffer, &input_buffer, &compute_buffer,
+    ).await;
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+    let compute_header = instruction::ComputeHeader::deserialize(
+        &mut account.data.as_slice()).unwrap();
+    assert!(compute_header.completed);
+    assert_eq!(
+        compute_header.instruction_num as usize,
+        dsl.len() / instruction::INSTRUCTION_SIZE,
+    );
+    assert_eq!(compute_header.verdict, instruction::Verdict::Accepted);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[verify_caller(&[0])],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stale_crank() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let rent = banks_client.get_rent().await;
+    let rent = rent.unwrap();
+
+    let compute_buffer = Keypair::new();
+    let input_buffer = Keypair::new();
+    let instruction_buffer = Keypair::new();
+
+    let offset: u32 = instruction::HEADER_SIZE.try_into().unwrap();
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &[
+            instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+                input_offset: offset,
+                compute_offset: offset,
+                bytes: 32,
+            }),
+            instruction::DSLInstruction::CopyInput(instruction::CopyInputData{
+                input_offset: offset,
+                compute_offset: offset + 32,
+                bytes: 32,
+            }),
+        ]);
+
+    let instruction_buffer_len = (instruction::HEADER_SIZE + dsl.len()) as usize;
+    let input_buffer_len = instruction::HEADER_SIZE + 32;
+    let compute_buffer_len = instruction::HEADER_SIZE + 64;
+
+    let mut instructions = vec![];
+    instructions.extend_from_slice(
+        &create_buffer_instructions(
+            &payer,
+            &rent,
+            &instruction_buffer,
+            instruction_buffer_len,
+            &input_buffer,
+            input_buffer_len,
+            &compute_buffer,
+            compute_buffer_len,
+            false,
+        ),
+    );
+
+    write_dsl_instructions(&mut instructions, &dsl, &payer, &instruction_buffer);
+
+    instructions.push(
+        instruction::write_bytes(
+            input_buffer.pubkey(),
+            payer.pubkey(),
+            instruction::HEADER_SIZE as u32,
+            true,
+            &[1; 32],
+        ),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        instructions.as_slice(),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &instruction_buffer, &input_buffer, &compute_buffer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let crank = |instruction_num: u32, max_steps: u8| instruction::crank_compute_steps(
+        instruction_buffer.pubkey(),
+        input_buffer.pubkey(),
+        compute_buffer.pubkey(),
+        instruction_num,
+        max_steps,
+    );
+
+    // a truncated instruction_num doesn't skip the check
+    let mut truncated = crank(1, 1);
+    truncated.data.truncate(4);
+    let mut transaction = Transaction::new_with_payer(
+        &[truncated],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData),
+    );
+
+    // ahead, the first crank, then a retry of it and the second
+    for (instruction, ok) in [
+        (crank(1, 2), false),
+        (crank(0, 1), true),
+        (crank(0, 2), false),
+        (crank(1, 1), true),
+    ] {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        let result = banks_client.process_transaction(transaction).await;
+        if ok {
+            result.unwrap();
+        } else {
+            assert_eq!(
+                result.unwrap_err().unwrap(),
+                TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(Curve25519Error::MismatchedInstructionNum as u32),
+                ),
+            );
+        }
+    }
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+    let compute_header = instruction::ComputeHeader::deserialize(
+        &mut account.data.as_slice()).unwrap();
+    assert!(compute_header.completed);
+    assert_eq!(&account.data[offset as usize..offset as usize + 64], &[1; 64][..]);
+}
+
+#[tokio::test]
+async fn test_header_offset() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    let offset = instruction::HEADER_SIZE as u32;
+
+    // a result written over the header would forge the verdict
+    for write_header in [
+        instruction::DSLInstruction::MulByCofactor(instruction::BuildLookupTableData{
+            point_offset: offset,
+            table_offset: 0,
+        }),
+        instruction::DSLInstruction::EdwardsAdd(instruction::EdwardsBinaryOpData{
+            lhs_offset: offset,
+            rhs_offset: offset,
+            result_offset: 0,
+        }),
+    ] {
+        let dsl = instruction::dsl_instructions_to_bytes(
+            &[
+                instruction::DSLInstruction::WriteEdwardsBasepoint(instruction::RunDecompressData{
+                    offset,
+                }),
+                write_header,
+            ]);
+        let (compute_buffer, result) = run_dsl(
+            &dsl, &[], instruction::HEADER_SIZE + 128, false,
+            &payer, &mut banks_client, recent_blockhash,
+        ).await;
+        assert_eq!(
+            result,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(Curve25519Error::HeaderOffset as u32),
+            )),
+        );
+
+        let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+        let compute_header = instruction::ComputeHeader::deserialize(
+            &mut account.data.as_slice()).unwrap();
+        assert_eq!(compute_header.verdict, instruction::Verdict::Pending);
+        assert!(!compute_header.completed);
+        assert_eq!(compute_header.instruction_num, 1);
+    }
+}
+
+#[tokio::test]
+async fn test_assert_zero_point() {
+    let pc = ProgramTest::new("curve25519_dalek_onchain", id(), processor!(process_instruction));
+
+    let (mut banks_client, payer, recent_blockhash) = pc.start().await;
+
+    // (0:0:0:0) satisfies the projective equality check against any point so
+    // it must not pass as the identity
+    let offset = instruction::HEADER_SIZE as u32;
+    let dsl = instruction::dsl_instructions_to_bytes(
+        &[
+            instruction::DSLInstruction::AssertIdentity(instruction::RunDecompressData{
+                offset,
+            }),
+        ]);
+    let (compute_buffer, result) = run_dsl(
+        &dsl, &[], instruction::HEADER_SIZE + 128, false,
+        &payer, &mut banks_client, recent_blockhash,
+    ).await;
+    assert_eq!(result, Ok(()));
+
+    let account = banks_client.get_account(compute_buffer.pubkey()).await.unwrap().unwrap();
+    let compute_header = instruction::ComputeHeader::deserialize(
+        &mut account.data.as_slice()).unwrap();
+    assert_eq!(compute_header.verdict, instruction::Verdict::Rejected);
+    assert_eq!(compute_header.rejected_instruction, Some(0));
+}